@@ -1,12 +1,1049 @@
 //! CLI命令处理模块
 
-use crate::core::file_ops::{list_directory, list_directory_tree};
+use super::config::Config;
+use super::parser::{
+    ConflictArgs, CopyArgs, FilterArgs, FindArgs, GrepArgs, ListArgs, MoveArgs, RenameArgs, RmArgs,
+    StatArgs, TrashArgs, TrashCommand, TuiArgs, UndoArgs,
+};
+use colored::*;
+use file_manage::core::copy::{CopyOptions, CopyPlan, copy, plan_copy};
+use file_manage::core::filter::FilterOptions;
+use file_manage::core::grep::{GrepOptions, grep};
+use file_manage::core::journal::{Action, Journal};
+use file_manage::core::models::Entry;
+use file_manage::core::models::EntryError;
+use file_manage::core::move_ops::{
+    ConflictPolicy, MoveOutcome, move_path, move_targets, remove_all,
+};
+use file_manage::core::rename::{RenameOptions, apply_rename, plan_from_names, plan_rename};
+use file_manage::core::search::{FindOptions, find};
+use file_manage::core::stat::stat;
+use file_manage::core::trash::{Backup, Trash, TrashItem};
+use file_manage::display::formatter::{
+    DEFAULT_TREE_COLORS, ListLayout, OutputFormat, RenderOptions, SizeStyle, format_size,
+    write_grep_matches, write_listing, write_match_counts, write_paths, write_paths_null,
+    write_renames, write_stat, write_tree,
+};
+use file_manage::display::json::{JsonEntry, JsonStat, write_json, write_ndjson};
+use file_manage::display::progress::ProgressBar;
+use file_manage::display::styles::style_sheet;
+use file_manage::display::time::TimeStyle;
+use file_manage::{
+    EntryKind, FmgError, ListOptions, Result, SortOptions, TreeOptions, list, walk_tree,
+};
+use std::io::{self, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 
 /// 处理列表命令
-pub fn handle_list_command(args: &super::parser::ListArgs) {
-    if args.tree {
-        list_directory_tree(&args.path, args.long, args.depth.unwrap_or(usize::MAX), 0);
+pub fn handle_list_command(args: &ListArgs, config: &Config) -> Result<()> {
+    let sort = SortOptions {
+        key: args.sort,
+        reverse: args.reverse,
+        dirs_first: args.dirs_first,
+    };
+    let render = render_options(args, config);
+    let path = Path::new(&args.path);
+    let mut stdout = io::stdout().lock();
+
+    let (errors, walked) = if args.tree {
+        let options = TreeOptions {
+            max_depth: args.depth,
+            sort,
+            filter: filter_options(&args.filter),
+            du: args.du,
+            follow: args.follow,
+        };
+        let started = Instant::now();
+        let listing = walk_tree(path, &options)?;
+        let walked = (listing.tree.descendant_count(), started.elapsed());
+        let write_result = match args.format {
+            OutputFormat::Text => write_tree(&mut stdout, &listing.tree, &render),
+            OutputFormat::Json => write_json(
+                &mut stdout,
+                &JsonEntry::from_tree(&listing.tree)
+                    .children
+                    .unwrap_or_default(),
+            ),
+            OutputFormat::Ndjson => write_ndjson(
+                &mut stdout,
+                JsonEntry::from_tree(&listing.tree)
+                    .children
+                    .unwrap_or_default(),
+            ),
+        };
+        finish_output(write_result.and_then(|_| stdout.flush()))?;
+        (listing.errors, walked)
+    } else {
+        let options = ListOptions {
+            sort,
+            filter: filter_options(&args.filter),
+            du: args.du,
+            follow: args.follow,
+        };
+        let started = Instant::now();
+        let listing = list(path, &options)?;
+        let walked = (listing.entries.len(), started.elapsed());
+        let json_entries = || {
+            listing
+                .entries
+                .iter()
+                .map(JsonEntry::new)
+                .collect::<Vec<_>>()
+        };
+        let write_result = match args.format {
+            OutputFormat::Text => write_listing(&mut stdout, &listing.entries, &render),
+            OutputFormat::Json => write_json(&mut stdout, &json_entries()),
+            OutputFormat::Ndjson => write_ndjson(&mut stdout, json_entries()),
+        };
+        finish_output(write_result.and_then(|_| stdout.flush()))?;
+        (listing.errors, walked)
+    };
+
+    if args.stats {
+        let (entries, elapsed) = walked;
+        report_stats(entries, "entries", elapsed);
+    }
+    report_entry_errors(path, &errors)
+}
+
+/// 处理查找命令
+pub fn handle_find_command(args: &FindArgs, config: &Config) -> Result<()> {
+    let newer = match &args.newer {
+        Some(reference) => Some(
+            std::fs::metadata(reference)
+                .and_then(|metadata| metadata.modified())
+                .map_err(|e| FmgError::from_io(reference, e))?,
+        ),
+        None => None,
+    };
+    let options = FindOptions {
+        name: args.name.clone(),
+        regex: args.regex.clone(),
+        types: args.types.clone(),
+        sizes: args.sizes.clone(),
+        newer,
+        mtimes: args.mtimes.clone(),
+        max_depth: args.max_depth,
+        sort: SortOptions {
+            key: args.sort,
+            reverse: args.reverse,
+            dirs_first: false,
+        },
+        filter: filter_options(&args.filter),
+        follow: args.follow,
+    };
+    let path = Path::new(&args.path);
+    let result = find(path, &options)?;
+
+    let mut stdout = io::stdout().lock();
+    let write_result = match (args.format, args.tree) {
+        (OutputFormat::Text, true) => {
+            let render = RenderOptions {
+                tree_colors: tree_colors(config),
+                quoting: args.quoting_style,
+                ..RenderOptions::default()
+            };
+            write_tree(&mut stdout, &result.tree, &render)
+        }
+        (OutputFormat::Text, false) if args.print0 => {
+            write_paths_null(&mut stdout, &result.matches)
+        }
+        (OutputFormat::Text, false) => {
+            write_paths(&mut stdout, &result.matches, args.quoting_style)
+        }
+        (OutputFormat::Json, true) => write_json(
+            &mut stdout,
+            &JsonEntry::from_tree(&result.tree)
+                .children
+                .unwrap_or_default(),
+        ),
+        (OutputFormat::Ndjson, true) => write_ndjson(
+            &mut stdout,
+            JsonEntry::from_tree(&result.tree)
+                .children
+                .unwrap_or_default(),
+        ),
+        (OutputFormat::Json, false) => write_json(
+            &mut stdout,
+            &result
+                .matches
+                .iter()
+                .map(JsonEntry::new)
+                .collect::<Vec<_>>(),
+        ),
+        (OutputFormat::Ndjson, false) => write_ndjson(
+            &mut stdout,
+            result.matches.iter().map(JsonEntry::new).collect(),
+        ),
+    };
+    finish_output(write_result.and_then(|_| stdout.flush()))?;
+
+    report_entry_errors(path, &result.errors)
+}
+
+/// 处理详细元数据命令；多个路径中有无法读取的项时继续处理其余路径
+pub fn handle_stat_command(args: &StatArgs) -> Result<()> {
+    let mut stats = Vec::new();
+    let mut failed = 0;
+    for path in &args.paths {
+        match stat(path, args.follow) {
+            Ok(stat) => stats.push(stat),
+            Err(error) if args.paths.len() == 1 => return Err(error),
+            Err(error) => {
+                eprintln!("{} {}", "Error:".red().bold(), error);
+                failed += 1;
+            }
+        }
+    }
+
+    let mut stdout = io::stdout().lock();
+    let write_result = match args.format {
+        OutputFormat::Text => stats.iter().enumerate().try_for_each(|(i, stat)| {
+            if i > 0 {
+                writeln!(stdout)?;
+            }
+            write_stat(&mut stdout, stat, args.quoting_style)
+        }),
+        OutputFormat::Json => {
+            let json: Vec<_> = stats.iter().map(JsonStat::new).collect();
+            serde_json::to_writer_pretty(&mut stdout, &json)
+                .map_err(io::Error::from)
+                .and_then(|_| writeln!(stdout))
+        }
+        OutputFormat::Ndjson => stats.iter().try_for_each(|stat| {
+            serde_json::to_writer(&mut stdout, &JsonStat::new(stat))?;
+            writeln!(stdout)
+        }),
+    };
+    finish_output(write_result.and_then(|_| stdout.flush()))?;
+
+    if failed > 0 {
+        return Err(FmgError::PartialFailure {
+            action: "read",
+            failed,
+        });
+    }
+    Ok(())
+}
+
+/// 处理内容搜索命令
+pub fn handle_grep_command(args: &GrepArgs) -> Result<()> {
+    let context = args.context.unwrap_or(0);
+    let options = GrepOptions {
+        pattern: args.pattern.clone(),
+        ignore_case: args.ignore_case,
+        fixed_strings: args.fixed_strings,
+        before_context: args.before_context.unwrap_or(context),
+        after_context: args.after_context.unwrap_or(context),
+        name: args.name.clone(),
+        max_depth: args.max_depth,
+        sort: SortOptions::default(),
+        filter: filter_options(&args.filter),
+        follow: args.follow,
+    };
+    let path = Path::new(&args.path);
+    let started = Instant::now();
+    let result = grep(path, &options)?;
+    let elapsed = started.elapsed();
+
+    let mut stdout = io::stdout().lock();
+    let write_result = if args.files_with_matches {
+        let entries: Vec<_> = result.files.iter().map(|f| f.entry.clone()).collect();
+        write_paths(&mut stdout, &entries, args.quoting_style)
+    } else if args.count {
+        write_match_counts(&mut stdout, &result.files, args.quoting_style)
+    } else {
+        let separate_groups = options.before_context > 0 || options.after_context > 0;
+        write_grep_matches(
+            &mut stdout,
+            &result.files,
+            args.quoting_style,
+            separate_groups,
+        )
+    };
+    finish_output(write_result.and_then(|_| stdout.flush()))?;
+
+    if args.stats {
+        report_stats(result.searched, "files", elapsed);
+    }
+    report_entry_errors(path, &result.errors)
+}
+
+/// 处理复制命令
+pub fn handle_copy_command(args: &CopyArgs, config: &Config) -> Result<()> {
+    let options = CopyOptions {
+        preserve: args.preserve,
+        verify: args.verify,
+        resume: args.resume,
+        overwrite: args.overwrite,
+        follow: args.follow,
+        backup: Trash::from_env(),
+    };
+    let plan = plan_copy(&args.sources, &args.dest, &options)?;
+
+    if args.dry_run {
+        let mut stdout = io::stdout().lock();
+        let write_result = write_copy_plan(&mut stdout, &plan, config);
+        finish_output(write_result.and_then(|_| stdout.flush()))?;
+        return report_failures(&plan.errors, "read");
+    }
+
+    let mut progress = ProgressBar::new(0, !args.quiet && io::stderr().is_terminal());
+    let report = copy(&plan, &options, &mut progress);
+    progress.finish();
+
+    // 撤销时先删除新写入的内容，再放回被覆盖的原目标
+    let actions = report
+        .trashed
+        .iter()
+        .cloned()
+        .map(Action::trashed)
+        .chain(report.replaced.iter().map(|path| replaced(path)))
+        .chain(report.created.iter().map(|path| Action::created(path)))
+        .collect();
+    record("copy", actions);
+
+    if !args.quiet {
+        let mut summary = format!(
+            "Copied {} file{} ({})",
+            report.files,
+            if report.files == 1 { "" } else { "s" },
+            format_size(report.bytes, SizeStyle::Binary)
+        );
+        if report.resumed > 0 {
+            summary.push_str(&format!(
+                ", {} already present",
+                format_size(report.resumed, SizeStyle::Binary)
+            ));
+        }
+        println!("{}", summary.green());
+    }
+
+    let mut errors = plan.errors;
+    errors.extend(report.errors);
+    report_failures(&errors, "copied")
+}
+
+/// 处理移动命令
+pub fn handle_move_command(args: &MoveArgs) -> Result<()> {
+    let targets = move_targets(&args.sources, &args.dest)?;
+    let mut progress = ProgressBar::new(0, !args.quiet && io::stderr().is_terminal());
+    let (mut moved, mut copied, mut skipped) = (0, 0, 0);
+    let mut errors = Vec::new();
+    let mut actions = Vec::new();
+
+    for (source, target) in args.sources.iter().zip(targets) {
+        let existed = std::fs::symlink_metadata(&target).is_ok();
+        let conflict = if args.conflict.ask && existed {
+            progress.finish();
+            ask_conflict(&target)?
+        } else {
+            conflict_policy(&args.conflict)
+        };
+        let result = if existed && conflict == ConflictPolicy::Overwrite {
+            overwrite(&target, &mut actions, || {
+                move_path(source, &target, conflict, &mut progress)
+            })
+        } else {
+            move_path(source, &target, conflict, &mut progress)
+        };
+        let to = match result {
+            Ok(MoveOutcome::Renamed(to)) => to,
+            Ok(MoveOutcome::Copied(to)) => {
+                copied += 1;
+                to
+            }
+            Ok(MoveOutcome::Skipped) => {
+                skipped += 1;
+                continue;
+            }
+            Err(error) => {
+                errors.push(EntryError {
+                    path: source.clone(),
+                    error,
+                });
+                continue;
+            }
+        };
+        moved += 1;
+        actions.push(Action::moved(source, &to));
+    }
+    progress.finish();
+    record("move", actions);
+
+    if !args.quiet && (errors.is_empty() || moved + skipped > 0) {
+        let mut summary = format!("Moved {} item{}", moved, if moved == 1 { "" } else { "s" });
+        if copied > 0 {
+            summary.push_str(&format!(" ({} across filesystems)", copied));
+        }
+        if skipped > 0 {
+            summary.push_str(&format!(", skipped {}", skipped));
+        }
+        println!("{}", summary.green());
+    }
+    report_failures(&errors, "moved")
+}
+
+/// 处理批量重命名命令：先输出预览，确认后再执行
+pub fn handle_rename_command(args: &RenameArgs) -> Result<()> {
+    let entries = rename_entries(args)?;
+    let plan = if args.editor {
+        let names = edit_names(&entries)?;
+        plan_from_names(entries, names)?
+    } else {
+        let options = RenameOptions {
+            substitution: args.substitution.clone(),
+            template: args.template.clone(),
+            case: args.case,
+            start: args.start,
+        };
+        plan_rename(entries, &options)?
+    };
+    if plan.renames.is_empty() {
+        println!("Nothing to rename");
+        return Ok(());
+    }
+
+    let mut stdout = io::stdout().lock();
+    let write_result = write_renames(&mut stdout, &plan.renames, args.quoting_style);
+    finish_output(write_result.and_then(|_| stdout.flush()))?;
+    drop(stdout);
+    let cycles = plan.cycles();
+    if cycles > 0 {
+        let unit = if cycles == 1 { "cycle" } else { "cycles" };
+        println!(
+            "{}",
+            format!("{} {} will be broken with a temporary name", cycles, unit).dimmed()
+        );
+    }
+
+    let count = plan.renames.len();
+    let unit = if count == 1 { "item" } else { "items" };
+    if args.dry_run || (!args.yes && !confirm(&format!("Rename {} {}?", count, unit))?) {
+        return Ok(());
+    }
+    let mut actions = Vec::new();
+    let report = apply_rename(&plan, &mut |from, to| actions.push(Action::moved(from, to)));
+    record("rename", actions);
+    if report.renamed > 0 {
+        let unit = if report.renamed == 1 { "item" } else { "items" };
+        println!("{}", format!("Renamed {} {}", report.renamed, unit).green());
+    }
+    report_failures(&report.errors, "renamed")
+}
+
+/// 要重命名的目录项：文件按给定的顺序，目录替换为其中排序后的内容
+fn rename_entries(args: &RenameArgs) -> Result<Vec<Entry>> {
+    let options = ListOptions {
+        sort: SortOptions {
+            key: args.sort,
+            ..SortOptions::default()
+        },
+        filter: FilterOptions {
+            show_hidden: args.all,
+            ..FilterOptions::default()
+        },
+        ..ListOptions::default()
+    };
+    let mut entries = Vec::new();
+    for path in &args.paths {
+        let entry = Entry::from_path(path, 0).map_err(|e| FmgError::from_io(path, e))?;
+        if entry.kind != EntryKind::Dir {
+            entries.push(entry);
+            continue;
+        }
+        let listing = list(path, &options)?;
+        report_entry_errors(path, &listing.errors)?;
+        entries.extend(listing.entries);
+    }
+    Ok(entries)
+}
+
+/// 在 $VISUAL 或 $EDITOR 中编辑名称 (每行一个)，返回编辑后的名称
+fn edit_names(entries: &[Entry]) -> Result<Vec<String>> {
+    let mut contents = String::new();
+    for entry in entries {
+        match entry.name.to_str() {
+            Some(name) if !name.contains(['\n', '\r']) => {
+                contents.push_str(name);
+                contents.push('\n');
+            }
+            _ => {
+                return Err(FmgError::RenameConflict {
+                    path: entry.path.clone(),
+                    message: "names with newlines or invalid UTF-8 cannot be edited".to_string(),
+                });
+            }
+        }
+    }
+
+    // 以 0600 权限新建随机名称的文件，不跟随已有的符号链接，离开作用域 (包括出错与 panic) 时删除
+    let mut file = tempfile::Builder::new()
+        .prefix("fmg-rename-")
+        .suffix(".txt")
+        .tempfile()
+        .map_err(FmgError::from)?;
+    let path = file.path().to_path_buf();
+    file.write_all(contents.as_bytes())
+        .and_then(|_| file.flush())
+        .map_err(|e| FmgError::from_io(&path, e))?;
+    run_editor(&path)?;
+    // 编辑器可能以改名的方式保存，按路径重新读取
+    let edited = std::fs::read_to_string(&path).map_err(|e| FmgError::from_io(&path, e))?;
+
+    let names: Vec<String> = edited.lines().map(str::to_string).collect();
+    if names.len() != entries.len() {
+        return Err(FmgError::Io {
+            path: None,
+            source: io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "expected {} names but the edited file has {} lines; lines must not be added or removed",
+                    entries.len(),
+                    names.len()
+                ),
+            ),
+        });
+    }
+    Ok(names)
+}
+
+/// 用 $VISUAL、$EDITOR (可以带参数) 或 vi 打开文件并等待编辑器退出
+fn run_editor(file: &Path) -> Result<()> {
+    // 设置为空的变量视为未设置
+    let editor = ["VISUAL", "EDITOR"]
+        .iter()
+        .filter_map(|name| std::env::var(name).ok())
+        .find(|editor| !editor.trim().is_empty())
+        .unwrap_or_else(|| "vi".to_string());
+    let words = shell_words::split(&editor).map_err(|e| FmgError::Io {
+        path: None,
+        source: io::Error::new(io::ErrorKind::InvalidInput, format!("$EDITOR: {}", e)),
+    })?;
+    // 例如 EDITOR='#x' 经过 shell 分词后什么也不剩
+    let Some((program, editor_args)) = words.split_first() else {
+        return Err(FmgError::Io {
+            path: None,
+            source: io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "$EDITOR: the editor command is empty",
+            ),
+        });
+    };
+    let status = std::process::Command::new(program)
+        .args(editor_args)
+        .arg(file)
+        .status()
+        .map_err(|e| FmgError::from_io(Path::new(program), e))?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(FmgError::Io {
+            path: Some(PathBuf::from(program)),
+            source: io::Error::other(format!("editor exited with {}", status)),
+        })
+    }
+}
+
+/// 处理删除命令：默认移入回收站，--permanent 时确认后直接删除
+pub fn handle_rm_command(args: &RmArgs) -> Result<()> {
+    let count = args.paths.len();
+    let items = if count == 1 { "item" } else { "items" };
+    let mut errors = Vec::new();
+
+    if args.permanent {
+        if !args.yes && !confirm(&format!("Permanently delete {} {}?", count, items))? {
+            return Ok(());
+        }
+        let mut actions = Vec::new();
+        for path in &args.paths {
+            match remove_all(path) {
+                Ok(()) => {
+                    actions.push(std::path::absolute(path).map(|path| Action::Deleted { path }))
+                }
+                Err(error) => errors.push(EntryError {
+                    path: path.clone(),
+                    error,
+                }),
+            }
+        }
+        record("rm", actions);
+        if errors.len() < count {
+            let deleted = count - errors.len();
+            let unit = if deleted == 1 { "item" } else { "items" };
+            println!("{}", format!("Deleted {} {}", deleted, unit).green());
+        }
+        return report_failures(&errors, "deleted");
+    }
+
+    let trash = home_trash()?;
+    let mut actions = Vec::new();
+    for path in &args.paths {
+        match trash.put(path) {
+            Ok(item) => actions.push(Action::trashed(item)),
+            Err(error) => errors.push(EntryError {
+                path: path.clone(),
+                error,
+            }),
+        }
+    }
+    record("rm", actions);
+    if errors.len() < count {
+        let trashed = count - errors.len();
+        let unit = if trashed == 1 { "item" } else { "items" };
+        println!(
+            "{}",
+            format!("Moved {} {} to the trash", trashed, unit).green()
+        );
+    }
+    report_failures(&errors, "moved to the trash")
+}
+
+/// 处理回收站命令
+pub fn handle_trash_command(args: &TrashArgs) -> Result<()> {
+    let trash = home_trash()?;
+    let items = trash.list()?;
+
+    match &args.command {
+        TrashCommand::List => {
+            let mut stdout = io::stdout().lock();
+            let write_result = items.iter().try_for_each(|item| {
+                writeln!(
+                    stdout,
+                    "{}  {}",
+                    item.deleted.replace('T', " ").dimmed(),
+                    item.original.display()
+                )
+            });
+            finish_output(write_result.and_then(|_| stdout.flush()))
+        }
+        TrashCommand::Restore {
+            items: wanted,
+            conflict,
+        } => {
+            let mut restored = 0;
+            let mut used = Vec::new();
+            let mut errors = Vec::new();
+            let mut actions = Vec::new();
+            for wanted in wanted {
+                let Some(item) = find_trashed(&items, wanted, &used) else {
+                    errors.push(EntryError {
+                        path: wanted.clone(),
+                        error: io::Error::new(io::ErrorKind::NotFound, "not in the trash"),
+                    });
+                    continue;
+                };
+                used.push(item.info_path());
+                let existed = std::fs::symlink_metadata(&item.original).is_ok();
+                let policy = if conflict.ask && existed {
+                    ask_conflict(&item.original)?
+                } else {
+                    conflict_policy(conflict)
+                };
+                let result = if existed && policy == ConflictPolicy::Overwrite {
+                    overwrite(&item.original, &mut actions, || trash.restore(item, policy))
+                } else {
+                    trash.restore(item, policy)
+                };
+                match result {
+                    Ok(MoveOutcome::Skipped) => {}
+                    Ok(MoveOutcome::Renamed(to) | MoveOutcome::Copied(to)) => {
+                        actions.push(Action::restored(&to));
+                        restored += 1;
+                    }
+                    Err(error) => errors.push(EntryError {
+                        path: item.original.clone(),
+                        error,
+                    }),
+                }
+            }
+            record("trash restore", actions);
+            if restored > 0 {
+                let unit = if restored == 1 { "item" } else { "items" };
+                println!("{}", format!("Restored {} {}", restored, unit).green());
+            }
+            report_failures(&errors, "restored")
+        }
+        TrashCommand::Empty { yes } => {
+            if items.is_empty() {
+                println!("The trash is empty");
+                return Ok(());
+            }
+            let unit = if items.len() == 1 { "item" } else { "items" };
+            let prompt = format!("Permanently delete {} {} in the trash?", items.len(), unit);
+            if !yes && !confirm(&prompt)? {
+                return Ok(());
+            }
+            let mut errors = Vec::new();
+            let mut actions = Vec::new();
+            for item in &items {
+                match trash.purge(item) {
+                    Ok(()) => actions.push(Ok(Action::Deleted {
+                        path: item.original.clone(),
+                    })),
+                    Err(error) => errors.push(EntryError {
+                        path: item.file_path(),
+                        error,
+                    }),
+                }
+            }
+            record("trash empty", actions);
+            report_failures(&errors, "deleted")
+        }
+    }
+}
+
+/// 处理撤销命令：从最近的操作开始依次撤销
+pub fn handle_undo_command(args: &UndoArgs) -> Result<()> {
+    let journal = journal()?;
+    let trash = home_trash()?;
+    for _ in 0..args.count {
+        let Some(operation) = journal.undo(&trash)? else {
+            println!("Nothing to undo");
+            break;
+        };
+        let count = operation.actions.len();
+        println!(
+            "{}",
+            format!(
+                "Undid #{} {} ({} action{})",
+                operation.id,
+                operation.command,
+                count,
+                if count == 1 { "" } else { "s" }
+            )
+            .green()
+        );
+    }
+    Ok(())
+}
+
+/// 处理历史命令：按执行顺序列出记录的操作
+pub fn handle_history_command() -> Result<()> {
+    let operations = journal()?.history()?;
+    let mut stdout = io::stdout().lock();
+    let write_result = operations.iter().try_for_each(|operation| {
+        let header = format!(
+            "#{:<4} {}  {}",
+            operation.id, operation.time, operation.command
+        );
+        if operation.undone {
+            writeln!(stdout, "{}", format!("{} (undone)", header).dimmed())?;
+        } else if !operation.is_reversible() {
+            writeln!(
+                stdout,
+                "{}",
+                format!("{} (cannot be undone)", header).dimmed()
+            )?;
+        } else {
+            writeln!(stdout, "{}", header.bold())?;
+        }
+        for action in &operation.actions {
+            writeln!(stdout, "      {}", action.describe())?;
+        }
+        Ok(())
+    });
+    finish_output(write_result.and_then(|_| stdout.flush()))
+}
+
+/// 撤销日志
+fn journal() -> Result<Journal> {
+    Journal::from_env().ok_or_else(|| FmgError::Io {
+        path: None,
+        source: io::Error::new(
+            io::ErrorKind::NotFound,
+            "cannot locate the journal: neither XDG_STATE_HOME nor HOME is set",
+        ),
+    })
+}
+
+/// 将操作写入撤销日志；无法记录时只输出警告，不影响已完成的操作
+fn record(command: &str, actions: Vec<io::Result<Action>>) {
+    let result = actions
+        .into_iter()
+        .collect::<io::Result<Vec<_>>>()
+        .map_err(FmgError::from)
+        .and_then(|actions| journal()?.record(command, actions));
+    if let Err(error) = result {
+        eprintln!(
+            "{} the operation could not be recorded for undo: {}",
+            "Warning:".yellow().bold(),
+            error
+        );
+    }
+}
+
+/// 覆盖已存在的 path 的动作
+fn replaced(path: &Path) -> io::Result<Action> {
+    std::path::absolute(path).map(|path| Action::Replaced { path })
+}
+
+/// 覆盖已存在的 target：place 以重命名直接替换目标，原目标经硬链接备份后移入回收站，使覆盖可以撤销
+///
+/// 无法备份或找不到回收站时照常覆盖，并记录为无法恢复。
+fn overwrite<T>(
+    target: &Path,
+    actions: &mut Vec<io::Result<Action>>,
+    place: impl FnOnce() -> io::Result<T>,
+) -> io::Result<T> {
+    let backup = Backup::link(target);
+    let value = match place() {
+        Ok(value) => value,
+        Err(e) => {
+            if let Some(backup) = backup {
+                backup.discard();
+            }
+            return Err(e);
+        }
+    };
+    let item = match (backup, Trash::from_env()) {
+        (Some(backup), Some(trash)) => backup.keep(&trash),
+        (Some(backup), None) => {
+            backup.discard();
+            None
+        }
+        (None, _) => None,
+    };
+    actions.push(match item {
+        Some(item) => Action::trashed(item),
+        None => replaced(target),
+    });
+    Ok(value)
+}
+
+/// 处理交互式界面命令
+pub fn handle_tui_command(args: &TuiArgs) -> Result<()> {
+    crate::tui::run(&args.path, filter_options(&args.filter))
+}
+
+/// 当前用户的主回收站
+fn home_trash() -> Result<Trash> {
+    Trash::from_env().ok_or_else(|| FmgError::Io {
+        path: None,
+        source: io::Error::new(
+            io::ErrorKind::NotFound,
+            "cannot locate the trash: neither XDG_DATA_HOME nor HOME is set",
+        ),
+    })
+}
+
+/// 按原路径或回收站中的名称查找项目，同名时取最近删除且尚未恢复的一项
+fn find_trashed<'a>(
+    items: &'a [TrashItem],
+    wanted: &Path,
+    used: &[PathBuf],
+) -> Option<&'a TrashItem> {
+    let original = std::path::absolute(wanted).ok();
+    items.iter().rev().find(|item| {
+        (Some(&item.original) == original.as_ref() || item.name == wanted.as_os_str())
+            && !used.contains(&item.info_path())
+    })
+}
+
+/// 询问是否继续，只有输入 y 或 yes 时才返回 true
+fn confirm(prompt: &str) -> Result<bool> {
+    eprint!("{} [y/N] ", prompt);
+    let mut answer = String::new();
+    if io::stdin().read_line(&mut answer)? == 0 {
+        return Ok(false);
+    }
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
+/// 将冲突参数转换为处理策略 (--ask 在询问后才确定)
+fn conflict_policy(args: &ConflictArgs) -> ConflictPolicy {
+    if args.overwrite {
+        ConflictPolicy::Overwrite
+    } else if args.skip {
+        ConflictPolicy::Skip
+    } else if args.rename {
+        ConflictPolicy::Rename
+    } else {
+        ConflictPolicy::Fail
+    }
+}
+
+/// 询问如何处理已存在的目标，输入结束时视为跳过
+fn ask_conflict(target: &Path) -> Result<ConflictPolicy> {
+    loop {
+        eprint!(
+            "'{}' already exists. [o]verwrite, [s]kip or [r]ename? ",
+            target.display()
+        );
+        let mut answer = String::new();
+        if io::stdin().read_line(&mut answer)? == 0 {
+            return Ok(ConflictPolicy::Skip);
+        }
+        match answer.trim().to_lowercase().as_str() {
+            "o" | "overwrite" => return Ok(ConflictPolicy::Overwrite),
+            "s" | "skip" | "" => return Ok(ConflictPolicy::Skip),
+            "r" | "rename" => return Ok(ConflictPolicy::Rename),
+            _ => continue,
+        }
+    }
+}
+
+/// 以树形结构输出复制计划
+fn write_copy_plan<W: Write>(writer: &mut W, plan: &CopyPlan, config: &Config) -> io::Result<()> {
+    let render = RenderOptions {
+        size_style: SizeStyle::Binary,
+        show_size: true,
+        tree_colors: tree_colors(config),
+        ..RenderOptions::default()
+    };
+    let sheet = style_sheet();
+    for job in &plan.jobs {
+        let source = &job.source.entry;
+        writeln!(
+            writer,
+            "{} -> {}",
+            sheet.paint(source, &source.path.display().to_string()),
+            job.target.display()
+        )?;
+        if job.source.children.is_some() {
+            write_tree(writer, &job.source, &render)?;
+        }
+    }
+    writeln!(
+        writer,
+        "Would copy {} file{} ({})",
+        plan.files,
+        if plan.files == 1 { "" } else { "s" },
+        format_size(plan.total_bytes, SizeStyle::Binary)
+    )
+}
+
+/// 输出逐项操作中的错误，存在错误时返回 PartialFailure
+fn report_failures(errors: &[EntryError], action: &'static str) -> Result<()> {
+    for error in errors {
+        eprintln!("{} {}", "Error:".red().bold(), error);
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(FmgError::PartialFailure {
+            action,
+            failed: errors.len(),
+        })
+    }
+}
+
+/// 向标准错误输出处理的项目数量与速度
+fn report_stats(count: usize, unit: &str, elapsed: Duration) {
+    let secs = elapsed.as_secs_f64();
+    let rate = if secs > 0.0 { count as f64 / secs } else { 0.0 };
+    eprintln!(
+        "{} {} {} in {:.3}s ({:.0} {}/s)",
+        "Stats:".cyan().bold(),
+        count,
+        unit,
+        secs,
+        rate,
+        unit
+    );
+}
+
+/// 将命令行显示参数与配置中的样式转换为文本输出选项
+fn render_options(args: &ListArgs, config: &Config) -> RenderOptions {
+    let size_style = if args.si {
+        SizeStyle::Si
+    } else if args.human {
+        SizeStyle::Binary
+    } else {
+        SizeStyle::Bytes
+    };
+    RenderOptions {
+        long: args.long,
+        size_style,
+        show_size: args.du,
+        // 不带 --du 时树中目录的大小不含其内容，合计没有意义
+        show_total: if args.tree {
+            args.du
+        } else {
+            args.long || args.du
+        },
+        // --time-style 优先于配置中的 date-format
+        time_style: args
+            .time_style
+            .clone()
+            .or_else(|| config.date_format.clone().map(TimeStyle::Format))
+            .unwrap_or_default(),
+        time_field: args.time,
+        tree_colors: tree_colors(config),
+        quoting: args.quoting_style,
+        layout: list_layout(args),
+        width: terminal_width(),
+    }
+}
+
+/// 列表的排列方式，未指定时在终端中分多列、重定向时每行一项
+fn list_layout(args: &ListArgs) -> ListLayout {
+    if args.columns {
+        ListLayout::Columns
+    } else if args.across {
+        ListLayout::Across
+    } else if args.one_per_line || !io::stdout().is_terminal() {
+        ListLayout::SingleColumn
+    } else {
+        ListLayout::Columns
+    }
+}
+
+/// 终端宽度：优先使用 COLUMNS 环境变量，无法确定时为 80
+fn terminal_width() -> usize {
+    std::env::var("COLUMNS")
+        .ok()
+        .and_then(|columns| columns.parse().ok())
+        .filter(|&columns| columns > 0)
+        .or_else(|| {
+            ratatui::crossterm::terminal::size()
+                .ok()
+                .map(|(columns, _)| usize::from(columns))
+        })
+        .unwrap_or(80)
+}
+
+/// 配置中的树形连接线颜色，未配置时使用默认颜色
+fn tree_colors(config: &Config) -> Vec<Color> {
+    config
+        .theme
+        .tree_colors
+        .clone()
+        .unwrap_or_else(|| DEFAULT_TREE_COLORS.to_vec())
+}
+
+/// 将命令行过滤参数转换为过滤选项
+fn filter_options(args: &FilterArgs) -> FilterOptions {
+    FilterOptions {
+        show_hidden: args.all || args.almost_all,
+        dot_entries: args.all,
+        ignore: args.ignore.clone(),
+        gitignore: args.gitignore,
+    }
+}
+
+/// 处理输出结果，下游管道提前关闭 (如 `fmg ls | head`) 不视为错误
+fn finish_output(result: io::Result<()>) -> Result<()> {
+    match result {
+        Err(e) if e.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+        other => other.map_err(FmgError::from),
+    }
+}
+
+/// 输出遍历过程中遇到的单个目录项错误，存在错误时返回 PartialRead
+fn report_entry_errors(path: &Path, errors: &[EntryError]) -> Result<()> {
+    for error in errors {
+        eprintln!("{} Failed to read {}", "Error:".red().bold(), error);
+    }
+
+    if errors.is_empty() {
+        Ok(())
     } else {
-        list_directory(&args.path, args.long);
+        Err(FmgError::PartialRead {
+            path: path.to_path_buf(),
+            failed: errors.len(),
+        })
     }
 }
@@ -0,0 +1,499 @@
+//! 配置文件模块
+//!
+//! 配置按以下优先级合并：内置默认值 < 配置文件 < 命令行参数。配置文件默认为
+//! `$XDG_CONFIG_HOME/fmg/config.toml`，可由 `FMG_CONFIG` 或 `--config` 指定。
+
+use super::parser::{Cli, Commands, FilterArgs, ListArgs};
+use clap::parser::ValueSource;
+use clap::{ArgMatches, CommandFactory, ValueEnum};
+use colored::Color;
+use file_manage::core::dirs;
+use file_manage::core::sort::SortKey;
+use file_manage::display::formatter::OutputFormat;
+use file_manage::display::quoting::QuotingStyle;
+use file_manage::display::styles::{ColorChoice, StyleSheet};
+use file_manage::display::time::{TimeField, TimeStyle};
+use file_manage::{FmgError, Result};
+use serde::{Deserialize, Deserializer};
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// 配置文件的内容，所有字段均可省略
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Config {
+    /// 配置文件路径，未读取配置文件时为 None
+    #[serde(skip)]
+    pub source: Option<PathBuf>,
+    /// 是否显示 Logo
+    pub logo: Option<bool>,
+    /// 何时着色
+    #[serde(deserialize_with = "value_enum")]
+    pub color: Option<ColorChoice>,
+    /// 长格式中时间的 strftime 格式，例如 `%Y-%m-%d %H:%M`
+    pub date_format: Option<String>,
+    /// list 命令的默认参数
+    pub list: ListDefaults,
+    /// 配色
+    pub theme: Theme,
+    /// 命令别名，例如 `ll = "list -l"`
+    pub aliases: HashMap<String, String>,
+}
+
+/// list 命令的默认参数，含义与同名命令行参数一致
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct ListDefaults {
+    pub long: bool,
+    pub tree: bool,
+    pub depth: Option<usize>,
+    #[serde(deserialize_with = "value_enum")]
+    pub sort: Option<SortKey>,
+    pub reverse: bool,
+    pub dirs_first: bool,
+    #[serde(deserialize_with = "value_enum")]
+    pub format: Option<OutputFormat>,
+    #[serde(deserialize_with = "value_enum")]
+    pub quoting_style: Option<QuotingStyle>,
+    #[serde(deserialize_with = "parsed")]
+    pub time_style: Option<TimeStyle>,
+    #[serde(deserialize_with = "value_enum")]
+    pub time: Option<TimeField>,
+    pub human: bool,
+    pub si: bool,
+    pub du: bool,
+    pub follow: bool,
+    pub all: bool,
+    pub almost_all: bool,
+    pub ignore: Vec<String>,
+    pub gitignore: bool,
+}
+
+/// 配色设置
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Theme {
+    /// LS_COLORS 格式的配色，优先于环境变量
+    pub ls_colors: Option<String>,
+    /// dircolors 数据库文件，相对路径相对于配置文件所在目录
+    pub dircolors: Option<PathBuf>,
+    /// 树形连接线按深度循环使用的颜色，空列表表示不着色
+    #[serde(deserialize_with = "colors")]
+    pub tree_colors: Option<Vec<Color>>,
+}
+
+/// 按命令行参数名 (忽略大小写) 解析枚举值
+fn value_enum<'de, D, T>(deserializer: D) -> std::result::Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: ValueEnum,
+{
+    match Option::<String>::deserialize(deserializer)? {
+        Some(value) => T::from_str(&value, true)
+            .map(Some)
+            .map_err(serde::de::Error::custom),
+        None => Ok(None),
+    }
+}
+
+/// 按命令行参数的语法解析值，例如 `time-style = "+%d.%m.%Y"`
+fn parsed<'de, D, T>(deserializer: D) -> std::result::Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromStr<Err = String>,
+{
+    match Option::<String>::deserialize(deserializer)? {
+        Some(value) => value.parse().map(Some).map_err(serde::de::Error::custom),
+        None => Ok(None),
+    }
+}
+
+/// 解析颜色名称列表，例如 `["blue", "bright green"]`
+fn colors<'de, D>(deserializer: D) -> std::result::Result<Option<Vec<Color>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let Some(names) = Option::<Vec<String>>::deserialize(deserializer)? else {
+        return Ok(None);
+    };
+    names
+        .iter()
+        .map(|name| {
+            name.parse::<Color>()
+                .map_err(|_| serde::de::Error::custom(format!("unknown color '{}'", name)))
+        })
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .map(Some)
+}
+
+impl Config {
+    /// 读取配置文件；默认位置的文件不存在时使用空配置
+    pub fn load(args: &[OsString]) -> Result<Self> {
+        let (path, required) = match explicit_path(args) {
+            Some(path) => (path, true),
+            None => match dirs::config_dir() {
+                Some(dir) => (dir.join("fmg").join("config.toml"), false),
+                None => return Ok(Self::default()),
+            },
+        };
+
+        let text = match std::fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(e) if !required && e.kind() == io::ErrorKind::NotFound => {
+                return Ok(Self::default());
+            }
+            Err(e) => return Err(FmgError::from_io(&path, e)),
+        };
+        let mut config = Self::parse(&path, &text)?;
+
+        if let Some(dircolors) = &config.theme.dircolors {
+            let base = path.parent().unwrap_or(Path::new("."));
+            config.theme.dircolors = Some(resolve_path(base, dircolors));
+        }
+        config.source = Some(path);
+        Ok(config)
+    }
+
+    /// 解析配置文件内容，错误信息中带有行号
+    fn parse(path: &Path, text: &str) -> Result<Self> {
+        toml::from_str(text).map_err(|e| {
+            let line = e
+                .span()
+                .map(|span| text[..span.start].lines().count().max(1));
+            FmgError::InvalidConfig {
+                path: path.to_path_buf(),
+                message: match line {
+                    Some(line) => format!("line {}: {}", line, e.message()),
+                    None => e.message().to_string(),
+                },
+            }
+        })
+    }
+
+    /// 展开命令别名：第一个非选项参数是别名 (且不是内置命令) 时替换为别名的定义
+    pub fn expand_alias(&self, mut args: Vec<OsString>) -> Result<Vec<OsString>> {
+        let Some(index) = command_index(&args) else {
+            return Ok(args);
+        };
+        let Some(name) = args[index].to_str() else {
+            return Ok(args);
+        };
+        let Some(definition) = self.aliases.get(name) else {
+            return Ok(args);
+        };
+        let is_builtin = Cli::command()
+            .get_subcommands()
+            .any(|sub| sub.get_name() == name || sub.get_all_aliases().any(|a| a == name));
+        if is_builtin {
+            return Ok(args);
+        }
+
+        let words = shell_words::split(definition).map_err(|e| FmgError::InvalidConfig {
+            path: self.source.clone().unwrap_or_default(),
+            message: format!("alias '{}': {}", name, e),
+        })?;
+        args.splice(index..=index, words.into_iter().map(OsString::from));
+        Ok(args)
+    }
+
+    /// 将配置作为默认值合并到命令行参数中，命令行中显式给出的参数优先
+    pub fn apply(&self, cli: &mut Cli, matches: &ArgMatches) {
+        if let Some(color) = self.color
+            && !from_command_line(matches, "color")
+        {
+            cli.color = color;
+        }
+        if self.logo == Some(false) {
+            cli.no_logo = true;
+        }
+
+        match cli.command.get_or_insert_with(Commands::default) {
+            Commands::List(args) => {
+                if let Some(matches) = matches.subcommand_matches("list") {
+                    self.apply_list(args, matches);
+                }
+            }
+            // 查找、搜索与 TUI 只沿用 [list] 中的过滤设置
+            Commands::Find(args) => self.apply_filter(&mut args.filter),
+            Commands::Grep(args) => self.apply_filter(&mut args.filter),
+            Commands::Tui(args) => self.apply_filter(&mut args.filter),
+            Commands::Stat(_)
+            | Commands::Copy(_)
+            | Commands::Move(_)
+            | Commands::Rename(_)
+            | Commands::Rm(_)
+            | Commands::Trash(_)
+            | Commands::Undo(_)
+            | Commands::History => {}
+        }
+    }
+
+    /// 合并 list 命令的默认参数
+    fn apply_list(&self, args: &mut ListArgs, matches: &ArgMatches) {
+        let defaults = &self.list;
+        // 开关与其 --no-* 形式互相覆盖，命令行中后给出的一个生效
+        args.long |= defaults.long && !args.no_long;
+        args.tree |= defaults.tree && !args.no_tree;
+        args.reverse |= defaults.reverse && !args.no_reverse;
+        args.dirs_first |= defaults.dirs_first && !args.no_dirs_first;
+        args.human |= defaults.human && !args.no_human;
+        args.si |= defaults.si && !args.no_si;
+        args.du |= defaults.du && !args.no_du;
+        args.follow |= defaults.follow && !args.no_follow;
+        self.apply_filter(&mut args.filter);
+
+        if args.tree && args.depth.is_none() {
+            args.depth = defaults.depth;
+        }
+        if let Some(sort) = defaults.sort
+            && !from_command_line(matches, "sort")
+        {
+            args.sort = sort;
+        }
+        if let Some(format) = defaults.format
+            && !from_command_line(matches, "format")
+        {
+            args.format = format;
+        }
+        if let Some(quoting_style) = defaults.quoting_style
+            && !from_command_line(matches, "quoting_style")
+        {
+            args.quoting_style = quoting_style;
+        }
+        if args.time_style.is_none() {
+            args.time_style = defaults.time_style.clone();
+        }
+        if let Some(time) = defaults.time
+            && !from_command_line(matches, "time")
+        {
+            args.time = time;
+        }
+    }
+
+    /// 合并各命令共用的过滤参数
+    fn apply_filter(&self, filter: &mut FilterArgs) {
+        let defaults = &self.list;
+        filter.gitignore |= defaults.gitignore && !filter.no_gitignore;
+
+        // -a 与 -A 互斥，命令行给出其中之一 (或 --no-all) 时忽略配置中的设置
+        if !filter.all && !filter.almost_all && !filter.no_all {
+            filter.all = defaults.all;
+            filter.almost_all = defaults.almost_all && !defaults.all;
+        }
+
+        let mut ignore = defaults.ignore.clone();
+        ignore.append(&mut filter.ignore);
+        filter.ignore = ignore;
+    }
+
+    /// 配置中指定的样式表，未配置时返回 None
+    pub fn style_sheet(&self) -> Result<Option<StyleSheet>> {
+        if let Some(spec) = &self.theme.ls_colors {
+            return Ok(Some(StyleSheet::from_ls_colors(spec)));
+        }
+        match &self.theme.dircolors {
+            Some(path) => {
+                let database =
+                    std::fs::read_to_string(path).map_err(|e| FmgError::from_io(path, e))?;
+                Ok(Some(StyleSheet::from_dircolors(&database)))
+            }
+            None => Ok(None),
+        }
+    }
+}
+
+/// 参数是否在命令行中显式给出
+fn from_command_line(matches: &ArgMatches, id: &str) -> bool {
+    matches.value_source(id) == Some(ValueSource::CommandLine)
+}
+
+/// `--config` 或 `FMG_CONFIG` 指定的配置文件
+///
+/// 配置需要在解析命令行之前读取 (别名会改变参数)，因此这里直接扫描原始参数。
+fn explicit_path(args: &[OsString]) -> Option<PathBuf> {
+    let mut iter = args.iter().skip(1);
+    while let Some(arg) = iter.next() {
+        if arg == "--" {
+            break;
+        }
+        if arg == "--config" {
+            return iter.next().map(PathBuf::from);
+        }
+        if let Some(path) = arg.to_str().and_then(|a| a.strip_prefix("--config=")) {
+            return Some(PathBuf::from(path));
+        }
+    }
+    std::env::var_os("FMG_CONFIG")
+        .filter(|path| !path.is_empty())
+        .map(PathBuf::from)
+}
+
+/// 第一个非选项参数 (即子命令) 的位置，跳过带值的全局选项
+fn command_index(args: &[OsString]) -> Option<usize> {
+    let mut index = 1;
+    while index < args.len() {
+        let arg = &args[index];
+        if arg == "--color" || arg == "--config" {
+            index += 2;
+        } else if arg.to_str().is_some_and(|a| a.starts_with('-')) {
+            index += 1;
+        } else {
+            return Some(index);
+        }
+    }
+    None
+}
+
+/// 展开 `~/` 并将相对路径解析到 base 下
+fn resolve_path(base: &Path, path: &Path) -> PathBuf {
+    if let Ok(rest) = path.strip_prefix("~")
+        && let Some(home) = dirs::home_dir()
+    {
+        return home.join(rest);
+    }
+    base.join(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::FromArgMatches;
+
+    fn parse(text: &str) -> Result<Config> {
+        Config::parse(Path::new("config.toml"), text)
+    }
+
+    fn args(list: &[&str]) -> Vec<OsString> {
+        list.iter().map(OsString::from).collect()
+    }
+
+    /// 按 main 中的顺序展开别名、解析命令行并合并配置
+    fn cli_with(config: &Config, argv: &[&str]) -> Cli {
+        let argv = config.expand_alias(args(argv)).unwrap();
+        let matches = Cli::command().try_get_matches_from(argv).unwrap();
+        let mut cli = Cli::from_arg_matches(&matches).unwrap();
+        config.apply(&mut cli, &matches);
+        cli
+    }
+
+    #[test]
+    fn test_parse_config() {
+        let config = parse(
+            r#"
+            logo = false
+            color = "never"
+            date-format = "%F"
+
+            [list]
+            sort = "size"
+            dirs-first = true
+            ignore = ["target"]
+            time-style = "+%d.%m.%Y"
+            time = "ctime"
+
+            [theme]
+            tree-colors = ["blue", "bright green"]
+
+            [aliases]
+            ll = "list -l"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.logo, Some(false));
+        assert_eq!(config.color, Some(ColorChoice::Never));
+        assert_eq!(config.list.sort, Some(SortKey::Size));
+        assert_eq!(
+            config.list.time_style,
+            Some(TimeStyle::Format("%d.%m.%Y".to_string()))
+        );
+        assert_eq!(config.list.time, Some(TimeField::Ctime));
+        assert_eq!(
+            config.theme.tree_colors,
+            Some(vec![Color::Blue, Color::BrightGreen])
+        );
+        assert_eq!(config.aliases["ll"], "list -l");
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let error = parse("[list]\nsort = \"sideways\"\n").unwrap_err();
+        assert!(error.to_string().contains("line 2"), "{}", error);
+        assert!(parse("colour = \"never\"").is_err());
+        assert!(parse("[list]\ntime-style = \"%Y\"").is_err());
+        assert!(parse("[theme]\ntree-colors = [\"plaid\"]").is_err());
+    }
+
+    #[test]
+    fn test_command_line_takes_precedence() {
+        let config =
+            parse("color = \"always\"\n[list]\nsort = \"size\"\nlong = true\nignore = [\"*.o\"]\n")
+                .unwrap();
+
+        let cli = cli_with(&config, &["fmg", "list", "-I", "*.tmp"]);
+        assert_eq!(cli.color, ColorChoice::Always);
+        let Some(Commands::List(list)) = &cli.command else {
+            panic!("expected list command");
+        };
+        assert_eq!(list.sort, SortKey::Size);
+        assert!(list.long);
+        assert_eq!(list.filter.ignore, ["*.o", "*.tmp"]);
+
+        let cli = cli_with(
+            &config,
+            &["fmg", "--color", "never", "ls", "--sort", "name"],
+        );
+        assert_eq!(cli.color, ColorChoice::Never);
+        let Some(Commands::List(list)) = &cli.command else {
+            panic!("expected list command");
+        };
+        assert_eq!(list.sort, SortKey::Name);
+
+        // --no-* 关闭配置中打开的开关，与原开关之间后给出的生效
+        let config = parse("[list]\ntree = true\nlong = true\nall = true\n").unwrap();
+        let cli = cli_with(
+            &config,
+            &["fmg", "list", "--no-tree", "--no-long", "--no-all"],
+        );
+        let Some(Commands::List(list)) = &cli.command else {
+            panic!("expected list command");
+        };
+        assert!(!list.tree && !list.long);
+        assert!(!list.filter.all && !list.filter.almost_all);
+
+        let cli = cli_with(&config, &["fmg", "list", "--no-long", "-l", "-A"]);
+        let Some(Commands::List(list)) = &cli.command else {
+            panic!("expected list command");
+        };
+        assert!(list.long && list.tree);
+        assert!(list.filter.almost_all && !list.filter.all);
+    }
+
+    #[test]
+    fn test_expand_alias() {
+        let config =
+            parse("[aliases]\nlt = \"list --tree --depth 2\"\nls = \"list -l\"\n").unwrap();
+
+        let cli = cli_with(&config, &["fmg", "--no-logo", "lt", "src"]);
+        let Some(Commands::List(list)) = &cli.command else {
+            panic!("expected list command");
+        };
+        assert!(list.tree);
+        assert_eq!(list.depth, Some(2));
+        assert_eq!(list.path, "src");
+
+        // 内置命令与别名同名时不展开
+        let expanded = config.expand_alias(args(&["fmg", "ls"])).unwrap();
+        assert_eq!(expanded, args(&["fmg", "ls"]));
+    }
+
+    #[test]
+    fn test_explicit_path() {
+        let path = explicit_path(&args(&["fmg", "--config", "a.toml", "ls"]));
+        assert_eq!(path, Some(PathBuf::from("a.toml")));
+        let path = explicit_path(&args(&["fmg", "ls", "--config=b.toml"]));
+        assert_eq!(path, Some(PathBuf::from("b.toml")));
+    }
+}
@@ -1,4 +1,5 @@
 //! CLI模块 - 处理命令行接口相关功能
 
 pub mod commands;
+pub mod config;
 pub mod parser;
@@ -1,14 +1,35 @@
 //! CLI参数解析模块
 
-use clap::{Args, Parser, Subcommand};
+use clap::{ArgAction, ArgGroup, Args, Parser, Subcommand};
+use file_manage::core::rename::{CaseConversion, Substitution, Template};
+use file_manage::core::search::{AgeFilter, SizeFilter, TypeFilter};
+use file_manage::core::sort::SortKey;
+use file_manage::display::formatter::OutputFormat;
+use file_manage::display::quoting::QuotingStyle;
+use file_manage::display::styles::ColorChoice;
+use file_manage::display::time::{TimeField, TimeStyle};
+use std::path::PathBuf;
 
 /// Rust Terminal File Manager
 #[derive(Parser)]
 #[command(name = "fmg")]
 #[command(author, version, about, long_about = None)]
 pub struct Cli {
+    /// 没有子命令时进入 TUI
     #[command(subcommand)]
-    pub command: Commands,
+    pub command: Option<Commands>,
+
+    /// When to use colors
+    #[arg(long, value_enum, value_name = "WHEN", global = true, default_value_t = ColorChoice::Auto)]
+    pub color: ColorChoice,
+
+    /// Do not print the banner
+    #[arg(long, global = true)]
+    pub no_logo: bool,
+
+    /// Read settings from FILE instead of $XDG_CONFIG_HOME/fmg/config.toml (also FMG_CONFIG)
+    #[arg(long, global = true, value_name = "FILE")]
+    pub config: Option<PathBuf>,
 }
 
 #[derive(Subcommand)]
@@ -16,9 +37,40 @@ pub enum Commands {
     /// List directory contents
     #[command(alias = "ls")]
     List(ListArgs),
+    /// Search a directory tree for entries matching name, type, size and time filters
+    Find(FindArgs),
+    /// Search file contents for a regular expression
+    Grep(GrepArgs),
+    /// Show detailed metadata: inode, blocks, precise times, MIME type and xattrs
+    Stat(StatArgs),
+    /// Copy files and directory trees
+    #[command(alias = "cp")]
+    Copy(CopyArgs),
+    /// Move or rename files and directories, across filesystems if needed
+    #[command(alias = "mv")]
+    Move(MoveArgs),
+    /// Rename many files at once with a regex, a template or in $EDITOR
+    Rename(RenameArgs),
+    /// Move files and directories to the trash
+    Rm(RmArgs),
+    /// List, restore or empty the trash
+    Trash(TrashArgs),
+    /// Undo the most recent file operations
+    Undo(UndoArgs),
+    /// Show the recorded file operations that can be undone
+    History,
+    /// Browse interactively in a full-screen interface (the default without a subcommand)
+    Tui(TuiArgs),
+}
+
+impl Default for Commands {
+    fn default() -> Self {
+        Commands::Tui(TuiArgs::default())
+    }
 }
 
 #[derive(Args)]
+#[command(disable_help_flag = true)] // -h 用于可读的大小，与 ls 一致
 pub struct ListArgs {
     /// Path to the directory to list
     #[arg(default_value = ".", value_parser)]
@@ -32,7 +84,479 @@ pub struct ListArgs {
     #[arg(short, long)]
     pub tree: bool,
 
+    /// List entries in columns, top to bottom (the default on a terminal)
+    #[arg(short = 'C', overrides_with_all = ["across", "one_per_line"])]
+    pub columns: bool,
+
+    /// List entries in columns, left to right
+    #[arg(short = 'x', overrides_with_all = ["columns", "one_per_line"])]
+    pub across: bool,
+
+    /// List one entry per line (the default when output is not a terminal)
+    #[arg(short = '1', overrides_with_all = ["columns", "across"])]
+    pub one_per_line: bool,
+
     /// Limit tree depth
     #[arg(short = 'D', long, requires = "tree")] // Changed short flag to 'D' to avoid conflict
     pub depth: Option<usize>,
+
+    /// Sort entries by the given key
+    #[arg(long, value_enum, default_value_t = SortKey::Name)]
+    pub sort: SortKey,
+
+    /// Reverse the sort order
+    #[arg(short, long)]
+    pub reverse: bool,
+
+    /// List directories before files
+    #[arg(long)]
+    pub dirs_first: bool,
+
+    /// Output format
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    pub format: OutputFormat,
+
+    /// Print sizes in powers of 1024 (e.g. 1.5 KiB, 23 MiB)
+    #[arg(short = 'h', long)]
+    pub human: bool,
+
+    /// Print sizes in powers of 1000 (e.g. 1.5 kB, 23 MB)
+    #[arg(long)]
+    pub si: bool,
+
+    /// Show the recursive size of directories and a total
+    #[arg(long)]
+    pub du: bool,
+
+    /// Follow symbolic links (show and descend into their targets)
+    #[arg(short = 'L', long)]
+    pub follow: bool,
+
+    /// How to show times in the long listing: iso, long-iso, full, relative or +FORMAT (strftime)
+    #[arg(long, value_name = "STYLE")]
+    pub time_style: Option<TimeStyle>,
+
+    /// Which timestamp to show in the long listing
+    #[arg(long, value_enum, value_name = "WORD", default_value_t = TimeField::Mtime)]
+    pub time: TimeField,
+
+    /// How to display names containing spaces, control characters or invalid UTF-8
+    #[arg(long, value_enum, value_name = "STYLE", default_value_t = QuotingStyle::Literal)]
+    pub quoting_style: QuotingStyle,
+
+    /// Print the number of entries walked and entries/sec to stderr
+    #[arg(long)]
+    pub stats: bool,
+
+    /// Turn off -l (e.g. when the config file enables it)
+    #[arg(long, overrides_with = "long")]
+    pub no_long: bool,
+
+    /// Turn off --tree
+    #[arg(long, overrides_with = "tree")]
+    pub no_tree: bool,
+
+    /// Turn off --reverse
+    #[arg(long, overrides_with = "reverse")]
+    pub no_reverse: bool,
+
+    /// Turn off --dirs-first
+    #[arg(long, overrides_with = "dirs_first")]
+    pub no_dirs_first: bool,
+
+    /// Turn off --human
+    #[arg(long, overrides_with = "human")]
+    pub no_human: bool,
+
+    /// Turn off --si
+    #[arg(long, overrides_with = "si")]
+    pub no_si: bool,
+
+    /// Turn off --du
+    #[arg(long, overrides_with = "du")]
+    pub no_du: bool,
+
+    /// Turn off --follow
+    #[arg(long, overrides_with = "follow")]
+    pub no_follow: bool,
+
+    #[command(flatten)]
+    pub filter: FilterArgs,
+
+    /// Print help
+    #[arg(long, action = ArgAction::Help)]
+    pub help: Option<bool>,
+}
+
+#[derive(Args)]
+pub struct FindArgs {
+    /// Directory to search
+    #[arg(default_value = ".", value_parser)]
+    pub path: String,
+
+    /// Match file names against a glob (e.g. '*.rs')
+    #[arg(long, value_name = "GLOB")]
+    pub name: Option<String>,
+
+    /// Match file names against a regular expression
+    #[arg(long, value_name = "REGEX")]
+    pub regex: Option<String>,
+
+    /// Match entries of the given type (repeatable)
+    #[arg(long = "type", value_enum, value_name = "TYPE")]
+    pub types: Vec<TypeFilter>,
+
+    /// Match sizes: +N larger, -N smaller, N exactly; units c, k, M, G, T (repeatable)
+    #[arg(long = "size", value_name = "SIZE", allow_hyphen_values = true)]
+    pub sizes: Vec<SizeFilter>,
+
+    /// Match entries modified more recently than FILE
+    #[arg(long, value_name = "FILE")]
+    pub newer: Option<PathBuf>,
+
+    /// Match modification age: -7d within, +7d older than; units s, m, h, d, w (repeatable)
+    #[arg(long = "mtime", value_name = "AGE", allow_hyphen_values = true)]
+    pub mtimes: Vec<AgeFilter>,
+
+    /// Descend at most N levels (1 searches only the direct children)
+    #[arg(long, value_name = "N")]
+    pub max_depth: Option<usize>,
+
+    /// Print the matching paths as a tree, keeping their parent directories
+    #[arg(short, long, conflicts_with = "print0")]
+    pub tree: bool,
+
+    /// Separate paths with NUL instead of newline (for xargs -0)
+    #[arg(short = '0', long, conflicts_with = "format")]
+    pub print0: bool,
+
+    /// Sort entries by the given key
+    #[arg(long, value_enum, default_value_t = SortKey::Name)]
+    pub sort: SortKey,
+
+    /// Reverse the sort order
+    #[arg(short, long)]
+    pub reverse: bool,
+
+    /// Output format
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    pub format: OutputFormat,
+
+    /// Follow symbolic links (match and descend into their targets)
+    #[arg(short = 'L', long)]
+    pub follow: bool,
+
+    /// How to display names containing spaces, control characters or invalid UTF-8
+    #[arg(long, value_enum, value_name = "STYLE", default_value_t = QuotingStyle::Literal)]
+    pub quoting_style: QuotingStyle,
+
+    #[command(flatten)]
+    pub filter: FilterArgs,
+}
+
+#[derive(Args)]
+pub struct GrepArgs {
+    /// Regular expression to search for
+    pub pattern: String,
+
+    /// Directory or file to search
+    #[arg(default_value = ".", value_parser)]
+    pub path: String,
+
+    /// Match case-insensitively
+    #[arg(short, long)]
+    pub ignore_case: bool,
+
+    /// Treat the pattern as a literal string instead of a regular expression
+    #[arg(short = 'F', long)]
+    pub fixed_strings: bool,
+
+    /// Show N lines before and after each match
+    #[arg(short = 'C', long, value_name = "N")]
+    pub context: Option<usize>,
+
+    /// Show N lines before each match (-B; -A is --almost-all here, as in list)
+    #[arg(short = 'B', long, value_name = "N")]
+    pub before_context: Option<usize>,
+
+    /// Show N lines after each match
+    #[arg(long, value_name = "N")]
+    pub after_context: Option<usize>,
+
+    /// Only print the paths of files with matches
+    #[arg(short = 'l', long, conflicts_with = "count")]
+    pub files_with_matches: bool,
+
+    /// Only print the number of matching lines per file
+    #[arg(short, long)]
+    pub count: bool,
+
+    /// Only search files whose name matches the glob (e.g. '*.log')
+    #[arg(long, value_name = "GLOB")]
+    pub name: Option<String>,
+
+    /// Descend at most N levels (1 searches only the files in PATH)
+    #[arg(long, value_name = "N")]
+    pub max_depth: Option<usize>,
+
+    /// Follow symbolic links (search their targets and descend into them)
+    #[arg(short = 'L', long)]
+    pub follow: bool,
+
+    /// How to display names containing spaces, control characters or invalid UTF-8
+    #[arg(long, value_enum, value_name = "STYLE", default_value_t = QuotingStyle::Literal)]
+    pub quoting_style: QuotingStyle,
+
+    /// Print the number of files searched and files/sec to stderr
+    #[arg(long)]
+    pub stats: bool,
+
+    #[command(flatten)]
+    pub filter: FilterArgs,
+}
+
+#[derive(Args)]
+pub struct CopyArgs {
+    /// Files or directories to copy
+    #[arg(required = true, value_name = "SOURCE")]
+    pub sources: Vec<PathBuf>,
+
+    /// Destination path, or an existing directory to copy into
+    #[arg(value_name = "DEST")]
+    pub dest: PathBuf,
+
+    /// Preserve mode, ownership, timestamps and extended attributes
+    #[arg(short, long)]
+    pub preserve: bool,
+
+    /// Compare SHA-256 checksums of each file after copying
+    #[arg(long)]
+    pub verify: bool,
+
+    /// Continue partially copied files and skip files that are already complete
+    #[arg(long)]
+    pub resume: bool,
+
+    /// Replace existing destination files
+    #[arg(short = 'f', long)]
+    pub overwrite: bool,
+
+    /// Follow symbolic links and copy their targets instead of the links
+    #[arg(short = 'L', long)]
+    pub follow: bool,
+
+    /// Print what would be copied as a tree, without copying
+    #[arg(short = 'n', long)]
+    pub dry_run: bool,
+
+    /// Do not show the progress bar or the summary
+    #[arg(short, long)]
+    pub quiet: bool,
+}
+
+#[derive(Args)]
+pub struct MoveArgs {
+    /// Files or directories to move
+    #[arg(required = true, value_name = "SOURCE")]
+    pub sources: Vec<PathBuf>,
+
+    /// Destination path, or an existing directory to move into
+    #[arg(value_name = "DEST")]
+    pub dest: PathBuf,
+
+    #[command(flatten)]
+    pub conflict: ConflictArgs,
+
+    /// Do not show the progress bar or the summary
+    #[arg(short, long)]
+    pub quiet: bool,
+}
+
+#[derive(Args)]
+#[command(group(
+    ArgGroup::new("rule")
+        .required(true)
+        .multiple(true)
+        .args(["substitution", "template", "case", "editor"])
+))]
+pub struct RenameArgs {
+    /// Files to rename; a directory stands for the entries inside it
+    #[arg(required = true, value_name = "PATH")]
+    pub paths: Vec<PathBuf>,
+
+    /// Regex substitution such as 's/IMG_(\d+)/photo-$1/' (flags: g all matches, i ignore case)
+    #[arg(short = 'e', long = "expr", value_name = "s/PATTERN/REPLACEMENT/")]
+    pub substitution: Option<Substitution>,
+
+    /// Template for the new name: {name}, {ext}, {n}, {n:03}, {date}, {date:%Y%m%d}
+    #[arg(short, long)]
+    pub template: Option<Template>,
+
+    /// Convert the case of the new name
+    #[arg(long, value_enum)]
+    pub case: Option<CaseConversion>,
+
+    /// First value of the {n} counter
+    #[arg(long, default_value_t = 1)]
+    pub start: u64,
+
+    /// Edit the names in $VISUAL or $EDITOR, one per line
+    #[arg(long, conflicts_with_all = ["substitution", "template", "case"])]
+    pub editor: bool,
+
+    /// Include hidden entries of directories
+    #[arg(short, long)]
+    pub all: bool,
+
+    /// Order in which entries of directories are numbered
+    #[arg(long, value_enum, default_value_t = SortKey::Version)]
+    pub sort: SortKey,
+
+    /// Show the preview without renaming
+    #[arg(short = 'n', long)]
+    pub dry_run: bool,
+
+    /// Rename without asking for confirmation
+    #[arg(short, long)]
+    pub yes: bool,
+
+    /// How to display names containing spaces, control characters or invalid UTF-8
+    #[arg(long, value_enum, value_name = "STYLE", default_value_t = QuotingStyle::Literal)]
+    pub quoting_style: QuotingStyle,
+}
+
+#[derive(Args)]
+pub struct RmArgs {
+    /// Files or directories to remove
+    #[arg(required = true, value_name = "PATH")]
+    pub paths: Vec<PathBuf>,
+
+    /// Delete permanently instead of moving to the trash
+    #[arg(long)]
+    pub permanent: bool,
+
+    /// Do not ask for confirmation before deleting permanently
+    #[arg(short, long, requires = "permanent")]
+    pub yes: bool,
+}
+
+#[derive(Args)]
+pub struct TrashArgs {
+    #[command(subcommand)]
+    pub command: TrashCommand,
+}
+
+#[derive(Subcommand)]
+pub enum TrashCommand {
+    /// List trashed items with their deletion dates and original paths
+    List,
+    /// Move trashed items back to their original location
+    Restore {
+        /// Original paths or names in the trash; the most recently deleted match is restored
+        #[arg(required = true, value_name = "ITEM")]
+        items: Vec<PathBuf>,
+
+        #[command(flatten)]
+        conflict: ConflictArgs,
+    },
+    /// Permanently delete everything in the trash
+    Empty {
+        /// Do not ask for confirmation
+        #[arg(short, long)]
+        yes: bool,
+    },
+}
+
+#[derive(Args)]
+pub struct StatArgs {
+    /// Files or directories to inspect
+    #[arg(required = true)]
+    pub paths: Vec<PathBuf>,
+
+    /// Follow symbolic links and show their targets
+    #[arg(short = 'L', long)]
+    pub follow: bool,
+
+    /// Output format
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    pub format: OutputFormat,
+
+    /// How to display names containing spaces, control characters or invalid UTF-8
+    #[arg(long, value_enum, value_name = "STYLE", default_value_t = QuotingStyle::Literal)]
+    pub quoting_style: QuotingStyle,
+}
+
+#[derive(Args)]
+pub struct UndoArgs {
+    /// Number of operations to undo, newest first
+    #[arg(default_value_t = 1, value_parser = clap::value_parser!(u64).range(1..))]
+    pub count: u64,
+}
+
+/// 目标已存在时的处理方式，最多指定一个
+#[derive(Args)]
+#[group(multiple = false)]
+pub struct ConflictArgs {
+    /// Replace existing destinations
+    #[arg(short = 'f', long)]
+    pub overwrite: bool,
+
+    /// Leave existing destinations alone and skip those sources
+    #[arg(long)]
+    pub skip: bool,
+
+    /// Move to a free name such as 'name (1).ext' instead
+    #[arg(long)]
+    pub rename: bool,
+
+    /// Ask what to do for each existing destination
+    #[arg(short = 'i', long)]
+    pub ask: bool,
+}
+
+#[derive(Args)]
+pub struct TuiArgs {
+    /// Directory to start in
+    #[arg(default_value = ".")]
+    pub path: PathBuf,
+
+    #[command(flatten)]
+    pub filter: FilterArgs,
+}
+
+impl Default for TuiArgs {
+    fn default() -> Self {
+        Self {
+            path: PathBuf::from("."),
+            filter: FilterArgs::default(),
+        }
+    }
+}
+
+/// 各命令共用的过滤参数
+#[derive(Args, Default)]
+pub struct FilterArgs {
+    /// Show hidden entries, including . and ..
+    #[arg(short = 'a', long)]
+    pub all: bool,
+
+    /// Show hidden entries, except . and ..
+    #[arg(short = 'A', long, conflicts_with = "all")]
+    pub almost_all: bool,
+
+    /// Skip entries whose name matches the glob (repeatable)
+    #[arg(short = 'I', long, value_name = "GLOB")]
+    pub ignore: Vec<String>,
+
+    /// Respect .gitignore, .ignore and global git excludes
+    #[arg(long)]
+    pub gitignore: bool,
+
+    /// Hide hidden entries (turns off -a and -A, e.g. when the config file enables them)
+    #[arg(long, overrides_with_all = ["all", "almost_all"])]
+    pub no_all: bool,
+
+    /// Turn off --gitignore
+    #[arg(long, overrides_with = "gitignore")]
+    pub no_gitignore: bool,
 }
@@ -0,0 +1,811 @@
+//! 复制模块：复制文件与目录树，可保留属性、校验内容并续传未完成的文件
+//!
+//! 复制分为两步：[`plan_copy`] 遍历源路径得到目录树与总字节数 (用于进度显示与
+//! `--dry-run`)，[`copy`] 再按计划逐个复制。
+
+use crate::core::error::{FmgError, Result};
+use crate::core::file_ops::{TreeOptions, walk_tree};
+use crate::core::filter::FilterOptions;
+use crate::core::models::{DirTree, Entry, EntryError, EntryKind};
+use crate::core::trash::{Backup, Trash, TrashItem};
+use sha2::{Digest, Sha256};
+use std::ffi::OsString;
+use std::fs::{self, File, FileTimes, Metadata};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+/// 读写缓冲区大小
+const BUFFER_SIZE: usize = 1 << 20;
+
+/// 续传前比较的已复制部分末尾的字节数
+const RESUME_CHECK_LEN: u64 = 64 * 1024;
+
+/// 复制选项
+#[derive(Debug, Clone, Default)]
+pub struct CopyOptions {
+    /// 保留权限、属主、时间戳与扩展属性
+    pub preserve: bool,
+    /// 复制后比较源文件与目标文件的 SHA-256
+    pub verify: bool,
+    /// 目标文件是源文件的前缀时从断点继续复制，已完整的文件不再复制；其他已存在的目标按 overwrite 处理
+    pub resume: bool,
+    /// 覆盖已存在的目标文件
+    pub overwrite: bool,
+    /// 跟随源中的符号链接，复制链接目标而不是链接本身
+    pub follow: bool,
+    /// 覆盖已存在的目标后将其备份移入该回收站，使覆盖可以撤销
+    pub backup: Option<Trash>,
+}
+
+/// 一个复制任务：源 (文件或目录树) 与目标路径
+#[derive(Debug)]
+pub struct CopyJob {
+    pub source: DirTree,
+    pub target: PathBuf,
+}
+
+/// 复制计划
+#[derive(Debug)]
+pub struct CopyPlan {
+    pub jobs: Vec<CopyJob>,
+    /// 需要复制的文件数
+    pub files: usize,
+    /// 需要复制的总字节数
+    pub total_bytes: u64,
+    /// 遍历源目录时读取失败的目录项
+    pub errors: Vec<EntryError>,
+}
+
+/// 复制结果
+#[derive(Debug, Default)]
+pub struct CopyReport {
+    /// 复制完成的文件数
+    pub files: usize,
+    /// 实际写入的字节数
+    pub bytes: u64,
+    /// 续传时沿用的已复制字节数
+    pub resumed: u64,
+    /// 新写入的目标：新建的目录只记录目录本身，合并到已有目录时记录其中写入的每一项
+    pub created: Vec<PathBuf>,
+    /// 被覆盖且无法恢复的已有目标 (没有指定备份回收站或无法备份)
+    pub replaced: Vec<PathBuf>,
+    /// 被覆盖后其备份已移入回收站的已有目标
+    pub trashed: Vec<TrashItem>,
+    /// 复制失败的目录项
+    pub errors: Vec<EntryError>,
+}
+
+/// 复制进度的接收者
+pub trait CopyProgress {
+    /// 开始按计划复制，total 为需要复制的总字节数
+    fn begin(&mut self, total: u64);
+    /// 从 entry 写入了 bytes 字节
+    fn copied(&mut self, entry: &Entry, bytes: u64);
+}
+
+/// 闭包只接收写入的字节数
+impl<F: FnMut(&Entry, u64)> CopyProgress for F {
+    fn begin(&mut self, _total: u64) {}
+
+    fn copied(&mut self, entry: &Entry, bytes: u64) {
+        self(entry, bytes)
+    }
+}
+
+/// 规划复制：dest 为已存在的目录时复制到其中，否则 dest 即为目标路径 (只允许一个源)
+pub fn plan_copy<P: AsRef<Path>>(
+    sources: &[P],
+    dest: &Path,
+    options: &CopyOptions,
+) -> Result<CopyPlan> {
+    let targets = resolve_targets(sources, dest)?;
+    let mut plan = CopyPlan {
+        jobs: Vec::new(),
+        files: 0,
+        total_bytes: 0,
+        errors: Vec::new(),
+    };
+    for (source, target) in sources.iter().zip(targets) {
+        let source = source.as_ref();
+        let mut entry = Entry::from_path(source, 0).map_err(|e| FmgError::from_io(source, e))?;
+        if options.follow {
+            entry = entry.follow();
+        }
+
+        let tree = if entry.kind == EntryKind::Dir {
+            check_not_inside(source, &target).map_err(|e| FmgError::from_io(&target, e))?;
+            let tree_options = TreeOptions {
+                filter: FilterOptions {
+                    show_hidden: true,
+                    ..FilterOptions::default()
+                },
+                follow: options.follow,
+                ..TreeOptions::default()
+            };
+            let listing = walk_tree(source, &tree_options)?;
+            plan.errors.extend(listing.errors);
+            listing.tree
+        } else {
+            DirTree {
+                entry,
+                children: None,
+            }
+        };
+        count_files(&tree, &mut plan.files, &mut plan.total_bytes);
+        plan.jobs.push(CopyJob {
+            source: tree,
+            target,
+        });
+    }
+    Ok(plan)
+}
+
+/// 每个源对应的目标路径：dest 为已存在的目录时放入其中，否则 dest 即为目标 (只允许一个源)
+pub(crate) fn resolve_targets<P: AsRef<Path>>(sources: &[P], dest: &Path) -> Result<Vec<PathBuf>> {
+    let dest_is_dir = fs::metadata(dest).is_ok_and(|m| m.is_dir());
+    if sources.len() > 1 && !dest_is_dir {
+        return Err(FmgError::NotADirectory(dest.to_path_buf()));
+    }
+    sources
+        .iter()
+        .map(|source| match dest_is_dir {
+            true => Ok(dest.join(source_name(source.as_ref())?)),
+            false => Ok(dest.to_path_buf()),
+        })
+        .collect()
+}
+
+/// 源路径的名称；`.` 等没有文件名的路径使用其规范化路径的名称
+fn source_name(source: &Path) -> Result<OsString> {
+    if let Some(name) = source.file_name() {
+        return Ok(name.to_os_string());
+    }
+    let canonical = source
+        .canonicalize()
+        .map_err(|e| FmgError::from_io(source, e))?;
+    canonical
+        .file_name()
+        .map(|name| name.to_os_string())
+        .ok_or_else(|| FmgError::Io {
+            path: Some(source.to_path_buf()),
+            source: io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "cannot use the root directory as a source",
+            ),
+        })
+}
+
+/// 拒绝把目录复制或移动到其自身之中
+pub(crate) fn check_not_inside(source: &Path, target: &Path) -> io::Result<()> {
+    let source = source.canonicalize()?;
+    // 目标可能尚不存在，此时以其父目录判断
+    let parent = match target.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let Ok(parent) = parent.canonicalize() else {
+        return Ok(());
+    };
+    if parent
+        .join(target.file_name().unwrap_or_default())
+        .starts_with(&source)
+    {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "cannot place a directory inside itself",
+        ));
+    }
+    Ok(())
+}
+
+/// 统计树中的文件数量与总大小
+fn count_files(node: &DirTree, files: &mut usize, bytes: &mut u64) {
+    if node.entry.kind == EntryKind::File {
+        *files += 1;
+        *bytes += node.entry.size;
+    }
+    for child in node.children.iter().flatten() {
+        count_files(child, files, bytes);
+    }
+}
+
+/// 按计划复制，每写入一段数据向 progress 报告一次
+///
+/// 单个目录项失败不会中断复制，错误记录在 [`CopyReport::errors`] 中。
+pub fn copy(plan: &CopyPlan, options: &CopyOptions, progress: &mut dyn CopyProgress) -> CopyReport {
+    progress.begin(plan.total_bytes);
+    let mut copier = Copier {
+        options,
+        progress,
+        report: CopyReport::default(),
+        in_new_dir: false,
+    };
+    for job in &plan.jobs {
+        copier.copy_node(&job.source, &job.target);
+    }
+    copier.report
+}
+
+/// 复制过程中的状态
+struct Copier<'a> {
+    options: &'a CopyOptions,
+    progress: &'a mut dyn CopyProgress,
+    report: CopyReport,
+    /// 正在复制到本次新建的目录之中，其内容随目录一起记录
+    in_new_dir: bool,
+}
+
+impl Copier<'_> {
+    /// 复制一个节点，错误记录在报告中
+    fn copy_node(&mut self, node: &DirTree, target: &Path) {
+        let entry = &node.entry;
+        let result = match entry.kind {
+            EntryKind::Dir => self.copy_dir(node, target),
+            EntryKind::File => self.copy_file(entry, target),
+            EntryKind::Symlink => self.copy_symlink(entry, target),
+            _ => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "cannot copy special files",
+            )),
+        };
+        if let Err(error) = result {
+            self.report.errors.push(EntryError {
+                path: entry.path.clone(),
+                error,
+            });
+        }
+    }
+
+    /// 创建目录 (已存在时合并) 并复制其内容，目录的时间戳在内容复制完成后设置
+    fn copy_dir(&mut self, node: &DirTree, target: &Path) -> io::Result<()> {
+        let created = match fs::create_dir(target) {
+            Ok(()) => true,
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists && target.is_dir() => false,
+            Err(e) => return Err(e),
+        };
+        if created {
+            self.written(target);
+        }
+        let outer = self.in_new_dir;
+        self.in_new_dir |= created;
+        for child in node.children.iter().flatten() {
+            self.copy_node(child, &target.join(&child.entry.name));
+        }
+        self.in_new_dir = outer;
+        if self.options.preserve {
+            preserve_attributes(&node.entry, target)?;
+        }
+        Ok(())
+    }
+
+    /// 复制普通文件，需要时从断点续传、校验并保留属性
+    fn copy_file(&mut self, entry: &Entry, target: &Path) -> io::Result<()> {
+        let mut source = File::open(&entry.path)?;
+        let Some(existing) = existing_metadata(target)? else {
+            let output = File::options().write(true).create_new(true).open(target)?;
+            self.write_file(entry, &mut source, output, target, 0)?;
+            self.written(target);
+            self.report.files += 1;
+            return Ok(());
+        };
+        if is_same_file(entry, &existing, target) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "source and destination are the same file",
+            ));
+        }
+        if existing.is_dir() {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                "destination is a directory",
+            ));
+        }
+
+        // 只有确认是已复制的前缀才续传，否则与未指定 --resume 时一样处理
+        let resumable = match self.options.resume && existing.is_file() {
+            true => resume_offset(&mut source, target, existing.len(), entry.size)?,
+            false => None,
+        };
+        match resumable {
+            Some(offset) => {
+                let output = File::options().write(true).open(target)?;
+                self.write_file(entry, &mut source, output, target, offset)?;
+                // 已完整的文件没有写入任何内容
+                if offset < entry.size {
+                    self.written(target);
+                }
+            }
+            None if self.options.overwrite => {
+                // 先写入同一目录中的临时名称再替换目标，失败时原有的目标保持不变；
+                // 目标是符号链接时替换链接本身，不会通过它写入其他位置
+                let temp = temp_path(target);
+                let output = File::options().write(true).create_new(true).open(&temp)?;
+                let result = self
+                    .write_file(entry, &mut source, output, &temp, 0)
+                    .and_then(|_| self.replace(&temp, target));
+                if let Err(e) = result {
+                    let _ = fs::remove_file(&temp);
+                    return Err(e);
+                }
+            }
+            None => {
+                return Err(io::Error::new(
+                    io::ErrorKind::AlreadyExists,
+                    "destination already exists",
+                ));
+            }
+        }
+        self.report.files += 1;
+        Ok(())
+    }
+
+    /// 在报告中记录新写入的目标
+    fn written(&mut self, target: &Path) {
+        if !self.in_new_dir {
+            self.report.created.push(target.to_path_buf());
+        }
+    }
+
+    /// 将 temp 重命名为已存在的 target
+    ///
+    /// 指定了备份回收站时先为 target 创建硬链接，替换完成后再移入回收站；
+    /// 无法备份时照常替换，记录为无法撤销的覆盖。
+    fn replace(&mut self, temp: &Path, target: &Path) -> io::Result<()> {
+        let backup = self
+            .options
+            .backup
+            .as_ref()
+            .and_then(|_| Backup::link(target));
+        if let Err(e) = fs::rename(temp, target) {
+            if let Some(backup) = backup {
+                backup.discard();
+            }
+            return Err(e);
+        }
+        let item = match (backup, &self.options.backup) {
+            (Some(backup), Some(trash)) => backup.keep(trash),
+            _ => None,
+        };
+        match item {
+            Some(item) => {
+                self.report.trashed.push(item);
+                self.written(target);
+            }
+            None => self.report.replaced.push(target.to_path_buf()),
+        }
+        Ok(())
+    }
+
+    /// 从 offset 处开始将源文件写入 output (位于 path)，之后校验并保留属性
+    fn write_file(
+        &mut self,
+        entry: &Entry,
+        source: &mut File,
+        mut output: File,
+        path: &Path,
+        offset: u64,
+    ) -> io::Result<()> {
+        output.seek(SeekFrom::Start(offset))?;
+        source.seek(SeekFrom::Start(offset))?;
+        if offset > 0 {
+            self.report.resumed += offset;
+            self.progress.copied(entry, offset);
+        }
+
+        let mut buffer = vec![0; BUFFER_SIZE];
+        loop {
+            let read = match source.read(&mut buffer) {
+                Ok(0) => break,
+                Ok(read) => read,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            output.write_all(&buffer[..read])?;
+            self.report.bytes += read as u64;
+            self.progress.copied(entry, read as u64);
+        }
+        output.set_permissions(new_permissions(&source.metadata()?))?;
+        drop(output);
+
+        if self.options.verify && file_checksum(&entry.path)? != file_checksum(path)? {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "checksum mismatch after copy",
+            ));
+        }
+        if self.options.preserve {
+            preserve_attributes(entry, path)?;
+        }
+        Ok(())
+    }
+
+    /// 复制符号链接本身；目标已是指向同一位置的链接时视为已复制
+    #[cfg(unix)]
+    fn copy_symlink(&mut self, entry: &Entry, target: &Path) -> io::Result<()> {
+        let link = fs::read_link(&entry.path)?;
+        if existing_metadata(target)?.is_some() {
+            if fs::read_link(target).is_ok_and(|existing| existing == link) {
+                return Ok(());
+            }
+            if !self.options.overwrite || target.is_dir() {
+                return Err(io::Error::new(
+                    io::ErrorKind::AlreadyExists,
+                    "destination already exists",
+                ));
+            }
+            let temp = temp_path(target);
+            std::os::unix::fs::symlink(&link, &temp)?;
+            if let Err(e) = self.replace(&temp, target) {
+                let _ = fs::remove_file(&temp);
+                return Err(e);
+            }
+        } else {
+            std::os::unix::fs::symlink(&link, target)?;
+            self.written(target);
+        }
+        if self.options.preserve {
+            preserve_attributes(entry, target)?;
+        }
+        Ok(())
+    }
+
+    /// 复制符号链接本身 (非 Unix 平台需要 --follow 复制链接目标)
+    #[cfg(not(unix))]
+    fn copy_symlink(&mut self, _entry: &Entry, _target: &Path) -> io::Result<()> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "symbolic links can only be copied by following them",
+        ))
+    }
+}
+
+/// 目标路径的元数据 (不跟随符号链接)，不存在时返回 None
+pub(crate) fn existing_metadata(target: &Path) -> io::Result<Option<Metadata>> {
+    match fs::symlink_metadata(target) {
+        Ok(metadata) => Ok(Some(metadata)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// 目标目录中的临时名称，与目标位于同一文件系统
+pub(crate) fn temp_path(target: &Path) -> PathBuf {
+    let mut name = OsString::from(".");
+    name.push(target.file_name().unwrap_or_default());
+    name.push(format!(".fmg-{}", std::process::id()));
+    target.with_file_name(name)
+}
+
+/// 目标是否就是源文件本身 (包括硬链接)
+#[cfg(unix)]
+pub(crate) fn is_same_file(entry: &Entry, existing: &Metadata, _target: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    entry.dev == existing.dev() && entry.ino == existing.ino()
+}
+
+/// 目标是否就是源文件本身
+#[cfg(not(unix))]
+pub(crate) fn is_same_file(entry: &Entry, _existing: &Metadata, target: &Path) -> bool {
+    match (entry.path.canonicalize(), target.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
+/// 已复制部分的长度；目标比源文件长或末尾一段内容与源文件不一致时返回 None
+fn resume_offset(
+    source: &mut File,
+    target: &Path,
+    existing_len: u64,
+    source_len: u64,
+) -> io::Result<Option<u64>> {
+    if existing_len > source_len {
+        return Ok(None);
+    }
+    if existing_len == 0 {
+        return Ok(Some(0));
+    }
+    let check = existing_len.min(RESUME_CHECK_LEN);
+    let start = existing_len - check;
+    let mut expected = vec![0; check as usize];
+    source.seek(SeekFrom::Start(start))?;
+    source.read_exact(&mut expected)?;
+
+    let mut actual = vec![0; check as usize];
+    let mut existing = File::open(target)?;
+    existing.seek(SeekFrom::Start(start))?;
+    existing.read_exact(&mut actual)?;
+    Ok((expected == actual).then_some(existing_len))
+}
+
+/// 计算文件内容的 SHA-256
+pub(crate) fn file_checksum(path: &Path) -> io::Result<[u8; 32]> {
+    let mut file = File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0; BUFFER_SIZE];
+    loop {
+        match file.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(read) => hasher.update(&buffer[..read]),
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(hasher.finalize().into())
+}
+
+/// 源目录项的访问与修改时间
+fn file_times(entry: &Entry) -> FileTimes {
+    let mut times = FileTimes::new();
+    if let Some(accessed) = entry.accessed {
+        times = times.set_accessed(accessed);
+    }
+    if let Some(modified) = entry.modified {
+        times = times.set_modified(modified);
+    }
+    times
+}
+
+/// 将源目录项的扩展属性、属主、时间戳与权限应用到目标
+#[cfg(unix)]
+fn preserve_attributes(entry: &Entry, target: &Path) -> io::Result<()> {
+    use std::os::unix::fs::{PermissionsExt, lchown};
+
+    if entry.kind != EntryKind::Symlink {
+        copy_xattrs(&entry.path, target)?;
+    }
+    // 普通用户无法把文件交给其他用户，此时与 cp -p 一样保留自己为属主
+    match lchown(target, Some(entry.uid), Some(entry.gid)) {
+        Err(e) if e.kind() == io::ErrorKind::PermissionDenied => {}
+        result => result?,
+    }
+    // 符号链接本身没有权限位，其时间戳也无法通过打开文件来设置
+    if entry.kind == EntryKind::Symlink {
+        return Ok(());
+    }
+    // 先设置时间戳，避免权限变为只读或不可读后无法打开
+    File::open(target)?.set_times(file_times(entry))?;
+    fs::set_permissions(target, fs::Permissions::from_mode(entry.mode & 0o7777))
+}
+
+/// 不保留属性时新文件的权限：与 cp 一样只取源文件的 rwx 位并应用 umask
+#[cfg(unix)]
+fn new_permissions(source: &Metadata) -> fs::Permissions {
+    use std::os::unix::fs::PermissionsExt;
+    use std::sync::OnceLock;
+
+    static UMASK: OnceLock<u32> = OnceLock::new();
+    let umask = *UMASK.get_or_init(|| {
+        // SAFETY: umask 总是成功，读取后立即恢复原值
+        unsafe {
+            let mask = libc::umask(0);
+            libc::umask(mask);
+            mask as u32
+        }
+    });
+    fs::Permissions::from_mode(source.permissions().mode() & 0o777 & !umask)
+}
+
+/// 不保留属性时新文件的权限 (非 Unix 平台只有只读属性)
+#[cfg(not(unix))]
+fn new_permissions(source: &Metadata) -> fs::Permissions {
+    source.permissions()
+}
+
+/// 将源文件的只读属性与时间戳应用到目标 (非 Unix 平台没有属主与扩展属性)
+#[cfg(not(unix))]
+fn preserve_attributes(entry: &Entry, target: &Path) -> io::Result<()> {
+    if entry.kind != EntryKind::File {
+        return Ok(());
+    }
+    File::options()
+        .write(true)
+        .open(target)?
+        .set_times(file_times(entry))?;
+    let mut permissions = fs::metadata(target)?.permissions();
+    permissions.set_readonly(entry.mode & 0o200 == 0);
+    fs::set_permissions(target, permissions)
+}
+
+/// 复制扩展属性，文件系统不支持扩展属性时忽略
+#[cfg(unix)]
+fn copy_xattrs(source: &Path, target: &Path) -> io::Result<()> {
+    let names = match xattr::list(source) {
+        Ok(names) => names,
+        Err(e) if e.kind() == io::ErrorKind::Unsupported => return Ok(()),
+        Err(e) => return Err(e),
+    };
+    for name in names {
+        if let Some(value) = xattr::get(source, &name)? {
+            match xattr::set(target, &name, &value) {
+                Err(e) if e.kind() == io::ErrorKind::Unsupported => {}
+                result => result?,
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(sources: &[PathBuf], dest: &Path, options: &CopyOptions) -> (CopyPlan, CopyReport) {
+        let plan = plan_copy(sources, dest, options).unwrap();
+        let mut progressed = 0;
+        let report = copy(&plan, options, &mut |_: &Entry, bytes| progressed += bytes);
+        assert_eq!(progressed, report.bytes + report.resumed);
+        (plan, report)
+    }
+
+    #[test]
+    fn test_copy_tree_with_preserve_and_verify() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        let source = dir.join("src");
+        fs::create_dir_all(source.join("nested")).unwrap();
+        fs::write(source.join("a.txt"), "hello").unwrap();
+        fs::write(source.join("nested/.hidden"), "world!").unwrap();
+        let old = std::time::SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1_000_000);
+        File::options()
+            .write(true)
+            .open(source.join("a.txt"))
+            .unwrap()
+            .set_modified(old)
+            .unwrap();
+
+        let dest = dir.join("dest");
+        fs::create_dir(&dest).unwrap();
+        let options = CopyOptions {
+            preserve: true,
+            verify: true,
+            ..CopyOptions::default()
+        };
+        let (plan, report) = run(std::slice::from_ref(&source), &dest, &options);
+        assert_eq!((plan.files, plan.total_bytes), (2, 11));
+        assert_eq!((report.files, report.bytes), (2, 11));
+        assert!(report.errors.is_empty(), "{:?}", report.errors);
+        // 新建的目录只记录目录本身
+        assert_eq!(report.created, vec![dest.join("src")]);
+        assert_eq!(
+            fs::read_to_string(dest.join("src/nested/.hidden")).unwrap(),
+            "world!"
+        );
+        let modified = fs::metadata(dest.join("src/a.txt"))
+            .unwrap()
+            .modified()
+            .unwrap();
+        assert_eq!(modified, old);
+
+        // 目标已存在时默认拒绝覆盖
+        let (_, report) = run(
+            std::slice::from_ref(&source),
+            &dest,
+            &CopyOptions::default(),
+        );
+        assert_eq!(report.errors.len(), 2);
+        assert_eq!(report.errors[0].error.kind(), io::ErrorKind::AlreadyExists);
+
+        // 合并到已有目录时分别记录新建与覆盖的文件
+        fs::write(source.join("b.txt"), "b").unwrap();
+        let overwrite = CopyOptions {
+            overwrite: true,
+            ..CopyOptions::default()
+        };
+        let (_, report) = run(std::slice::from_ref(&source), &dest, &overwrite);
+        assert!(report.errors.is_empty(), "{:?}", report.errors);
+        assert_eq!(report.created, vec![dest.join("src/b.txt")]);
+        assert_eq!(report.replaced.len(), 2);
+        assert!(report.replaced.contains(&dest.join("src/nested/.hidden")));
+
+        // 不能复制到自身之中
+        assert!(plan_copy(&[&source], &source.join("nested"), &options).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_permissions_follow_umask_unless_preserved() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        let source = dir.join("tool");
+        fs::write(&source, "#!/bin/sh").unwrap();
+        fs::set_permissions(&source, fs::Permissions::from_mode(0o4755)).unwrap();
+        let mode = |path: &Path| fs::metadata(path).unwrap().permissions().mode() & 0o7777;
+
+        // 不保留属性时去掉 setuid 等特殊位，只保留 umask 允许的 rwx 位
+        let (_, report) = run(
+            std::slice::from_ref(&source),
+            &dir.join("plain"),
+            &CopyOptions::default(),
+        );
+        assert!(report.errors.is_empty(), "{:?}", report.errors);
+        assert_eq!(mode(&dir.join("plain")) & !0o755, 0);
+
+        let preserve = CopyOptions {
+            preserve: true,
+            ..CopyOptions::default()
+        };
+        run(std::slice::from_ref(&source), &dir.join("kept"), &preserve);
+        assert_eq!(mode(&dir.join("kept")), 0o4755);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_overwrite_replaces_target() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        let source = dir.join("new.txt");
+        fs::write(&source, "new").unwrap();
+        let outside = dir.join("outside.txt");
+        fs::write(&outside, "keep").unwrap();
+        let target = dir.join("target.txt");
+        std::os::unix::fs::symlink(&outside, &target).unwrap();
+
+        // 符号链接被替换为文件，链接指向的文件不受影响
+        let options = CopyOptions {
+            overwrite: true,
+            ..CopyOptions::default()
+        };
+        let (_, report) = run(std::slice::from_ref(&source), &target, &options);
+        assert!(report.errors.is_empty(), "{:?}", report.errors);
+        assert!(fs::symlink_metadata(&target).unwrap().is_file());
+        assert_eq!(fs::read_to_string(&target).unwrap(), "new");
+        assert_eq!(fs::read_to_string(&outside).unwrap(), "keep");
+        assert!(!temp_path(&target).exists());
+
+        // 替换的是目录项而不是原文件的内容，原文件的其他硬链接保持不变
+        fs::remove_file(&target).unwrap();
+        fs::hard_link(&outside, &target).unwrap();
+        let (_, report) = run(std::slice::from_ref(&source), &target, &options);
+        assert!(report.errors.is_empty(), "{:?}", report.errors);
+        assert_eq!(fs::read_to_string(&target).unwrap(), "new");
+        assert_eq!(fs::read_to_string(&outside).unwrap(), "keep");
+    }
+
+    #[test]
+    fn test_resume_partial_file() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        let content: Vec<u8> = (0..200_000u32).map(|i| (i % 251) as u8).collect();
+        let source = dir.join("big.bin");
+        let target = dir.join("copy.bin");
+        fs::write(&source, &content).unwrap();
+        fs::write(&target, &content[..150_000]).unwrap();
+
+        let options = CopyOptions {
+            resume: true,
+            verify: true,
+            ..CopyOptions::default()
+        };
+        let (_, report) = run(std::slice::from_ref(&source), &target, &options);
+        assert!(report.errors.is_empty(), "{:?}", report.errors);
+        assert_eq!((report.resumed, report.bytes), (150_000, 50_000));
+        assert_eq!(fs::read(&target).unwrap(), content);
+
+        // 已复制的部分与源文件不一致时不是续传，目标保持不变
+        fs::write(&target, vec![0xff; 1000]).unwrap();
+        let (_, report) = run(std::slice::from_ref(&source), &target, &options);
+        assert_eq!(report.errors.len(), 1);
+        assert_eq!(report.errors[0].error.kind(), io::ErrorKind::AlreadyExists);
+        assert_eq!(fs::read(&target).unwrap(), vec![0xff; 1000]);
+
+        // 目标比源文件长时同样拒绝
+        let longer = [content.as_slice(), b"tail"].concat();
+        fs::write(&target, &longer).unwrap();
+        let (_, report) = run(std::slice::from_ref(&source), &target, &options);
+        assert_eq!(report.errors.len(), 1);
+        assert_eq!(fs::read(&target).unwrap(), longer);
+
+        // 同时指定 --overwrite 时从头复制
+        let options = CopyOptions {
+            overwrite: true,
+            ..options
+        };
+        let (_, report) = run(std::slice::from_ref(&source), &target, &options);
+        assert!(report.errors.is_empty(), "{:?}", report.errors);
+        assert_eq!((report.resumed, report.bytes), (0, 200_000));
+        assert_eq!(fs::read(&target).unwrap(), content);
+    }
+}
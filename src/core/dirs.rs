@@ -0,0 +1,31 @@
+//! XDG 基础目录
+
+use std::env;
+use std::path::PathBuf;
+
+/// 读取指向绝对路径的环境变量，未设置、为空或为相对路径时返回 None (与 XDG 规范一致)
+fn absolute_env(name: &str) -> Option<PathBuf> {
+    env::var_os(name)
+        .map(PathBuf::from)
+        .filter(|path| path.is_absolute())
+}
+
+/// 用户主目录
+pub fn home_dir() -> Option<PathBuf> {
+    absolute_env("HOME").or_else(|| absolute_env("USERPROFILE"))
+}
+
+/// 用户配置目录：`$XDG_CONFIG_HOME`，默认为 `~/.config`
+pub fn config_dir() -> Option<PathBuf> {
+    absolute_env("XDG_CONFIG_HOME").or_else(|| home_dir().map(|home| home.join(".config")))
+}
+
+/// 用户数据目录：`$XDG_DATA_HOME`，默认为 `~/.local/share`
+pub fn data_dir() -> Option<PathBuf> {
+    absolute_env("XDG_DATA_HOME").or_else(|| home_dir().map(|home| home.join(".local/share")))
+}
+
+/// 用户状态目录：`$XDG_STATE_HOME`，默认为 `~/.local/state`
+pub fn state_dir() -> Option<PathBuf> {
+    absolute_env("XDG_STATE_HOME").or_else(|| home_dir().map(|home| home.join(".local/state")))
+}
@@ -0,0 +1,196 @@
+//! 错误类型模块
+
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// 库中所有操作的结果类型
+pub type Result<T> = std::result::Result<T, FmgError>;
+
+/// fmg 的错误类型
+#[derive(Debug)]
+pub enum FmgError {
+    /// 路径不存在
+    NotFound(PathBuf),
+    /// 路径不是目录
+    NotADirectory(PathBuf),
+    /// 没有访问权限
+    PermissionDenied(PathBuf),
+    /// 目标路径已存在
+    AlreadyExists(PathBuf),
+    /// 无效的匹配模式 (glob 或正则表达式)
+    InvalidPattern {
+        /// 原始模式
+        pattern: String,
+        /// 错误说明
+        message: String,
+    },
+    /// 配置文件无法解析
+    InvalidConfig {
+        /// 配置文件路径
+        path: PathBuf,
+        /// 错误说明
+        message: String,
+    },
+    /// 操作完成，但部分目录项无法读取
+    PartialRead {
+        /// 被遍历的路径
+        path: PathBuf,
+        /// 读取失败的目录项数量
+        failed: usize,
+    },
+    /// 操作完成，但部分目录项处理失败
+    PartialFailure {
+        /// 操作的名称，例如 "copied"
+        action: &'static str,
+        /// 处理失败的目录项数量
+        failed: usize,
+    },
+    /// 重命名的新名称无效或与其他文件冲突
+    RenameConflict {
+        /// 要重命名的路径
+        path: PathBuf,
+        /// 冲突说明
+        message: String,
+    },
+    /// 文件系统在操作之后发生了变化，撤销被拒绝
+    UndoRefused {
+        /// 操作编号
+        id: u64,
+        /// 拒绝的原因
+        reason: String,
+    },
+    /// 其他 IO 错误
+    Io {
+        /// 出错的路径 (如果已知)
+        path: Option<PathBuf>,
+        /// 底层 IO 错误
+        source: io::Error,
+    },
+}
+
+impl FmgError {
+    /// 根据 IO 错误的类型构建对应的错误
+    pub fn from_io(path: &Path, error: io::Error) -> Self {
+        match error.kind() {
+            io::ErrorKind::NotFound => FmgError::NotFound(path.to_path_buf()),
+            io::ErrorKind::NotADirectory => FmgError::NotADirectory(path.to_path_buf()),
+            io::ErrorKind::PermissionDenied => FmgError::PermissionDenied(path.to_path_buf()),
+            io::ErrorKind::AlreadyExists => FmgError::AlreadyExists(path.to_path_buf()),
+            _ => FmgError::Io {
+                path: Some(path.to_path_buf()),
+                source: error,
+            },
+        }
+    }
+
+    /// 进程退出码，与 `ls` 一致：1 表示轻微问题 (部分内容无法读取)，2 表示严重错误
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            FmgError::PartialRead { .. } | FmgError::PartialFailure { .. } => 1,
+            _ => 2,
+        }
+    }
+}
+
+impl fmt::Display for FmgError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FmgError::NotFound(path) => write!(f, "Path '{}' does not exist.", path.display()),
+            FmgError::NotADirectory(path) => {
+                write!(f, "Path '{}' is not a directory.", path.display())
+            }
+            FmgError::PermissionDenied(path) => {
+                write!(f, "Permission denied: '{}'.", path.display())
+            }
+            FmgError::AlreadyExists(path) => {
+                write!(f, "Path '{}' already exists.", path.display())
+            }
+            FmgError::InvalidPattern { pattern, message } => {
+                write!(f, "Invalid pattern '{}': {}", pattern, message)
+            }
+            FmgError::InvalidConfig { path, message } => {
+                write!(f, "Invalid config '{}': {}", path.display(), message)
+            }
+            FmgError::PartialRead { path, failed } => write!(
+                f,
+                "{} entr{} under '{}' could not be read.",
+                failed,
+                if *failed == 1 { "y" } else { "ies" },
+                path.display()
+            ),
+            FmgError::PartialFailure { action, failed } => write!(
+                f,
+                "{} entr{} could not be {}.",
+                failed,
+                if *failed == 1 { "y" } else { "ies" },
+                action
+            ),
+            FmgError::RenameConflict { path, message } => {
+                write!(f, "Cannot rename '{}': {}", path.display(), message)
+            }
+            FmgError::UndoRefused { id, reason } => {
+                write!(f, "Cannot undo operation #{}: {}", id, reason)
+            }
+            FmgError::Io {
+                path: Some(path),
+                source,
+            } => write!(f, "{}: {}", path.display(), source),
+            FmgError::Io { path: None, source } => write!(f, "{}", source),
+        }
+    }
+}
+
+impl std::error::Error for FmgError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            FmgError::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for FmgError {
+    fn from(error: io::Error) -> Self {
+        FmgError::Io {
+            path: None,
+            source: error,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_io_maps_kinds() {
+        let path = Path::new("x");
+        let not_found = FmgError::from_io(path, io::Error::from(io::ErrorKind::NotFound));
+        assert!(matches!(not_found, FmgError::NotFound(_)));
+        let denied = FmgError::from_io(path, io::Error::from(io::ErrorKind::PermissionDenied));
+        assert!(matches!(denied, FmgError::PermissionDenied(_)));
+        let other = FmgError::from_io(path, io::Error::other("boom"));
+        assert!(matches!(other, FmgError::Io { .. }));
+    }
+
+    #[test]
+    fn test_exit_codes() {
+        let partial = FmgError::PartialRead {
+            path: PathBuf::from("x"),
+            failed: 2,
+        };
+        assert_eq!(partial.exit_code(), 1);
+        assert_eq!(
+            partial.to_string(),
+            "2 entries under 'x' could not be read."
+        );
+        let partial = FmgError::PartialFailure {
+            action: "copied",
+            failed: 1,
+        };
+        assert_eq!(partial.exit_code(), 1);
+        assert_eq!(partial.to_string(), "1 entry could not be copied.");
+        assert_eq!(FmgError::NotFound(PathBuf::from("x")).exit_code(), 2);
+    }
+}
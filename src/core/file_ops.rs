@@ -1,251 +1,215 @@
 //! 核心文件操作模块
 
-use crate::display::formatter::{format_system_time, format_tree_prefix};
-use colored::*;
+use crate::core::error::{FmgError, Result};
+use crate::core::filter::{EntryFilter, FilterOptions};
+use crate::core::models::{DirTree, Entry, Listing, TreeListing};
+use crate::core::sort::SortOptions;
+use crate::core::walker::Walker;
 use std::path::Path;
-use std::time::UNIX_EPOCH;
-
-/// 列出目录内容
-pub fn list_directory(path_str: &str, long_format: bool) {
-    let path = Path::new(path_str);
-
-    if !path.exists() {
-        eprintln!(
-            "{} Path '{}' does not exist.",
-            "Error:".red().bold(),
-            path_str
-        );
-        return;
-    }
 
-    if !path.is_dir() {
-        eprintln!(
-            "{} Path '{}' is not a directory.",
-            "Error:".red().bold(),
-            path_str
-        );
-        return;
+/// 目录列表选项
+#[derive(Debug, Clone, Default)]
+pub struct ListOptions {
+    /// 排序选项
+    pub sort: SortOptions,
+    /// 过滤选项
+    pub filter: FilterOptions,
+    /// 将目录的大小替换为其所有内容的递归总大小
+    pub du: bool,
+    /// 跟随符号链接，显示链接目标的元数据
+    pub follow: bool,
+}
+
+/// 目录树遍历选项
+#[derive(Debug, Clone, Default)]
+pub struct TreeOptions {
+    /// 最大展开深度，None 表示不限制；深度为 max_depth 的目录不再展开
+    pub max_depth: Option<usize>,
+    /// 排序选项，应用于树的每一层
+    pub sort: SortOptions,
+    /// 过滤选项，应用于树的每一层 (`dot_entries` 在树形模式下无效)
+    pub filter: FilterOptions,
+    /// 将目录的大小替换为其所有内容的递归总大小
+    pub du: bool,
+    /// 跟随符号链接并展开指向目录的链接，链接形成的循环不会被重复展开
+    pub follow: bool,
+}
+
+/// 检查路径存在且为目录
+fn check_directory(path: &Path) -> Result<()> {
+    let metadata = std::fs::metadata(path).map_err(|e| FmgError::from_io(path, e))?;
+    if !metadata.is_dir() {
+        return Err(FmgError::NotADirectory(path.to_path_buf()));
     }
+    Ok(())
+}
+
+/// 构建 `.` 或 `..` 目录项
+fn dot_entry(dir: &Path, name: &str) -> Result<Entry> {
+    let path = dir.join(name);
+    let mut entry = Entry::from_path(&path, 0).map_err(|e| FmgError::from_io(&path, e))?;
+    entry.name = name.into();
+    Ok(entry)
+}
 
-    match std::fs::read_dir(path) {
-        Ok(entries) => {
-            for entry in entries {
-                match entry {
-                    Ok(entry) => {
-                        let file_name = entry.file_name();
-                        let file_path = entry.path();
-
-                        if long_format {
-                            match entry.metadata() {
-                                Ok(metadata) => {
-                                    let size = metadata.len();
-                                    // Simplified permissions display (Unix-style)
-                                    let permissions = if metadata.is_dir() {
-                                        "d".to_string()
-                                    } else {
-                                        "-".to_string()
-                                    };
-                                    let perm_string = format!("{}{}", permissions, "---------"); // Placeholder for actual perms
-
-                                    // Modified time (formatted)
-                                    let modified = metadata.modified().unwrap_or(UNIX_EPOCH);
-                                    let modified_str = format_system_time(modified);
-
-                                    if metadata.is_dir() {
-                                        println!(
-                                            "{} {:>10} {} {}",
-                                            perm_string,
-                                            size,
-                                            modified_str,
-                                            file_name.to_string_lossy().blue().bold()
-                                        );
-                                    } else {
-                                        println!(
-                                            "{} {:>10} {} {}",
-                                            perm_string,
-                                            size,
-                                            modified_str,
-                                            file_name.to_string_lossy().green()
-                                        );
-                                    }
-                                }
-                                Err(e) => {
-                                    eprintln!(
-                                        "{} Failed to get metadata for {}: {}",
-                                        "Error:".red().bold(),
-                                        file_name.to_string_lossy(),
-                                        e
-                                    );
-                                }
-                            }
-                        } else {
-                            if file_path.is_dir() {
-                                println!("{}", file_name.to_string_lossy().blue().bold());
-                            } else {
-                                println!("{}", file_name.to_string_lossy().green());
-                            }
-                        }
-                    }
-                    Err(e) => {
-                        eprintln!("{} Failed to read entry: {}", "Error:".red().bold(), e);
-                    }
-                }
-            }
-        }
-        Err(e) => {
-            eprintln!(
-                "{} Failed to read directory '{}': {}",
-                "Error:".red().bold(),
-                path_str,
-                e
-            );
-        }
+/// 读取目录内容
+///
+/// 路径不存在或不是目录时返回错误；单个目录项读取失败不会中断列表，
+/// 而是记录在 [`Listing::errors`] 中。
+pub fn list<P: AsRef<Path>>(path: P, options: &ListOptions) -> Result<Listing> {
+    let path = path.as_ref();
+    check_directory(path)?;
+
+    let walker = Walker::new(
+        &options.sort,
+        EntryFilter::new(&options.filter, path)?,
+        Some(0),
+        options.follow,
+        options.du,
+    );
+    let ignores = walker.filter().root_ignores();
+    let mut entries = walker
+        .read_entries(path, 0, &ignores)
+        .map_err(|e| FmgError::from_io(path, e))?;
+
+    if options.filter.dot_entries {
+        let dots = [dot_entry(path, ".")?, dot_entry(path, "..")?];
+        entries.splice(0..0, dots);
     }
+
+    Ok(Listing {
+        entries,
+        errors: walker.finish(),
+    })
 }
 
-/// 以树形结构列出目录内容
-pub fn list_directory_tree(
-    path_str: &str,
-    long_format: bool,
-    max_depth: usize,
-    current_depth: usize,
-) {
-    list_directory_tree_with_ancestors(path_str, long_format, max_depth, current_depth, &vec![])
+/// 以树形结构遍历目录
+///
+/// 返回以 `path` 为根的 [`DirTree`]，无法读取的子目录记录在
+/// [`TreeListing::errors`] 中并作为空目录保留在树里。
+pub fn walk_tree<P: AsRef<Path>>(path: P, options: &TreeOptions) -> Result<TreeListing> {
+    let path = path.as_ref();
+    check_directory(path)?;
+
+    let walker = Walker::new(
+        &options.sort,
+        EntryFilter::new(&options.filter, path)?,
+        options.max_depth,
+        options.follow,
+        options.du,
+    );
+    // 根目录本身可以是指向目录的符号链接
+    let root = Entry::from_path(path, 0)
+        .map_err(|e| FmgError::from_io(path, e))?
+        .follow();
+    let ignores = walker.filter().root_ignores();
+    let children = walker
+        .build_children(&root, &ignores)
+        .map_err(|e| FmgError::from_io(path, e))?;
+
+    Ok(TreeListing {
+        tree: DirTree {
+            entry: root,
+            children: Some(children),
+        },
+        errors: walker.finish(),
+    })
 }
 
-/// 递归实现树形结构列表，跟踪祖先节点信息
-fn list_directory_tree_with_ancestors(
-    path_str: &str,
-    long_format: bool,
-    max_depth: usize,
-    current_depth: usize,
-    ancestors: &Vec<bool>,
-) {
-    // Prevent infinite recursion
-    if current_depth > max_depth {
-        return;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::models::EntryKind;
+
+    #[test]
+    fn test_read_directory_sorted() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        std::fs::write(dir.join("b.txt"), "12").unwrap();
+        std::fs::write(dir.join("a.txt"), "").unwrap();
+        std::fs::create_dir(dir.join("c")).unwrap();
+
+        let listing = list(dir, &ListOptions::default()).unwrap();
+        let names: Vec<_> = listing.entries.iter().map(|e| e.name_lossy()).collect();
+        assert_eq!(names, ["a.txt", "b.txt", "c"]);
+        assert_eq!(listing.entries[1].size, 2);
+        assert_eq!(listing.entries[2].kind, EntryKind::Dir);
+        assert!(listing.errors.is_empty());
     }
 
-    let path = Path::new(path_str);
+    #[test]
+    fn test_read_directory_missing() {
+        let error = list("/nonexistent/fmg", &ListOptions::default()).unwrap_err();
+        assert!(matches!(error, FmgError::NotFound(_)));
+    }
 
-    if !path.exists() {
-        eprintln!(
-            "{} Path '{}' does not exist.",
-            "Error:".red().bold(),
-            path_str
-        );
-        return;
+    #[test]
+    fn test_walk_directory_tree_depth() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        std::fs::create_dir_all(dir.join("a/b/c")).unwrap();
+        std::fs::write(dir.join("a/b/c/deep.txt"), "").unwrap();
+
+        let options = TreeOptions {
+            max_depth: Some(1),
+            ..TreeOptions::default()
+        };
+        let listing = walk_tree(dir, &options).unwrap();
+        let a = &listing.tree.children.as_ref().unwrap()[0];
+        assert_eq!((a.entry.name_lossy(), a.entry.depth), ("a".into(), 0));
+        let b = &a.children.as_ref().unwrap()[0];
+        assert_eq!((b.entry.name_lossy(), b.entry.depth), ("b".into(), 1));
+        // 达到深度限制的目录不再展开
+        assert!(b.children.is_none());
     }
 
-    if !path.is_dir() {
-        eprintln!(
-            "{} Path '{}' is not a directory.",
-            "Error:".red().bold(),
-            path_str
-        );
-        return;
+    #[cfg(unix)]
+    #[test]
+    fn test_walk_tree_symlink_loop() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        std::fs::create_dir_all(dir.join("a")).unwrap();
+        std::os::unix::fs::symlink("..", dir.join("a/up")).unwrap();
+
+        // 默认不跟随符号链接
+        let listing = walk_tree(dir, &TreeOptions::default()).unwrap();
+        let a = &listing.tree.children.as_ref().unwrap()[0];
+        let up = &a.children.as_ref().unwrap()[0];
+        assert_eq!(up.entry.kind, EntryKind::Symlink);
+        assert!(up.children.is_none());
+        assert!(listing.errors.is_empty());
+
+        // 跟随时链接回到祖先目录，循环被检测到且不再展开
+        let options = TreeOptions {
+            follow: true,
+            ..TreeOptions::default()
+        };
+        let listing = walk_tree(dir, &options).unwrap();
+        let a = &listing.tree.children.as_ref().unwrap()[0];
+        let up = &a.children.as_ref().unwrap()[0];
+        assert_eq!(up.entry.kind, EntryKind::Dir);
+        assert!(up.children.is_none());
+        assert_eq!(listing.errors.len(), 1);
+        assert!(listing.errors[0].path.ends_with("a/up"));
     }
 
-    // For root level (current_depth == 0), we don't print the directory name itself
-    // Read directory entries
-    match std::fs::read_dir(path) {
-        Ok(entries) => {
-            // Filter out entries that might cause issues and collect them
-            let entries: Vec<_> = entries.filter_map(|e| e.ok()).collect();
-            let count = entries.len();
-
-            for (i, entry) in entries.iter().enumerate() {
-                let file_name = entry.file_name();
-                let file_path = entry.path();
-                let is_last = i == count - 1;
-
-                // Create prefix for tree structure using the new formatter
-                let prefix = format_tree_prefix(is_last, ancestors, current_depth);
-
-                if file_path.is_dir() {
-                    if long_format {
-                        match entry.metadata() {
-                            Ok(metadata) => {
-                                let size = metadata.len();
-                                let permissions = "d";
-                                let perm_string = format!("{}{}", permissions, "---------");
-                                let modified = metadata.modified().unwrap_or(UNIX_EPOCH);
-                                let modified_str = format_system_time(modified);
-                                println!(
-                                    "{}{} {:>10} {} {}",
-                                    prefix,
-                                    perm_string,
-                                    size,
-                                    modified_str,
-                                    file_name.to_string_lossy().blue().bold()
-                                );
-                            }
-                            Err(e) => {
-                                eprintln!(
-                                    "{} Failed to get metadata for {}: {}",
-                                    "Error:".red().bold(),
-                                    file_name.to_string_lossy(),
-                                    e
-                                );
-                            }
-                        }
-                    } else {
-                        println!("{}{}", prefix, file_name.to_string_lossy().blue().bold());
-                    }
-
-                    // Recursively list subdirectory if depth allows
-                    if current_depth < max_depth {
-                        // 更新祖先节点信息
-                        let mut new_ancestors = ancestors.clone();
-                        new_ancestors.push(is_last);
-                        list_directory_tree_with_ancestors(
-                            file_path.to_str().unwrap_or(""),
-                            long_format,
-                            max_depth,
-                            current_depth + 1,
-                            &new_ancestors,
-                        );
-                    }
-                } else {
-                    if long_format {
-                        match entry.metadata() {
-                            Ok(metadata) => {
-                                let size = metadata.len();
-                                let permissions = "-";
-                                let perm_string = format!("{}{}", permissions, "---------");
-                                let modified = metadata.modified().unwrap_or(UNIX_EPOCH);
-                                let modified_str = format_system_time(modified);
-                                println!(
-                                    "{}{} {:>10} {} {}",
-                                    prefix,
-                                    perm_string,
-                                    size,
-                                    modified_str,
-                                    file_name.to_string_lossy().green()
-                                );
-                            }
-                            Err(e) => {
-                                eprintln!(
-                                    "{} Failed to get metadata for {}: {}",
-                                    "Error:".red().bold(),
-                                    file_name.to_string_lossy(),
-                                    e
-                                );
-                            }
-                        }
-                    } else {
-                        println!("{}{}", prefix, file_name.to_string_lossy().green());
-                    }
-                }
-            }
-        }
-        Err(e) => {
-            eprintln!(
-                "{} Failed to read directory '{}': {}",
-                "Error:".red().bold(),
-                path_str,
-                e
-            );
-        }
+    // macOS 等文件系统不允许无效 UTF-8 的文件名
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_walk_tree_non_utf8_names() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        let name = std::ffi::OsStr::from_bytes(b"\xd6\xd0\xce\xc4");
+        std::fs::create_dir(dir.join(name)).unwrap();
+        std::fs::write(dir.join(name).join("inner.txt"), "").unwrap();
+
+        let listing = walk_tree(dir, &TreeOptions::default()).unwrap();
+        let node = &listing.tree.children.as_ref().unwrap()[0];
+        assert_eq!(node.entry.name, name);
+        let inner = &node.children.as_ref().unwrap()[0];
+        assert_eq!(inner.entry.name_lossy(), "inner.txt");
+        assert!(listing.errors.is_empty());
     }
 }
@@ -0,0 +1,254 @@
+//! 目录项过滤模块：隐藏文件、忽略模式与 .gitignore 规则
+
+use crate::core::error::{FmgError, Result};
+use crate::core::models::{Entry, EntryKind};
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use ignore::Match;
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// 过滤选项
+#[derive(Debug, Clone, Default)]
+pub struct FilterOptions {
+    /// 显示以 `.` 开头的隐藏文件
+    pub show_hidden: bool,
+    /// 在平铺列表中额外显示 `.` 与 `..`
+    pub dot_entries: bool,
+    /// 按文件名匹配的忽略模式 (glob)
+    pub ignore: Vec<String>,
+    /// 遵循 .gitignore、.ignore 与全局 git 排除规则
+    pub gitignore: bool,
+}
+
+/// 目录中 .gitignore 等文件编译后的规则，按目录层级由浅到深排列
+pub type IgnoreStack = Vec<Arc<Gitignore>>;
+
+/// 根据过滤选项编译得到的过滤器
+#[derive(Debug)]
+pub struct EntryFilter {
+    show_hidden: bool,
+    gitignore: bool,
+    globs: GlobSet,
+    global: Option<Gitignore>,
+    /// 遍历起点 (与目录项路径形式一致)
+    root: PathBuf,
+    /// 遍历起点的绝对路径，gitignore 规则均以绝对路径匹配
+    abs_root: PathBuf,
+}
+
+impl EntryFilter {
+    /// 编译过滤选项，root 为遍历起点
+    pub fn new(options: &FilterOptions, root: &Path) -> Result<Self> {
+        let mut builder = GlobSetBuilder::new();
+        for pattern in &options.ignore {
+            let glob = Glob::new(pattern).map_err(|e| FmgError::InvalidPattern {
+                pattern: pattern.clone(),
+                message: e.kind().to_string(),
+            })?;
+            builder.add(glob);
+        }
+        let globs = builder.build().map_err(|e| FmgError::InvalidPattern {
+            pattern: options.ignore.join(", "),
+            message: e.to_string(),
+        })?;
+
+        let (global, abs_root) = if options.gitignore {
+            let (global, _) = Gitignore::global();
+            let abs_root = root
+                .canonicalize()
+                .map_err(|e| FmgError::from_io(root, e))?;
+            (Some(global), abs_root)
+        } else {
+            (None, root.to_path_buf())
+        };
+
+        Ok(Self {
+            show_hidden: options.show_hidden,
+            gitignore: options.gitignore,
+            globs,
+            global,
+            root: root.to_path_buf(),
+            abs_root,
+        })
+    }
+
+    /// 遍历起点目录及其所在 git 仓库中各级祖先目录的忽略规则
+    pub fn root_ignores(&self) -> IgnoreStack {
+        if !self.gitignore {
+            return IgnoreStack::new();
+        }
+
+        // 向上查找仓库根目录，找不到时只使用起点目录自身的规则
+        let repo_root = self
+            .abs_root
+            .ancestors()
+            .find(|dir| dir.join(".git").exists())
+            .unwrap_or(&self.abs_root);
+
+        let mut dirs: Vec<&Path> = self
+            .abs_root
+            .ancestors()
+            .take_while(|dir| dir.starts_with(repo_root))
+            .collect();
+        dirs.reverse();
+
+        let mut stack = IgnoreStack::new();
+        for dir in dirs {
+            if let Some(gitignore) = load_dir_ignores(dir, dir == repo_root) {
+                stack.push(Arc::new(gitignore));
+            }
+        }
+        stack
+    }
+
+    /// 进入子目录时，在规则栈上追加该目录的忽略规则
+    pub fn enter_dir(&self, dir: &Path, parent: &IgnoreStack) -> IgnoreStack {
+        let mut stack = parent.clone();
+        if self.gitignore
+            && let Some(gitignore) = load_dir_ignores(&self.absolute(dir), false)
+        {
+            stack.push(Arc::new(gitignore));
+        }
+        stack
+    }
+
+    /// 判断目录项是否应当显示
+    pub fn is_visible(&self, entry: &Entry, ignores: &IgnoreStack) -> bool {
+        let name = entry.name_lossy();
+        if !self.show_hidden && name.starts_with('.') {
+            return false;
+        }
+        if self.globs.is_match(Path::new(&entry.name)) {
+            return false;
+        }
+        if self.gitignore {
+            // 仓库元数据目录始终不显示
+            if name == ".git" {
+                return false;
+            }
+            return !self.is_git_ignored(entry, ignores);
+        }
+        true
+    }
+
+    /// 按由深到浅的顺序匹配规则，最先命中的规则生效，最后检查全局排除规则
+    fn is_git_ignored(&self, entry: &Entry, ignores: &IgnoreStack) -> bool {
+        let path = self.absolute(&entry.path);
+        let is_dir = entry.kind == EntryKind::Dir;
+        let matchers = ignores
+            .iter()
+            .rev()
+            .map(|gi| gi.as_ref())
+            .chain(self.global.as_ref());
+        for matcher in matchers {
+            match matcher.matched(&path, is_dir) {
+                Match::Ignore(_) => return true,
+                Match::Whitelist(_) => return false,
+                Match::None => {}
+            }
+        }
+        false
+    }
+
+    /// 将遍历得到的路径转换为绝对路径
+    fn absolute(&self, path: &Path) -> PathBuf {
+        match path.strip_prefix(&self.root) {
+            Ok(relative) => self.abs_root.join(relative),
+            Err(_) => path.to_path_buf(),
+        }
+    }
+}
+
+/// 读取目录下的 .gitignore 与 .ignore (以及仓库根目录的 .git/info/exclude)
+fn load_dir_ignores(dir: &Path, is_repo_root: bool) -> Option<Gitignore> {
+    let mut candidates = Vec::new();
+    if is_repo_root {
+        candidates.push(dir.join(".git").join("info").join("exclude"));
+    }
+    // 后添加的文件优先级更高
+    candidates.push(dir.join(".gitignore"));
+    candidates.push(dir.join(".ignore"));
+
+    let mut builder = GitignoreBuilder::new(dir);
+    let mut found = false;
+    for file in candidates.iter().filter(|file| file.is_file()) {
+        // 单行语法错误不影响其余规则
+        let _ = builder.add(file);
+        found = true;
+    }
+    if !found {
+        return None;
+    }
+    builder.build().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn visible_names(dir: &Path, options: &FilterOptions) -> Vec<String> {
+        let filter = EntryFilter::new(options, dir).unwrap();
+        let ignores = filter.root_ignores();
+        let mut names: Vec<String> = std::fs::read_dir(dir)
+            .unwrap()
+            .map(|e| Entry::from_path(&e.unwrap().path(), 0).unwrap())
+            .filter(|entry| filter.is_visible(entry, &ignores))
+            .map(|entry| entry.name_lossy().into_owned())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn test_hidden_and_glob() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        for name in [".hidden", "keep.rs", "drop.o"] {
+            std::fs::write(dir.join(name), "").unwrap();
+        }
+
+        assert_eq!(
+            visible_names(dir, &FilterOptions::default()),
+            ["drop.o", "keep.rs"]
+        );
+        let options = FilterOptions {
+            show_hidden: true,
+            ignore: vec!["*.o".to_string()],
+            ..FilterOptions::default()
+        };
+        assert_eq!(visible_names(dir, &options), [".hidden", "keep.rs"]);
+    }
+
+    #[test]
+    fn test_gitignore_rules() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        std::fs::create_dir_all(dir.join(".git")).unwrap();
+        std::fs::create_dir_all(dir.join("target")).unwrap();
+        std::fs::write(dir.join(".gitignore"), "target/\n*.log\n!keep.log\n").unwrap();
+        for name in ["main.rs", "debug.log", "keep.log"] {
+            std::fs::write(dir.join(name), "").unwrap();
+        }
+
+        let options = FilterOptions {
+            show_hidden: true,
+            gitignore: true,
+            ..FilterOptions::default()
+        };
+        assert_eq!(
+            visible_names(dir, &options),
+            [".gitignore", "keep.log", "main.rs"]
+        );
+    }
+
+    #[test]
+    fn test_invalid_glob() {
+        let options = FilterOptions {
+            ignore: vec!["[".to_string()],
+            ..FilterOptions::default()
+        };
+        let error = EntryFilter::new(&options, Path::new(".")).unwrap_err();
+        assert!(matches!(error, FmgError::InvalidPattern { .. }));
+    }
+}
@@ -0,0 +1,306 @@
+//! 内容搜索模块：在目录树的文件中按正则表达式逐行查找
+//!
+//! 文件列表由并行遍历器得到，各文件的搜索再交给 rayon 并发执行，结果按遍历顺序收集。
+
+use crate::core::error::{FmgError, Result};
+use crate::core::file_ops::{TreeOptions, walk_tree};
+use crate::core::filter::FilterOptions;
+use crate::core::models::{DirTree, Entry, EntryError, EntryKind};
+use crate::core::search::compile_glob;
+use crate::core::sort::SortOptions;
+use rayon::prelude::*;
+use regex::{Regex, RegexBuilder};
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::ops::Range;
+use std::path::Path;
+
+/// 检查是否为二进制文件时读取的字节数
+const BINARY_PROBE_LEN: usize = 8192;
+
+/// 内容搜索选项
+#[derive(Debug, Clone, Default)]
+pub struct GrepOptions {
+    /// 正则表达式
+    pub pattern: String,
+    /// 忽略大小写
+    pub ignore_case: bool,
+    /// 将 pattern 视为普通字符串而不是正则表达式
+    pub fixed_strings: bool,
+    /// 每个匹配行之前输出的上下文行数
+    pub before_context: usize,
+    /// 每个匹配行之后输出的上下文行数
+    pub after_context: usize,
+    /// 只搜索文件名匹配该 glob 的文件
+    pub name: Option<String>,
+    /// 最大深度，1 表示只搜索起点目录中的文件；None 表示不限制
+    pub max_depth: Option<usize>,
+    /// 排序选项，决定文件的输出顺序
+    pub sort: SortOptions,
+    /// 过滤选项，被过滤的目录不会进入
+    pub filter: FilterOptions,
+    /// 跟随符号链接
+    pub follow: bool,
+}
+
+/// 输出行的类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineKind {
+    /// 匹配行
+    Match,
+    /// 上下文行
+    Context,
+}
+
+/// 一行搜索结果
+#[derive(Debug, Clone)]
+pub struct GrepLine {
+    /// 行号，从 1 开始
+    pub number: u64,
+    pub kind: LineKind,
+    /// 行内容 (去掉换行符，无效的 UTF-8 以 U+FFFD 替代)
+    pub text: String,
+    /// 匹配部分在 text 中的字节范围
+    pub matches: Vec<Range<usize>>,
+}
+
+/// 单个文件的搜索结果
+#[derive(Debug)]
+pub struct FileMatches {
+    pub entry: Entry,
+    /// 文件包含 NUL 字节，被视为二进制文件，此时不保存行内容
+    pub binary: bool,
+    /// 匹配的行数
+    pub match_count: usize,
+    /// 匹配行与上下文行，按行号排列
+    pub lines: Vec<GrepLine>,
+}
+
+/// 内容搜索结果
+#[derive(Debug)]
+pub struct GrepResult {
+    /// 存在匹配的文件，按遍历顺序排列
+    pub files: Vec<FileMatches>,
+    /// 搜索过的文件数量
+    pub searched: usize,
+    /// 读取失败的目录项与文件
+    pub errors: Vec<EntryError>,
+}
+
+/// 在 path 下的所有文件中搜索；path 也可以是单个文件
+pub fn grep<P: AsRef<Path>>(path: P, options: &GrepOptions) -> Result<GrepResult> {
+    let path = path.as_ref();
+    let regex = RegexBuilder::new(&pattern_source(options))
+        .case_insensitive(options.ignore_case)
+        .build()
+        .map_err(|e| FmgError::InvalidPattern {
+            pattern: options.pattern.clone(),
+            message: e.to_string(),
+        })?;
+    let name = options.name.as_deref().map(compile_glob).transpose()?;
+
+    let root = Entry::from_path(path, 0)
+        .map_err(|e| FmgError::from_io(path, e))?
+        .follow();
+    let (files, mut errors) = if root.kind == EntryKind::Dir {
+        let tree_options = TreeOptions {
+            max_depth: options.max_depth.map(|depth| depth.saturating_sub(1)),
+            sort: options.sort,
+            filter: options.filter.clone(),
+            du: false,
+            follow: options.follow,
+        };
+        let listing = walk_tree(path, &tree_options)?;
+        let mut files = Vec::new();
+        if options.max_depth != Some(0) {
+            collect_files(listing.tree, &mut files);
+        }
+        if let Some(name) = &name {
+            files.retain(|entry| name.is_match(Path::new(&entry.name)));
+        }
+        (files, listing.errors)
+    } else {
+        (vec![root], Vec::new())
+    };
+
+    let searched = files.len();
+    let results: Vec<_> = files
+        .into_par_iter()
+        .map(|entry| search_file(entry, &regex, options))
+        .collect();
+
+    let mut matches = Vec::new();
+    for result in results {
+        match result {
+            Ok(file) if file.match_count > 0 => matches.push(file),
+            Ok(_) => {}
+            Err(error) => errors.push(error),
+        }
+    }
+    errors.sort_by(|a, b| a.path.cmp(&b.path));
+
+    Ok(GrepResult {
+        files: matches,
+        searched,
+        errors,
+    })
+}
+
+/// 实际交给正则引擎的表达式
+fn pattern_source(options: &GrepOptions) -> String {
+    if options.fixed_strings {
+        regex::escape(&options.pattern)
+    } else {
+        options.pattern.clone()
+    }
+}
+
+/// 按遍历顺序收集树中的普通文件
+fn collect_files(node: DirTree, files: &mut Vec<Entry>) {
+    for child in node.children.into_iter().flatten() {
+        if child.entry.kind == EntryKind::File {
+            files.push(child.entry);
+        } else {
+            collect_files(child, files);
+        }
+    }
+}
+
+/// 逐行搜索单个文件，只在内存中保留匹配行与所需的上下文
+fn search_file(
+    entry: Entry,
+    regex: &Regex,
+    options: &GrepOptions,
+) -> std::result::Result<FileMatches, EntryError> {
+    let to_error = |entry: &Entry, error: io::Error| EntryError {
+        path: entry.path.clone(),
+        error,
+    };
+    let file = match File::open(&entry.path) {
+        Ok(file) => file,
+        Err(error) => return Err(to_error(&entry, error)),
+    };
+    let mut reader = BufReader::with_capacity(BINARY_PROBE_LEN, file);
+    let binary = match reader.fill_buf() {
+        Ok(head) => head.contains(&0),
+        Err(error) => return Err(to_error(&entry, error)),
+    };
+
+    let mut result = FileMatches {
+        entry,
+        binary,
+        match_count: 0,
+        lines: Vec::new(),
+    };
+    let mut before: VecDeque<GrepLine> = VecDeque::with_capacity(options.before_context);
+    let mut after_remaining = 0;
+    let mut buf = Vec::new();
+    let mut number = 0;
+
+    loop {
+        buf.clear();
+        match reader.read_until(b'\n', &mut buf) {
+            Ok(0) => break,
+            Ok(_) => {}
+            Err(error) => return Err(to_error(&result.entry, error)),
+        }
+        number += 1;
+        if buf.last() == Some(&b'\n') {
+            buf.pop();
+            if buf.last() == Some(&b'\r') {
+                buf.pop();
+            }
+        }
+
+        let text = String::from_utf8_lossy(&buf);
+        let matches: Vec<_> = regex.find_iter(&text).map(|m| m.range()).collect();
+        if result.binary {
+            // 二进制文件只统计匹配行数，不输出内容
+            result.match_count += usize::from(!matches.is_empty());
+            continue;
+        }
+
+        let kind = if !matches.is_empty() {
+            result.match_count += 1;
+            result.lines.extend(before.drain(..));
+            after_remaining = options.after_context;
+            LineKind::Match
+        } else if after_remaining > 0 {
+            after_remaining -= 1;
+            LineKind::Context
+        } else {
+            if options.before_context > 0 {
+                if before.len() == options.before_context {
+                    before.pop_front();
+                }
+                before.push_back(GrepLine {
+                    number,
+                    kind: LineKind::Context,
+                    text: text.into_owned(),
+                    matches,
+                });
+            }
+            continue;
+        };
+        result.lines.push(GrepLine {
+            number,
+            kind,
+            text: text.into_owned(),
+            matches,
+        });
+    }
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_grep_context_and_binary() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        std::fs::create_dir_all(dir.join("logs")).unwrap();
+        std::fs::write(
+            dir.join("logs/app.log"),
+            "start\nok\nERROR disk full\nretry\nok\nok\nok\nerror again\r\n",
+        )
+        .unwrap();
+        std::fs::write(dir.join("data.bin"), b"\x00\x01ERROR\x02").unwrap();
+        std::fs::write(dir.join("notes.txt"), "nothing here\n").unwrap();
+
+        let options = GrepOptions {
+            pattern: "error".to_string(),
+            ignore_case: true,
+            before_context: 1,
+            after_context: 1,
+            ..GrepOptions::default()
+        };
+        let result = grep(dir, &options).unwrap();
+        assert_eq!(result.searched, 3);
+        assert!(result.errors.is_empty());
+
+        let names: Vec<_> = result.files.iter().map(|f| f.entry.name_lossy()).collect();
+        assert_eq!(names, ["data.bin", "app.log"]);
+        assert!(result.files[0].binary && result.files[0].lines.is_empty());
+
+        let log = &result.files[1];
+        assert_eq!(log.match_count, 2);
+        let numbers: Vec<_> = log.lines.iter().map(|line| line.number).collect();
+        assert_eq!(numbers, [2, 3, 4, 7, 8]);
+        assert_eq!(log.lines[1].kind, LineKind::Match);
+        assert_eq!(log.lines[1].matches, vec![0..5]);
+        assert_eq!(log.lines[4].text, "error again");
+
+        // 普通字符串模式下元字符没有特殊含义
+        let options = GrepOptions {
+            pattern: "disk.".to_string(),
+            fixed_strings: true,
+            name: Some("*.log".to_string()),
+            ..GrepOptions::default()
+        };
+        assert!(grep(dir, &options).unwrap().files.is_empty());
+    }
+}
@@ -0,0 +1,510 @@
+//! 撤销日志模块：记录修改文件系统的操作，并在文件系统未变化时撤销
+//!
+//! 日志保存在 `$XDG_STATE_HOME/fmg/journal.jsonl`，每行一个 JSON 格式的操作。每个动作
+//! 记录操作完成后目标的指纹 (项目数、总字节数与最新的修改时间)，撤销前重新计算并比较，
+//! 不一致时拒绝撤销，不会覆盖之后发生的修改。
+
+use crate::core::dirs;
+use crate::core::error::{FmgError, Result};
+use crate::core::models::Entry;
+use crate::core::move_ops::{ConflictPolicy, move_path, remove_all};
+use crate::core::trash::{Trash, TrashItem};
+use crate::display::time::format_local;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// 文件或目录树在某一时刻的指纹
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Fingerprint {
+    /// 项目数 (包括根本身)
+    pub entries: u64,
+    /// 普通文件的总字节数
+    pub bytes: u64,
+    /// 所有项目中最新的修改时间 (自 UNIX 纪元起的纳秒数)
+    pub modified: i64,
+}
+
+impl Fingerprint {
+    /// 计算 path 的指纹，不跟随符号链接
+    pub fn of(path: &Path) -> io::Result<Self> {
+        let metadata = fs::symlink_metadata(path)?;
+        let mut fingerprint = Fingerprint {
+            entries: 1,
+            bytes: if metadata.is_file() {
+                metadata.len()
+            } else {
+                0
+            },
+            modified: modified_nanos(&metadata),
+        };
+        if metadata.is_dir() {
+            for child in fs::read_dir(path)? {
+                let child = Fingerprint::of(&child?.path())?;
+                fingerprint.entries += child.entries;
+                fingerprint.bytes += child.bytes;
+                fingerprint.modified = fingerprint.modified.max(child.modified);
+            }
+        }
+        Ok(fingerprint)
+    }
+}
+
+/// 修改时间，早于 UNIX 纪元时为负数
+fn modified_nanos(metadata: &fs::Metadata) -> i64 {
+    let nanos = match metadata.modified() {
+        Ok(time) => match time.duration_since(UNIX_EPOCH) {
+            Ok(after) => after.as_nanos() as i128,
+            Err(before) => -(before.duration().as_nanos() as i128),
+        },
+        Err(_) => 0,
+    };
+    // i64 纳秒可以表示 1677 年到 2262 年，之外的时间截断到边界
+    nanos.clamp(i64::MIN.into(), i64::MAX.into()) as i64
+}
+
+/// 操作中的单个动作
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum Action {
+    /// from 被移动到 to
+    Moved {
+        from: PathBuf,
+        to: PathBuf,
+        fingerprint: Fingerprint,
+    },
+    /// 新建了 path (例如复制的目标)
+    Created {
+        path: PathBuf,
+        fingerprint: Fingerprint,
+    },
+    /// 移入了回收站
+    Trashed {
+        item: TrashItem,
+        fingerprint: Fingerprint,
+    },
+    /// 从回收站恢复到 to
+    Restored {
+        to: PathBuf,
+        fingerprint: Fingerprint,
+    },
+    /// 已存在的 path 被覆盖且没有移入回收站，原内容无法恢复
+    Replaced { path: PathBuf },
+    /// path 被永久删除，无法恢复
+    Deleted { path: PathBuf },
+}
+
+impl Action {
+    /// 操作完成后记录 from 到 to 的移动
+    pub fn moved(from: &Path, to: &Path) -> io::Result<Self> {
+        Ok(Action::Moved {
+            from: std::path::absolute(from)?,
+            to: std::path::absolute(to)?,
+            fingerprint: Fingerprint::of(to)?,
+        })
+    }
+
+    /// 操作完成后记录新建的 path
+    pub fn created(path: &Path) -> io::Result<Self> {
+        Ok(Action::Created {
+            path: std::path::absolute(path)?,
+            fingerprint: Fingerprint::of(path)?,
+        })
+    }
+
+    /// 记录移入回收站的项目
+    pub fn trashed(item: TrashItem) -> io::Result<Self> {
+        let fingerprint = Fingerprint::of(&item.file_path())?;
+        Ok(Action::Trashed { item, fingerprint })
+    }
+
+    /// 记录从回收站恢复到 to 的项目
+    pub fn restored(to: &Path) -> io::Result<Self> {
+        Ok(Action::Restored {
+            to: std::path::absolute(to)?,
+            fingerprint: Fingerprint::of(to)?,
+        })
+    }
+
+    /// 动作能否撤销：永久删除与没有备份的覆盖无法撤销
+    pub fn is_reversible(&self) -> bool {
+        !matches!(self, Action::Replaced { .. } | Action::Deleted { .. })
+    }
+
+    /// 动作的简短描述，用于 `fmg history`
+    pub fn describe(&self) -> String {
+        match self {
+            Action::Moved { from, to, .. } => {
+                format!("{} -> {}", from.display(), to.display())
+            }
+            Action::Created { path, .. } => format!("created {}", path.display()),
+            Action::Trashed { item, .. } => format!("trashed {}", item.original.display()),
+            Action::Restored { to, .. } => format!("restored {}", to.display()),
+            Action::Replaced { path } => format!("replaced {}", path.display()),
+            Action::Deleted { path } => format!("deleted {}", path.display()),
+        }
+    }
+
+    /// 检查动作能否撤销，不能时返回原因；通过检查后在 state 中记录撤销后的路径占用情况
+    fn check(&self, state: &mut UndoState) -> std::result::Result<(), String> {
+        match self {
+            Action::Moved {
+                from,
+                to,
+                fingerprint,
+            } => {
+                state.check_unchanged(to, fingerprint)?;
+                state.check_free(from)?;
+                state.vacate(to);
+                state.fill(from);
+            }
+            Action::Created { path, fingerprint }
+            | Action::Restored {
+                to: path,
+                fingerprint,
+            } => {
+                state.check_unchanged(path, fingerprint)?;
+                state.vacate(path);
+            }
+            Action::Trashed { item, fingerprint } => {
+                let file = item.file_path();
+                state.check_unchanged(&file, fingerprint)?;
+                state.check_free(&item.original)?;
+                state.vacate(&file);
+                state.fill(&item.original);
+            }
+            Action::Replaced { path } => {
+                return Err(format!(
+                    "'{}' was overwritten and cannot be recovered",
+                    path.display()
+                ));
+            }
+            Action::Deleted { path } => {
+                return Err(format!(
+                    "'{}' was deleted permanently and cannot be recovered",
+                    path.display()
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// 撤销动作
+    fn revert(&self, trash: &Trash) -> io::Result<()> {
+        match self {
+            Action::Moved { from, to, .. } => {
+                move_path(to, from, ConflictPolicy::Fail, &mut |_: &Entry, _| {}).map(drop)
+            }
+            Action::Created { path, .. } => remove_all(path),
+            Action::Trashed { item, .. } => trash.restore(item, ConflictPolicy::Fail).map(drop),
+            Action::Restored { to, .. } => trash.put(to).map(drop),
+            Action::Replaced { .. } | Action::Deleted { .. } => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "cannot be undone",
+            )),
+        }
+    }
+}
+
+/// 依次检查各动作时，已检查的动作撤销后会腾出或占用的路径
+///
+/// 链式与成环的重命名 (a→b、b→c) 中，后一个动作的目标要等前一个动作撤销后才空出。
+#[derive(Default)]
+struct UndoState {
+    /// 撤销前面的动作后将被腾出的路径
+    vacated: HashSet<PathBuf>,
+    /// 撤销前面的动作后将被占用的路径，其内容已由对应的动作检查过
+    filled: HashSet<PathBuf>,
+}
+
+impl UndoState {
+    /// path 的指纹与记录时一致
+    fn check_unchanged(
+        &self,
+        path: &Path,
+        fingerprint: &Fingerprint,
+    ) -> std::result::Result<(), String> {
+        if self.filled.contains(path) {
+            return Ok(());
+        }
+        match Fingerprint::of(path) {
+            Ok(_) if self.vacated.contains(path) => {
+                Err(format!("'{}' no longer exists", path.display()))
+            }
+            Ok(current) if current == *fingerprint => Ok(()),
+            Ok(_) => Err(format!("'{}' has changed since", path.display())),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                Err(format!("'{}' no longer exists", path.display()))
+            }
+            Err(e) => Err(format!("'{}': {}", path.display(), e)),
+        }
+    }
+
+    /// path 在撤销到该动作时尚未被占用
+    fn check_free(&self, path: &Path) -> std::result::Result<(), String> {
+        let occupied = if self.vacated.contains(path) {
+            false
+        } else {
+            self.filled.contains(path) || fs::symlink_metadata(path).is_ok()
+        };
+        if occupied {
+            Err(format!("'{}' already exists", path.display()))
+        } else {
+            Ok(())
+        }
+    }
+
+    fn vacate(&mut self, path: &Path) {
+        self.filled.remove(path);
+        self.vacated.insert(path.to_path_buf());
+    }
+
+    fn fill(&mut self, path: &Path) {
+        self.vacated.remove(path);
+        self.filled.insert(path.to_path_buf());
+    }
+}
+
+/// 一次命令执行所做的修改
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Operation {
+    /// 递增的编号，从 1 开始
+    pub id: u64,
+    /// 执行时间 (本地时间)
+    pub time: String,
+    /// 命令，例如 `move`
+    pub command: String,
+    pub actions: Vec<Action>,
+    /// 已被撤销
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub undone: bool,
+}
+
+impl Operation {
+    /// 操作能否撤销，不能时返回原因
+    pub fn check(&self) -> Result<()> {
+        if self.undone {
+            return Err(self.refused("it has already been undone".to_string()));
+        }
+        // 按撤销的顺序检查，任何一个动作无法撤销时都不做任何修改
+        let mut state = UndoState::default();
+        for action in self.actions.iter().rev() {
+            action
+                .check(&mut state)
+                .map_err(|reason| self.refused(reason))?;
+        }
+        Ok(())
+    }
+
+    /// 至少有一个动作可以撤销；全部无法撤销的操作 (如清空回收站) 在撤销时被跳过
+    pub fn is_reversible(&self) -> bool {
+        self.actions.iter().any(Action::is_reversible)
+    }
+
+    fn refused(&self, reason: String) -> FmgError {
+        FmgError::UndoRefused {
+            id: self.id,
+            reason,
+        }
+    }
+}
+
+/// 操作日志
+#[derive(Debug, Clone)]
+pub struct Journal {
+    path: PathBuf,
+}
+
+impl Journal {
+    /// 使用 path 作为日志文件
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    /// 按 XDG 规范定位日志：`$XDG_STATE_HOME/fmg/journal.jsonl`
+    pub fn from_env() -> Option<Self> {
+        dirs::state_dir().map(|state| Self::new(state.join("fmg").join("journal.jsonl")))
+    }
+
+    /// 日志文件路径
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// 记录一次操作并返回其编号；没有动作时不记录
+    pub fn record(&self, command: &str, actions: Vec<Action>) -> Result<Option<u64>> {
+        if actions.is_empty() {
+            return Ok(None);
+        }
+        let id = self.history()?.last().map_or(1, |last| last.id + 1);
+        let operation = Operation {
+            id,
+            time: format_local(SystemTime::now(), "%Y-%m-%d %H:%M:%S"),
+            command: command.to_string(),
+            actions,
+            undone: false,
+        };
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent).map_err(|e| FmgError::from_io(parent, e))?;
+        }
+        let mut line = serde_json::to_string(&operation).map_err(|e| self.invalid(e))?;
+        line.push('\n');
+        File::options()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .and_then(|mut file| file.write_all(line.as_bytes()))
+            .map_err(|e| FmgError::from_io(&self.path, e))?;
+        Ok(Some(id))
+    }
+
+    /// 所有记录的操作，按执行顺序排列
+    pub fn history(&self) -> Result<Vec<Operation>> {
+        let file = match File::open(&self.path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(FmgError::from_io(&self.path, e)),
+        };
+        let mut operations = Vec::new();
+        for line in BufReader::new(file).lines() {
+            let line = line.map_err(|e| FmgError::from_io(&self.path, e))?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            operations.push(serde_json::from_str(&line).map_err(|e| self.invalid(e))?);
+        }
+        Ok(operations)
+    }
+
+    /// 撤销最近一次尚未撤销的操作；没有可撤销的操作时返回 None
+    ///
+    /// 完全无法撤销的操作会被跳过，不会挡住更早的操作。
+    pub fn undo(&self, trash: &Trash) -> Result<Option<Operation>> {
+        let mut operations = self.history()?;
+        let Some(index) = operations
+            .iter()
+            .rposition(|op| !op.undone && op.is_reversible())
+        else {
+            return Ok(None);
+        };
+        operations[index].check()?;
+
+        let reverted = operations[index].clone();
+        let mut result = Ok(());
+        while let Some(action) = operations[index].actions.last() {
+            if let Err(e) = action.revert(trash) {
+                result = Err(e);
+                break;
+            }
+            operations[index].actions.pop();
+        }
+        // 全部撤销后保留动作供 `fmg history` 显示；部分失败时只保留尚未撤销的动作，
+        // 以便修复问题后再次撤销
+        if result.is_ok() {
+            operations[index] = Operation {
+                undone: true,
+                ..reverted.clone()
+            };
+        }
+        self.rewrite(&operations)?;
+        result.map_err(|e| FmgError::UndoRefused {
+            id: reverted.id,
+            reason: e.to_string(),
+        })?;
+        Ok(Some(reverted))
+    }
+
+    /// 以临时文件加重命名的方式整体重写日志
+    fn rewrite(&self, operations: &[Operation]) -> Result<()> {
+        let mut contents = String::new();
+        for operation in operations {
+            contents.push_str(&serde_json::to_string(operation).map_err(|e| self.invalid(e))?);
+            contents.push('\n');
+        }
+        let temp = self.path.with_extension("jsonl.tmp");
+        fs::write(&temp, contents)
+            .and_then(|_| fs::rename(&temp, &self.path))
+            .map_err(|e| FmgError::from_io(&self.path, e))
+    }
+
+    fn invalid(&self, error: serde_json::Error) -> FmgError {
+        FmgError::InvalidConfig {
+            path: self.path.clone(),
+            message: error.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_undo_and_refuse() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        fs::create_dir_all(dir.join("src")).unwrap();
+        let journal = Journal::new(dir.join("state/journal.jsonl"));
+        let trash = Trash::new(dir.join("Trash"));
+
+        // 移动后撤销
+        fs::write(dir.join("src/a.txt"), "a").unwrap();
+        fs::rename(dir.join("src/a.txt"), dir.join("b.txt")).unwrap();
+        let action = Action::moved(&dir.join("src/a.txt"), &dir.join("b.txt")).unwrap();
+        assert_eq!(journal.record("move", vec![action]).unwrap(), Some(1));
+
+        // 移入回收站后目标被修改，撤销被拒绝
+        fs::write(dir.join("src/c.txt"), "c").unwrap();
+        let item = trash.put(&dir.join("src/c.txt")).unwrap();
+        let action = Action::trashed(item.clone()).unwrap();
+        journal.record("rm", vec![action]).unwrap();
+        let modified = fs::metadata(item.file_path()).unwrap().modified().unwrap();
+        fs::write(item.file_path(), "changed").unwrap();
+        let err = journal.undo(&trash).unwrap_err();
+        assert!(matches!(err, FmgError::UndoRefused { id: 2, .. }));
+        assert!(err.to_string().contains("has changed since"));
+
+        // 恢复原样后可以撤销
+        fs::write(item.file_path(), "c").unwrap();
+        File::options()
+            .write(true)
+            .open(item.file_path())
+            .and_then(|file| file.set_modified(modified))
+            .unwrap();
+        assert_eq!(journal.undo(&trash).unwrap().unwrap().id, 2);
+        assert_eq!(fs::read_to_string(dir.join("src/c.txt")).unwrap(), "c");
+
+        assert_eq!(journal.undo(&trash).unwrap().unwrap().id, 1);
+        assert_eq!(fs::read_to_string(dir.join("src/a.txt")).unwrap(), "a");
+        assert!(journal.undo(&trash).unwrap().is_none());
+        let history = journal.history().unwrap();
+        assert!(history.iter().all(|op| op.undone && op.actions.len() == 1));
+
+        // 永久删除无法撤销，撤销时跳过它，更早的操作仍可撤销
+        fs::rename(dir.join("src/a.txt"), dir.join("b.txt")).unwrap();
+        let action = Action::moved(&dir.join("src/a.txt"), &dir.join("b.txt")).unwrap();
+        journal.record("move", vec![action]).unwrap();
+        let deleted = Action::Deleted {
+            path: dir.join("gone"),
+        };
+        journal.record("rm", vec![deleted]).unwrap();
+        assert_eq!(journal.undo(&trash).unwrap().unwrap().id, 3);
+        assert!(journal.undo(&trash).unwrap().is_none());
+        assert!(!journal.history().unwrap()[3].undone);
+
+        // 同时包含无法撤销的动作时整个操作拒绝撤销
+        fs::write(dir.join("new.txt"), "new").unwrap();
+        let actions = vec![
+            Action::created(&dir.join("new.txt")).unwrap(),
+            Action::Replaced {
+                path: dir.join("old.txt"),
+            },
+        ];
+        journal.record("copy", actions).unwrap();
+        let err = journal.undo(&trash).unwrap_err();
+        assert!(err.to_string().contains("cannot be recovered"));
+    }
+}
@@ -1,4 +1,19 @@
 //! 核心模块 - 包含文件管理的核心功能
 
+pub mod copy;
+pub mod dirs;
+pub mod error;
 pub mod file_ops;
+pub mod filter;
+pub mod grep;
+pub mod journal;
 pub mod models;
+pub mod move_ops;
+pub mod rename;
+pub mod search;
+pub mod sort;
+pub mod stat;
+pub mod trash;
+pub mod usage;
+pub mod users;
+pub(crate) mod walker;
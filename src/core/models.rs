@@ -1,3 +1,303 @@
 //! 核心数据模型
 
-// 这里将包含项目中使用的核心数据结构和模型
+use std::ffi::OsString;
+use std::fmt;
+use std::fs::{FileType, Metadata};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// 文件类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntryKind {
+    File,
+    Dir,
+    Symlink,
+    Fifo,
+    Socket,
+    CharDevice,
+    BlockDevice,
+    Other,
+}
+
+impl EntryKind {
+    /// 根据 (不跟随符号链接的) 文件类型判断
+    pub fn from_file_type(file_type: &FileType) -> Self {
+        if file_type.is_symlink() {
+            return EntryKind::Symlink;
+        }
+        if file_type.is_dir() {
+            return EntryKind::Dir;
+        }
+        if file_type.is_file() {
+            return EntryKind::File;
+        }
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::FileTypeExt;
+            if file_type.is_fifo() {
+                return EntryKind::Fifo;
+            }
+            if file_type.is_socket() {
+                return EntryKind::Socket;
+            }
+            if file_type.is_char_device() {
+                return EntryKind::CharDevice;
+            }
+            if file_type.is_block_device() {
+                return EntryKind::BlockDevice;
+            }
+        }
+        EntryKind::Other
+    }
+
+    /// 类型名称，用于结构化输出
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EntryKind::File => "file",
+            EntryKind::Dir => "dir",
+            EntryKind::Symlink => "symlink",
+            EntryKind::Fifo => "fifo",
+            EntryKind::Socket => "socket",
+            EntryKind::CharDevice => "char_device",
+            EntryKind::BlockDevice => "block_device",
+            EntryKind::Other => "other",
+        }
+    }
+
+    /// 对应的 st_mode 文件类型位
+    #[cfg(not(unix))]
+    fn mode_bits(&self) -> u32 {
+        match self {
+            EntryKind::File => 0o100000,
+            EntryKind::Dir => 0o040000,
+            EntryKind::Symlink => 0o120000,
+            EntryKind::Fifo => 0o010000,
+            EntryKind::Socket => 0o140000,
+            EntryKind::CharDevice => 0o020000,
+            EntryKind::BlockDevice => 0o060000,
+            EntryKind::Other => 0,
+        }
+    }
+}
+
+/// 单个目录项及其元数据
+#[derive(Debug, Clone)]
+pub struct Entry {
+    /// 文件名
+    pub name: OsString,
+    /// 完整路径
+    pub path: PathBuf,
+    /// 文件类型 (不跟随符号链接)
+    pub kind: EntryKind,
+    /// 文件大小 (字节)
+    pub size: u64,
+    /// st_mode，包含文件类型位与权限位；非 Unix 平台由类型与只读属性合成
+    pub mode: u32,
+    /// 硬链接数
+    pub nlink: u64,
+    /// 属主 uid
+    pub uid: u32,
+    /// 属组 gid
+    pub gid: u32,
+    /// 修改时间
+    pub modified: Option<SystemTime>,
+    /// 访问时间
+    pub accessed: Option<SystemTime>,
+    /// 创建时间
+    pub created: Option<SystemTime>,
+    /// 状态改变时间 (ctime)
+    pub changed: Option<SystemTime>,
+    /// 所在设备号 (非 Unix 平台为 0)
+    pub dev: u64,
+    /// inode 号 (非 Unix 平台为 0)
+    pub ino: u64,
+    /// 符号链接指向的路径
+    pub symlink_target: Option<PathBuf>,
+    /// 符号链接目标的类型，链接失效或不是符号链接时为 None
+    pub target_kind: Option<EntryKind>,
+    /// 在树中的深度，根目录的直接子项为 0
+    pub depth: usize,
+}
+
+impl Entry {
+    /// 读取路径的元数据 (不跟随符号链接) 并构建目录项
+    pub fn from_path(path: &Path, depth: usize) -> io::Result<Self> {
+        let metadata = std::fs::symlink_metadata(path)?;
+        Ok(Self::from_metadata(path, &metadata, depth))
+    }
+
+    /// 根据已有的 (不跟随符号链接的) 元数据构建目录项
+    pub fn from_metadata(path: &Path, metadata: &Metadata, depth: usize) -> Self {
+        let kind = EntryKind::from_file_type(&metadata.file_type());
+        let (symlink_target, target_kind) = if kind == EntryKind::Symlink {
+            (
+                std::fs::read_link(path).ok(),
+                std::fs::metadata(path)
+                    .ok()
+                    .map(|m| EntryKind::from_file_type(&m.file_type())),
+            )
+        } else {
+            (None, None)
+        };
+        let (mode, nlink, uid, gid, changed) = unix_fields(kind, metadata);
+        let (dev, ino) = file_id(metadata);
+
+        Self {
+            name: path
+                .file_name()
+                .map(|n| n.to_os_string())
+                .unwrap_or_else(|| path.as_os_str().to_os_string()),
+            path: path.to_path_buf(),
+            kind,
+            size: metadata.len(),
+            mode,
+            nlink,
+            uid,
+            gid,
+            modified: metadata.modified().ok(),
+            accessed: metadata.accessed().ok(),
+            created: metadata.created().ok(),
+            changed,
+            dev,
+            ino,
+            symlink_target,
+            target_kind,
+            depth,
+        }
+    }
+
+    /// 跟随符号链接，返回以链接目标的元数据构建的目录项 (名称与路径不变)
+    ///
+    /// 不是符号链接或链接失效时原样返回。
+    pub fn follow(self) -> Self {
+        if self.kind != EntryKind::Symlink {
+            return self;
+        }
+        match std::fs::metadata(&self.path) {
+            Ok(metadata) => {
+                let mut followed = Self::from_metadata(&self.path, &metadata, self.depth);
+                followed.name = self.name;
+                followed
+            }
+            Err(_) => self,
+        }
+    }
+
+    /// 是否为失效的符号链接
+    pub fn is_broken_link(&self) -> bool {
+        self.kind == EntryKind::Symlink && self.target_kind.is_none()
+    }
+
+    /// 是否为目录，指向目录的符号链接也视为目录
+    pub fn is_dir(&self) -> bool {
+        self.kind == EntryKind::Dir || self.target_kind == Some(EntryKind::Dir)
+    }
+
+    /// 文件名的字符串形式，非法 UTF-8 字符以替换字符显示
+    pub fn name_lossy(&self) -> std::borrow::Cow<'_, str> {
+        self.name.to_string_lossy()
+    }
+}
+
+/// 从元数据中提取 mode、硬链接数、uid、gid 与 ctime
+#[cfg(unix)]
+fn unix_fields(_kind: EntryKind, metadata: &Metadata) -> (u32, u64, u32, u32, Option<SystemTime>) {
+    use std::os::unix::fs::MetadataExt;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    // 纳秒部分总是非负，早于纪元的时间先减去整秒再加上纳秒
+    let secs = metadata.ctime();
+    let nanos = Duration::from_nanos(metadata.ctime_nsec() as u64);
+    let changed = if secs >= 0 {
+        UNIX_EPOCH.checked_add(Duration::from_secs(secs as u64) + nanos)
+    } else {
+        UNIX_EPOCH
+            .checked_sub(Duration::from_secs(secs.unsigned_abs()))
+            .and_then(|time| time.checked_add(nanos))
+    };
+    (
+        metadata.mode(),
+        metadata.nlink(),
+        metadata.uid(),
+        metadata.gid(),
+        changed,
+    )
+}
+
+/// 设备号与 inode 号，用于识别同一个文件
+#[cfg(unix)]
+fn file_id(metadata: &Metadata) -> (u64, u64) {
+    use std::os::unix::fs::MetadataExt;
+    (metadata.dev(), metadata.ino())
+}
+
+/// 设备号与 inode 号 (非 Unix 平台不可用)
+#[cfg(not(unix))]
+fn file_id(_metadata: &Metadata) -> (u64, u64) {
+    (0, 0)
+}
+
+/// 从元数据中提取 mode、硬链接数、uid、gid 与 ctime (非 Unix 平台按类型与只读属性合成)
+#[cfg(not(unix))]
+fn unix_fields(kind: EntryKind, metadata: &Metadata) -> (u32, u64, u32, u32, Option<SystemTime>) {
+    let perms = if metadata.permissions().readonly() {
+        0o555
+    } else {
+        0o777
+    };
+    (kind.mode_bits() | perms, 1, 0, 0, metadata.created().ok())
+}
+
+/// 目录树节点
+#[derive(Debug, Clone)]
+pub struct DirTree {
+    /// 节点本身
+    pub entry: Entry,
+    /// 子节点；未展开 (非目录或超出深度限制) 时为 None
+    pub children: Option<Vec<DirTree>>,
+}
+
+impl DirTree {
+    /// 树中除根节点外的目录项数量
+    pub fn descendant_count(&self) -> usize {
+        self.children
+            .iter()
+            .flatten()
+            .map(|child| 1 + child.descendant_count())
+            .sum()
+    }
+}
+
+/// 遍历过程中单个目录项的读取错误
+#[derive(Debug)]
+pub struct EntryError {
+    /// 出错的路径
+    pub path: PathBuf,
+    /// 底层 IO 错误
+    pub error: io::Error,
+}
+
+impl fmt::Display for EntryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.path.display(), self.error)
+    }
+}
+
+/// 单层目录的读取结果
+#[derive(Debug)]
+pub struct Listing {
+    /// 成功读取的目录项
+    pub entries: Vec<Entry>,
+    /// 读取失败的目录项
+    pub errors: Vec<EntryError>,
+}
+
+/// 目录树的遍历结果
+#[derive(Debug)]
+pub struct TreeListing {
+    /// 以被遍历目录为根的树
+    pub tree: DirTree,
+    /// 遍历过程中遇到的错误
+    pub errors: Vec<EntryError>,
+}
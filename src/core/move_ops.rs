@@ -0,0 +1,226 @@
+//! 移动模块：同一文件系统内直接重命名，跨文件系统时复制、校验后再删除源
+//!
+//! 跨文件系统时先复制到目标目录中的临时名称，校验通过后再重命名到目标位置，
+//! 因此目标要么是完整的新内容，要么保持原样。
+
+use crate::core::copy::{
+    CopyOptions, CopyProgress, check_not_inside, copy, existing_metadata, is_same_file, plan_copy,
+    resolve_targets, temp_path,
+};
+use crate::core::error::{FmgError, Result};
+use crate::core::models::{Entry, EntryKind};
+use std::ffi::{OsStr, OsString};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// 目标已存在时的处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ConflictPolicy {
+    /// 报错，不移动
+    #[default]
+    Fail,
+    /// 替换已存在的目标
+    Overwrite,
+    /// 保留已存在的目标，跳过该项
+    Skip,
+    /// 移动到 `name (1).ext` 等尚未使用的名称
+    Rename,
+}
+
+/// 单项移动的结果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MoveOutcome {
+    /// 在同一文件系统内重命名到该路径
+    Renamed(PathBuf),
+    /// 跨文件系统复制到该路径后删除了源
+    Copied(PathBuf),
+    /// 目标已存在，按策略跳过
+    Skipped,
+}
+
+/// 每个源对应的目标路径，规则与复制相同
+pub fn move_targets<P: AsRef<Path>>(sources: &[P], dest: &Path) -> Result<Vec<PathBuf>> {
+    resolve_targets(sources, dest)
+}
+
+/// 将 source 移动到 target；跨文件系统复制时向 progress 报告进度
+pub fn move_path(
+    source: &Path,
+    target: &Path,
+    conflict: ConflictPolicy,
+    progress: &mut dyn CopyProgress,
+) -> io::Result<MoveOutcome> {
+    let entry = Entry::from_path(source, 0)?;
+    let mut target = target.to_path_buf();
+    if let Some(existing) = existing_metadata(&target)? {
+        if is_same_file(&entry, &existing, &target) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "source and destination are the same file",
+            ));
+        }
+        match conflict {
+            ConflictPolicy::Fail => {
+                return Err(io::Error::new(
+                    io::ErrorKind::AlreadyExists,
+                    "destination already exists",
+                ));
+            }
+            ConflictPolicy::Skip => return Ok(MoveOutcome::Skipped),
+            ConflictPolicy::Rename => target = free_name(&target),
+            ConflictPolicy::Overwrite => {}
+        }
+    }
+    if entry.kind == EntryKind::Dir {
+        check_not_inside(source, &target)?;
+    }
+
+    match fs::rename(source, &target) {
+        Ok(()) => Ok(MoveOutcome::Renamed(target)),
+        Err(e) if e.kind() == io::ErrorKind::CrossesDevices => {
+            move_across(source, &target, progress)?;
+            Ok(MoveOutcome::Copied(target))
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// 跨文件系统移动：复制到临时名称并校验，重命名到目标后删除源
+fn move_across(source: &Path, target: &Path, progress: &mut dyn CopyProgress) -> io::Result<()> {
+    let temp = temp_path(target);
+    let options = CopyOptions {
+        preserve: true,
+        verify: true,
+        ..CopyOptions::default()
+    };
+    let plan = plan_copy(&[source], &temp, &options).map_err(into_io)?;
+    let report = copy(&plan, &options, progress);
+
+    let mut errors = plan.errors.into_iter().chain(report.errors);
+    if let Some(first) = errors.next() {
+        let _ = remove_all(&temp);
+        return Err(io::Error::new(
+            first.error.kind(),
+            format!("{}: {}", first.path.display(), first.error),
+        ));
+    }
+    if let Err(e) = fs::rename(&temp, target) {
+        let _ = remove_all(&temp);
+        return Err(e);
+    }
+    remove_all(source).map_err(|e| {
+        io::Error::new(
+            e.kind(),
+            format!("copied, but the source could not be removed: {}", e),
+        )
+    })
+}
+
+/// 删除文件、链接或整个目录树
+pub fn remove_all(path: &Path) -> io::Result<()> {
+    if fs::symlink_metadata(path)?.is_dir() {
+        fs::remove_dir_all(path)
+    } else {
+        fs::remove_file(path)
+    }
+}
+
+/// 将库错误转换回 IO 错误，保留原始的错误类型
+fn into_io(error: FmgError) -> io::Error {
+    match error {
+        FmgError::Io { source, .. } => source,
+        other => io::Error::other(other.to_string()),
+    }
+}
+
+/// 在 target 所在目录中找到未被占用的名称：`report (1).tar.gz`、`report (2).tar.gz`…
+pub fn free_name(target: &Path) -> PathBuf {
+    let name = target.file_name().unwrap_or_default();
+    (1..)
+        .map(|n| target.with_file_name(numbered_name(name, n)))
+        .find(|candidate| fs::symlink_metadata(candidate).is_err())
+        .expect("ran out of candidate names")
+}
+
+/// 在扩展名之前插入编号，例如 `report (2).tar.gz`；`.bashrc` 这类隐藏文件变为 `.bashrc (2)`
+pub(crate) fn numbered_name(name: &OsStr, n: u32) -> OsString {
+    let Some(name) = name.to_str() else {
+        let mut numbered = name.to_os_string();
+        numbered.push(format!(" ({})", n));
+        return numbered;
+    };
+    // 跳过开头的点，不把整个隐藏文件名当作扩展名
+    let (stem, extension) = match name.char_indices().skip(1).find(|&(_, c)| c == '.') {
+        Some((i, _)) => name.split_at(i),
+        None => (name, ""),
+    };
+    OsString::from(format!("{} ({}){}", stem, n, extension))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn no_progress() -> impl CopyProgress {
+        |_: &Entry, _: u64| {}
+    }
+
+    #[test]
+    fn test_move_conflict_policies() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        let source = dir.join("report.tar.gz");
+        let target = dir.join("out.tar.gz");
+        fs::write(&source, "new").unwrap();
+        fs::write(&target, "old").unwrap();
+
+        let err =
+            move_path(&source, &target, ConflictPolicy::Fail, &mut no_progress()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+        let outcome =
+            move_path(&source, &target, ConflictPolicy::Skip, &mut no_progress()).unwrap();
+        assert_eq!(outcome, MoveOutcome::Skipped);
+        assert!(source.exists());
+
+        let outcome =
+            move_path(&source, &target, ConflictPolicy::Rename, &mut no_progress()).unwrap();
+        assert_eq!(outcome, MoveOutcome::Renamed(dir.join("out (1).tar.gz")));
+        assert_eq!(fs::read_to_string(&target).unwrap(), "old");
+
+        let renamed = dir.join("out (1).tar.gz");
+        let outcome = move_path(
+            &renamed,
+            &target,
+            ConflictPolicy::Overwrite,
+            &mut no_progress(),
+        )
+        .unwrap();
+        assert_eq!(outcome, MoveOutcome::Renamed(target.clone()));
+        assert_eq!(fs::read_to_string(&target).unwrap(), "new");
+        assert!(!renamed.exists());
+
+        assert_eq!(free_name(&dir.join(".bashrc")), dir.join(".bashrc (1)"));
+    }
+
+    #[test]
+    fn test_move_across_filesystems() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        let source = dir.join("build");
+        fs::create_dir_all(source.join("lib")).unwrap();
+        fs::write(source.join("lib/out.o"), "object").unwrap();
+        let target = dir.join("dist");
+
+        // 直接调用跨文件系统的路径，不依赖测试环境中存在两个文件系统
+        let mut copied = 0;
+        move_across(&source, &target, &mut |_: &Entry, bytes| copied += bytes).unwrap();
+        assert_eq!(copied, 6);
+        assert!(!source.exists());
+        assert_eq!(
+            fs::read_to_string(target.join("lib/out.o")).unwrap(),
+            "object"
+        );
+        assert!(!temp_path(&target).exists());
+    }
+}
@@ -0,0 +1,579 @@
+//! 批量重命名模块：正则替换、模板与大小写转换
+//!
+//! 先由 [`plan_rename`] 或 [`plan_from_names`] 计算新名称并检查冲突，再由
+//! [`apply_rename`] 执行。链式重命名 (a→b、b→c) 按依赖顺序执行，环 (a→b、b→a)
+//! 通过同目录中的临时名称打开。
+
+use crate::core::copy::{existing_metadata, is_same_file};
+use crate::core::error::{FmgError, Result};
+use crate::core::models::{Entry, EntryError};
+use crate::display::time::format_local;
+use clap::ValueEnum;
+use regex::{Regex, RegexBuilder};
+use std::collections::{HashMap, HashSet};
+use std::ffi::OsString;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// 大小写转换 (变体注释会作为命令行帮助显示)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum CaseConversion {
+    /// all lowercase
+    Lower,
+    /// ALL UPPERCASE
+    Upper,
+    /// Capitalize Each Word (the extension is left alone)
+    Title,
+}
+
+/// sed 风格的正则替换：`s/模式/替换/标志`，标志 g 替换全部匹配，i 忽略大小写
+///
+/// 替换文本中以 `$1`、`${name}` 引用分组。`s` 之后的第一个字符为分隔符。
+#[derive(Debug, Clone)]
+pub struct Substitution {
+    regex: Regex,
+    replacement: String,
+    global: bool,
+}
+
+impl Substitution {
+    /// 替换名称中的匹配部分
+    pub fn apply(&self, name: &str) -> String {
+        if self.global {
+            self.regex.replace_all(name, &self.replacement).into_owned()
+        } else {
+            self.regex.replace(name, &self.replacement).into_owned()
+        }
+    }
+}
+
+impl FromStr for Substitution {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let mut chars = s.chars();
+        let delimiter = match (chars.next(), chars.next()) {
+            (Some('s'), Some(d)) if !d.is_alphanumeric() && d != '\\' => d,
+            _ => return Err("expected 's/PATTERN/REPLACEMENT/[FLAGS]'".to_string()),
+        };
+
+        // 拆分为三段，`\分隔符` 表示分隔符本身，其他转义原样交给正则引擎
+        let mut parts = vec![String::new()];
+        let mut escaped = false;
+        for c in chars {
+            let part = parts.last_mut().unwrap();
+            if escaped {
+                if c != delimiter {
+                    part.push('\\');
+                }
+                part.push(c);
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == delimiter {
+                parts.push(String::new());
+            } else {
+                part.push(c);
+            }
+        }
+        if escaped {
+            parts.last_mut().unwrap().push('\\');
+        }
+        let [pattern, replacement, flags] = <[String; 3]>::try_from(parts)
+            .map_err(|_| "expected 's/PATTERN/REPLACEMENT/[FLAGS]'".to_string())?;
+
+        let mut global = false;
+        let mut builder = RegexBuilder::new(&pattern);
+        for flag in flags.chars() {
+            match flag {
+                'g' => global = true,
+                'i' => {
+                    builder.case_insensitive(true);
+                }
+                _ => return Err(format!("unknown flag '{}' (use g or i)", flag)),
+            }
+        }
+        let regex = builder.build().map_err(|e| e.to_string())?;
+        Ok(Self {
+            regex,
+            replacement,
+            global,
+        })
+    }
+}
+
+/// 模板中的一段
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum TemplatePart {
+    Literal(String),
+    /// `{name}`：不含扩展名的名称
+    Stem,
+    /// `{ext}`：包含点的扩展名，没有扩展名时为空
+    Extension,
+    /// `{n}` 或 `{n:03}`：计数器与最小宽度 (以 0 填充)
+    Counter(usize),
+    /// `{date}` 或 `{date:%Y%m%d}`：修改时间 (本地时间)
+    Date(String),
+}
+
+/// 新名称的模板，例如 `{date}-{n:03}{ext}`；`{{` 与 `}}` 表示花括号本身
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Template {
+    parts: Vec<TemplatePart>,
+}
+
+impl Template {
+    /// 按模板生成名称；counter 为计数器的当前值
+    fn render(&self, name: &str, entry: &Entry, counter: u64) -> String {
+        let (stem, extension) = split_extension(name);
+        let mut output = String::new();
+        for part in &self.parts {
+            match part {
+                TemplatePart::Literal(text) => output.push_str(text),
+                TemplatePart::Stem => output.push_str(stem),
+                TemplatePart::Extension => output.push_str(extension),
+                TemplatePart::Counter(width) => {
+                    output.push_str(&format!("{:0width$}", counter, width = width))
+                }
+                TemplatePart::Date(format) => {
+                    if let Some(modified) = entry.modified {
+                        output.push_str(&format_local(modified, format));
+                    }
+                }
+            }
+        }
+        output
+    }
+}
+
+impl FromStr for Template {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let mut parts = Vec::new();
+        let mut literal = String::new();
+        let mut chars = s.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    literal.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    literal.push('}');
+                }
+                '{' => {
+                    let mut placeholder = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => placeholder.push(c),
+                            None => return Err(format!("unclosed '{{{}'", placeholder)),
+                        }
+                    }
+                    if !literal.is_empty() {
+                        parts.push(TemplatePart::Literal(std::mem::take(&mut literal)));
+                    }
+                    parts.push(parse_placeholder(&placeholder)?);
+                }
+                '}' => return Err("unmatched '}' (use '}}' for a literal brace)".to_string()),
+                c => literal.push(c),
+            }
+        }
+        if !literal.is_empty() {
+            parts.push(TemplatePart::Literal(literal));
+        }
+        Ok(Self { parts })
+    }
+}
+
+/// 解析 `{...}` 中的占位符
+fn parse_placeholder(placeholder: &str) -> std::result::Result<TemplatePart, String> {
+    let (key, argument) = match placeholder.split_once(':') {
+        Some((key, argument)) => (key, Some(argument)),
+        None => (placeholder, None),
+    };
+    match (key, argument) {
+        ("name", None) => Ok(TemplatePart::Stem),
+        ("ext", None) => Ok(TemplatePart::Extension),
+        ("n", None) => Ok(TemplatePart::Counter(0)),
+        ("n", Some(width)) => width
+            .parse()
+            .map(TemplatePart::Counter)
+            .map_err(|_| format!("invalid counter width '{}'", width)),
+        ("date", None) => Ok(TemplatePart::Date("%Y-%m-%d".to_string())),
+        ("date", Some(format)) => Ok(TemplatePart::Date(format.to_string())),
+        _ => Err(format!(
+            "unknown placeholder '{{{}}}' (use {{name}}, {{ext}}, {{n}}, {{n:03}}, {{date}} or {{date:%Y%m%d}})",
+            placeholder
+        )),
+    }
+}
+
+/// 拆分名称与扩展名 (包含点)；`.bashrc` 这类隐藏文件没有扩展名
+fn split_extension(name: &str) -> (&str, &str) {
+    match name.rfind('.') {
+        Some(i) if i > 0 => name.split_at(i),
+        _ => (name, ""),
+    }
+}
+
+/// 批量重命名选项，依次应用正则替换、模板与大小写转换
+#[derive(Debug, Clone)]
+pub struct RenameOptions {
+    pub substitution: Option<Substitution>,
+    pub template: Option<Template>,
+    pub case: Option<CaseConversion>,
+    /// 计数器的起始值
+    pub start: u64,
+}
+
+impl Default for RenameOptions {
+    fn default() -> Self {
+        Self {
+            substitution: None,
+            template: None,
+            case: None,
+            start: 1,
+        }
+    }
+}
+
+impl RenameOptions {
+    /// 计算新名称
+    fn new_name(&self, name: &str, entry: &Entry, counter: u64) -> String {
+        let mut name = match &self.substitution {
+            Some(substitution) => substitution.apply(name),
+            None => name.to_string(),
+        };
+        if let Some(template) = &self.template {
+            name = template.render(&name, entry, counter);
+        }
+        match self.case {
+            Some(CaseConversion::Lower) => name.to_lowercase(),
+            Some(CaseConversion::Upper) => name.to_uppercase(),
+            Some(CaseConversion::Title) => {
+                let (stem, extension) = split_extension(&name);
+                format!("{}{}", title_case(stem), extension)
+            }
+            None => name,
+        }
+    }
+}
+
+/// 每个单词首字母大写，其余小写；空白、`-`、`_` 与 `.` 分隔单词
+fn title_case(text: &str) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut word_start = true;
+    for c in text.chars() {
+        if word_start {
+            output.extend(c.to_uppercase());
+        } else {
+            output.extend(c.to_lowercase());
+        }
+        word_start = c.is_whitespace() || matches!(c, '-' | '_' | '.');
+    }
+    output
+}
+
+/// 一项重命名
+#[derive(Debug, Clone)]
+pub struct Rename {
+    pub entry: Entry,
+    /// 新路径，与原路径位于同一目录
+    pub target: PathBuf,
+}
+
+/// 重命名计划，只包含名称发生变化的项目
+#[derive(Debug)]
+pub struct RenamePlan {
+    pub renames: Vec<Rename>,
+    /// 名称不变的项目数
+    pub unchanged: usize,
+}
+
+impl RenamePlan {
+    /// 需要借助临时名称打开的环的数量
+    pub fn cycles(&self) -> usize {
+        let moves: HashMap<&Path, &Path> = self
+            .renames
+            .iter()
+            .map(|r| (r.entry.path.as_path(), r.target.as_path()))
+            .collect();
+        let mut visited = HashSet::new();
+        let mut cycles = 0;
+        for start in moves.keys() {
+            let mut path = *start;
+            let mut chain = HashSet::new();
+            while let Some(&next) = moves.get(path) {
+                if !chain.insert(path) || visited.contains(path) {
+                    break;
+                }
+                path = next;
+            }
+            if chain.contains(path) && !visited.contains(path) {
+                cycles += 1;
+            }
+            visited.extend(chain);
+        }
+        cycles
+    }
+}
+
+/// 按选项计算 entries 的新名称；计数器按 entries 的顺序递增
+pub fn plan_rename(entries: Vec<Entry>, options: &RenameOptions) -> Result<RenamePlan> {
+    let mut names = Vec::with_capacity(entries.len());
+    for (counter, entry) in (options.start..).zip(&entries) {
+        let name = utf8_name(entry)?;
+        names.push(options.new_name(name, entry, counter));
+    }
+    plan_from_names(entries, names)
+}
+
+/// 以给定的新名称 (例如在编辑器中修改后的名称) 构建计划，names 与 entries 一一对应
+pub fn plan_from_names(entries: Vec<Entry>, names: Vec<String>) -> Result<RenamePlan> {
+    if entries.len() != names.len() {
+        return Err(FmgError::RenameConflict {
+            path: PathBuf::from("."),
+            message: format!("expected {} names, got {}", entries.len(), names.len()),
+        });
+    }
+    let mut renames = Vec::new();
+    let mut unchanged = 0;
+    for (entry, name) in entries.into_iter().zip(names) {
+        if entry.name == OsString::from(&name) {
+            unchanged += 1;
+            continue;
+        }
+        check_name(&entry, &name)?;
+        let target = entry.path.with_file_name(&name);
+        renames.push(Rename { entry, target });
+    }
+    check_collisions(&renames)?;
+    Ok(RenamePlan { renames, unchanged })
+}
+
+/// 名称必须为合法的 UTF-8 才能套用规则
+fn utf8_name(entry: &Entry) -> Result<&str> {
+    entry.name.to_str().ok_or_else(|| FmgError::RenameConflict {
+        path: entry.path.clone(),
+        message: "the name is not valid UTF-8".to_string(),
+    })
+}
+
+/// 新名称必须是当前目录中的单个名称
+fn check_name(entry: &Entry, name: &str) -> Result<()> {
+    let problem = if name.is_empty() {
+        "the new name is empty"
+    } else if name == "." || name == ".." {
+        "the new name is '.' or '..'"
+    } else if name.contains(['/', '\0']) || (cfg!(windows) && name.contains('\\')) {
+        "the new name contains a path separator or NUL"
+    } else {
+        return Ok(());
+    };
+    Err(FmgError::RenameConflict {
+        path: entry.path.clone(),
+        message: problem.to_string(),
+    })
+}
+
+/// 检查多个项目重命名为同一名称，或新名称已被不参与重命名的文件占用
+fn check_collisions(renames: &[Rename]) -> Result<()> {
+    let sources: HashSet<&Path> = renames.iter().map(|r| r.entry.path.as_path()).collect();
+    let mut targets: HashMap<&Path, &Rename> = HashMap::new();
+    for rename in renames {
+        if let Some(other) = targets.insert(&rename.target, rename) {
+            return Err(FmgError::RenameConflict {
+                path: rename.entry.path.clone(),
+                message: format!(
+                    "'{}' would also be renamed to '{}'",
+                    other.entry.path.display(),
+                    rename.target.display()
+                ),
+            });
+        }
+        if sources.contains(rename.target.as_path()) {
+            continue;
+        }
+        let existing =
+            existing_metadata(&rename.target).map_err(|e| FmgError::from_io(&rename.target, e))?;
+        // 不区分大小写的文件系统上 a.txt → A.txt 指向同一个文件
+        if existing.is_some_and(|m| !is_same_file(&rename.entry, &m, &rename.target)) {
+            return Err(FmgError::RenameConflict {
+                path: rename.entry.path.clone(),
+                message: format!("'{}' already exists", rename.target.display()),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// 重命名结果
+#[derive(Debug, Default)]
+pub struct RenameReport {
+    /// 成功重命名的项目数
+    pub renamed: usize,
+    pub errors: Vec<EntryError>,
+}
+
+/// 执行计划；每完成一步 (包括移入临时名称) 调用 on_step(原路径, 新路径)
+pub fn apply_rename(plan: &RenamePlan, on_step: &mut dyn FnMut(&Path, &Path)) -> RenameReport {
+    let mut report = RenameReport::default();
+    let mut pending: Vec<(PathBuf, PathBuf, &Path)> = plan
+        .renames
+        .iter()
+        .map(|r| {
+            (
+                r.entry.path.clone(),
+                r.target.clone(),
+                r.entry.path.as_path(),
+            )
+        })
+        .collect();
+    let mut temp_counter = 0;
+
+    while !pending.is_empty() {
+        // 先执行目标不再被其他待重命名项目占用的项
+        let ready = pending
+            .iter()
+            .position(|(_, to, _)| !pending.iter().any(|(from, _, _)| from == to));
+        let Some(index) = ready else {
+            // 只剩下环：把其中一项移到临时名称，环就变成了链
+            let (from, to, original) = &mut pending[0];
+            temp_counter += 1;
+            let temp = to.with_file_name(format!(
+                ".fmg-rename-{}-{}",
+                std::process::id(),
+                temp_counter
+            ));
+            match rename_no_clobber(from, &temp) {
+                Ok(()) => {
+                    on_step(from, &temp);
+                    *from = temp;
+                }
+                Err(error) => {
+                    report.errors.push(EntryError {
+                        path: original.to_path_buf(),
+                        error,
+                    });
+                    pending.remove(0);
+                }
+            }
+            continue;
+        };
+
+        let (from, to, original) = pending.remove(index);
+        match rename_no_clobber(&from, &to) {
+            Ok(()) => {
+                on_step(&from, &to);
+                report.renamed += 1;
+            }
+            Err(error) => report.errors.push(EntryError {
+                path: original.to_path_buf(),
+                error,
+            }),
+        }
+    }
+    report
+}
+
+/// 重命名，目标已被其他文件占用时报错
+fn rename_no_clobber(from: &Path, to: &Path) -> io::Result<()> {
+    if let Some(existing) = existing_metadata(to)? {
+        let entry = Entry::from_path(from, 0)?;
+        if !is_same_file(&entry, &existing, to) {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("'{}' already exists", to.display()),
+            ));
+        }
+    }
+    fs::rename(from, to)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entries(dir: &Path, names: &[&str]) -> Vec<Entry> {
+        names
+            .iter()
+            .map(|name| Entry::from_path(&dir.join(name), 0).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_rules() {
+        let substitution: Substitution = r"s/IMG_(\d+)/photo-$1/".parse().unwrap();
+        assert_eq!(substitution.apply("IMG_0042.JPG"), "photo-0042.JPG");
+        let substitution: Substitution = "s|a|\\||gi".parse().unwrap();
+        assert_eq!(substitution.apply("banAna"), "b|n|n|");
+        assert!("s/a/b".parse::<Substitution>().is_err());
+        assert!("s/a/b/x".parse::<Substitution>().is_err());
+
+        let template: Template = "{{{n:03}}}-{name}{ext}".parse().unwrap();
+        let entry = Entry::from_path(Path::new("."), 0).unwrap();
+        assert_eq!(template.render("a.tar.gz", &entry, 7), "{007}-a.tar.gz");
+        assert!("{size}".parse::<Template>().is_err());
+        assert!("{n".parse::<Template>().is_err());
+
+        let options = RenameOptions {
+            case: Some(CaseConversion::Title),
+            ..RenameOptions::default()
+        };
+        assert_eq!(
+            options.new_name("my holiday-pics.JPG", &entry, 1),
+            "My Holiday-Pics.JPG"
+        );
+    }
+
+    #[test]
+    fn test_collisions_and_cycles() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        for name in ["a", "b", "c", "keep"] {
+            fs::write(dir.join(name), name).unwrap();
+        }
+
+        // 两项重命名为同一名称，或新名称被其他文件占用
+        let names = vec!["x".to_string(), "x".to_string()];
+        let err = plan_from_names(entries(dir, &["a", "b"]), names).unwrap_err();
+        assert!(err.to_string().contains("would also be renamed"));
+        let err = plan_from_names(entries(dir, &["a"]), vec!["keep".to_string()]).unwrap_err();
+        assert!(err.to_string().contains("already exists"));
+        let err = plan_from_names(entries(dir, &["a"]), vec!["x/y".to_string()]).unwrap_err();
+        assert!(err.to_string().contains("path separator"));
+
+        // a→b、b→a 构成环，c→d 是普通重命名
+        let names = vec!["b".to_string(), "a".to_string(), "d".to_string()];
+        let plan = plan_from_names(entries(dir, &["a", "b", "c"]), names).unwrap();
+        assert_eq!(plan.cycles(), 1);
+        let mut steps = 0;
+        let report = apply_rename(&plan, &mut |_, _| steps += 1);
+        assert!(report.errors.is_empty());
+        assert_eq!(report.renamed, 3);
+        assert_eq!(steps, 4);
+        assert_eq!(fs::read_to_string(dir.join("a")).unwrap(), "b");
+        assert_eq!(fs::read_to_string(dir.join("b")).unwrap(), "a");
+        assert_eq!(fs::read_to_string(dir.join("d")).unwrap(), "c");
+
+        // 链式重命名按依赖顺序执行
+        let options = RenameOptions {
+            template: Some("{n}".parse().unwrap()),
+            start: 0,
+            ..RenameOptions::default()
+        };
+        fs::rename(dir.join("keep"), dir.join("0")).unwrap();
+        fs::rename(dir.join("d"), dir.join("1")).unwrap();
+        let plan = plan_rename(entries(dir, &["b", "0", "1"]), &options).unwrap();
+        assert_eq!(plan.cycles(), 0);
+        let report = apply_rename(&plan, &mut |_, _| {});
+        assert_eq!(report.renamed, 3);
+        assert_eq!(fs::read_to_string(dir.join("0")).unwrap(), "a");
+        assert_eq!(fs::read_to_string(dir.join("2")).unwrap(), "c");
+    }
+}
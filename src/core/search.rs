@@ -0,0 +1,437 @@
+//! 文件查找模块：按名称、类型、大小与时间筛选目录树
+
+use crate::core::error::{FmgError, Result};
+use crate::core::file_ops::{TreeOptions, walk_tree};
+use crate::core::filter::FilterOptions;
+use crate::core::models::{DirTree, Entry, EntryError, EntryKind};
+use crate::core::sort::SortOptions;
+use clap::ValueEnum;
+use globset::{Glob, GlobMatcher};
+use regex::Regex;
+use std::path::Path;
+use std::str::FromStr;
+use std::time::{Duration, SystemTime};
+
+/// 按类型查找 (变体注释会作为命令行帮助显示)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum TypeFilter {
+    /// Regular files
+    #[value(name = "f", alias = "file")]
+    File,
+    /// Directories
+    #[value(name = "d", alias = "dir")]
+    Dir,
+    /// Symbolic links
+    #[value(name = "l", alias = "symlink")]
+    Symlink,
+}
+
+impl TypeFilter {
+    /// 目录项是否为该类型
+    fn matches(self, kind: EntryKind) -> bool {
+        matches!(
+            (self, kind),
+            (TypeFilter::File, EntryKind::File)
+                | (TypeFilter::Dir, EntryKind::Dir)
+                | (TypeFilter::Symlink, EntryKind::Symlink)
+        )
+    }
+}
+
+/// 数值比较方式：`+N` 大于，`-N` 小于，`N` 等于
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Greater,
+    Less,
+    Equal,
+}
+
+/// 拆分前缀 `+`/`-`
+fn split_comparison(s: &str) -> (Comparison, &str) {
+    if let Some(rest) = s.strip_prefix('+') {
+        (Comparison::Greater, rest)
+    } else if let Some(rest) = s.strip_prefix('-') {
+        (Comparison::Less, rest)
+    } else {
+        (Comparison::Equal, s)
+    }
+}
+
+/// 拆分数字与单位后缀
+fn split_unit(s: &str) -> std::result::Result<(u64, &str), String> {
+    let end = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let number = s[..end]
+        .parse()
+        .map_err(|_| format!("expected a number in '{}'", s))?;
+    Ok((number, &s[end..]))
+}
+
+/// 大小条件，例如 `+10M` (大于 10 MiB)、`-4k` (小于 4 KiB)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SizeFilter {
+    pub comparison: Comparison,
+    /// 字节数
+    pub bytes: u64,
+}
+
+impl FromStr for SizeFilter {
+    type Err = String;
+
+    /// 单位为 c (字节，默认)、k、M、G、T (以 1024 为进制，不区分大小写)
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let (comparison, rest) = split_comparison(s);
+        let (number, unit) = split_unit(rest)?;
+        let multiplier: u64 = match unit.to_ascii_lowercase().as_str() {
+            "" | "c" | "b" => 1,
+            "k" => 1 << 10,
+            "m" => 1 << 20,
+            "g" => 1 << 30,
+            "t" => 1 << 40,
+            _ => {
+                return Err(format!(
+                    "unknown size unit '{}' (use c, k, M, G or T)",
+                    unit
+                ));
+            }
+        };
+        let bytes = number
+            .checked_mul(multiplier)
+            .ok_or_else(|| format!("size '{}' is too large", s))?;
+        Ok(Self { comparison, bytes })
+    }
+}
+
+impl SizeFilter {
+    fn matches(&self, size: u64) -> bool {
+        match self.comparison {
+            Comparison::Greater => size > self.bytes,
+            Comparison::Less => size < self.bytes,
+            Comparison::Equal => size == self.bytes,
+        }
+    }
+}
+
+/// 修改时间距今的条件，例如 `-7d` (7 天以内)、`+1w` (一周以前)、`2h` (2 到 3 小时之前)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AgeFilter {
+    pub comparison: Comparison,
+    /// 时长
+    pub age: Duration,
+    /// 单位时长，用于 `N` 形式的区间比较
+    pub unit: Duration,
+}
+
+impl FromStr for AgeFilter {
+    type Err = String;
+
+    /// 单位为 s、m、h、d (默认)、w
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let (comparison, rest) = split_comparison(s);
+        let (number, unit) = split_unit(rest)?;
+        let unit_secs: u64 = match unit {
+            "s" => 1,
+            "m" => 60,
+            "h" => 3600,
+            "" | "d" => 86400,
+            "w" => 7 * 86400,
+            _ => {
+                return Err(format!(
+                    "unknown time unit '{}' (use s, m, h, d or w)",
+                    unit
+                ));
+            }
+        };
+        let secs = number
+            .checked_mul(unit_secs)
+            .ok_or_else(|| format!("age '{}' is too large", s))?;
+        Ok(Self {
+            comparison,
+            age: Duration::from_secs(secs),
+            unit: Duration::from_secs(unit_secs),
+        })
+    }
+}
+
+impl AgeFilter {
+    /// 修改时间为 modified 的文件在 now 时刻是否满足条件，未来的时间视为刚修改
+    fn matches(&self, modified: SystemTime, now: SystemTime) -> bool {
+        let age = now.duration_since(modified).unwrap_or_default();
+        match self.comparison {
+            Comparison::Greater => age > self.age,
+            Comparison::Less => age < self.age,
+            Comparison::Equal => age >= self.age && age < self.age + self.unit,
+        }
+    }
+}
+
+/// 查找选项，所有条件同时满足的目录项才算匹配
+#[derive(Debug, Clone, Default)]
+pub struct FindOptions {
+    /// 文件名匹配的 glob
+    pub name: Option<String>,
+    /// 文件名匹配的正则表达式
+    pub regex: Option<String>,
+    /// 允许的类型，为空时不限制
+    pub types: Vec<TypeFilter>,
+    /// 大小条件
+    pub sizes: Vec<SizeFilter>,
+    /// 修改时间晚于该时间
+    pub newer: Option<SystemTime>,
+    /// 修改时间距今的条件
+    pub mtimes: Vec<AgeFilter>,
+    /// 最大深度，1 表示只查找起点目录的直接子项；None 表示不限制
+    pub max_depth: Option<usize>,
+    /// 排序选项，应用于每一层
+    pub sort: SortOptions,
+    /// 过滤选项，被过滤的目录不会进入
+    pub filter: FilterOptions,
+    /// 跟随符号链接
+    pub follow: bool,
+}
+
+/// 编译文件名 glob
+pub(crate) fn compile_glob(pattern: &str) -> Result<GlobMatcher> {
+    Glob::new(pattern)
+        .map(|glob| glob.compile_matcher())
+        .map_err(|e| FmgError::InvalidPattern {
+            pattern: pattern.to_string(),
+            message: e.kind().to_string(),
+        })
+}
+
+/// 编译正则表达式
+pub(crate) fn compile_regex(pattern: &str) -> Result<Regex> {
+    Regex::new(pattern).map_err(|e| FmgError::InvalidPattern {
+        pattern: pattern.to_string(),
+        message: e.to_string(),
+    })
+}
+
+/// 查找结果
+#[derive(Debug)]
+pub struct FindResult {
+    /// 匹配的目录项，按遍历顺序 (深度优先，每层已排序) 排列
+    pub matches: Vec<Entry>,
+    /// 只保留匹配项及其祖先目录的目录树
+    pub tree: DirTree,
+    /// 读取失败的目录项
+    pub errors: Vec<EntryError>,
+}
+
+/// 编译后的查找条件
+struct Matcher<'a> {
+    options: &'a FindOptions,
+    name: Option<GlobMatcher>,
+    regex: Option<Regex>,
+    now: SystemTime,
+}
+
+impl<'a> Matcher<'a> {
+    fn new(options: &'a FindOptions) -> Result<Self> {
+        let name = options.name.as_deref().map(compile_glob).transpose()?;
+        let regex = options.regex.as_deref().map(compile_regex).transpose()?;
+
+        Ok(Self {
+            options,
+            name,
+            regex,
+            now: SystemTime::now(),
+        })
+    }
+
+    fn is_match(&self, entry: &Entry) -> bool {
+        let options = self.options;
+        if let Some(name) = &self.name
+            && !name.is_match(Path::new(&entry.name))
+        {
+            return false;
+        }
+        if let Some(regex) = &self.regex
+            && !regex.is_match(&entry.name_lossy())
+        {
+            return false;
+        }
+        if !options.types.is_empty() && !options.types.iter().any(|t| t.matches(entry.kind)) {
+            return false;
+        }
+        if !options.sizes.iter().all(|size| size.matches(entry.size)) {
+            return false;
+        }
+
+        let modified = entry.modified.unwrap_or(SystemTime::UNIX_EPOCH);
+        if let Some(newer) = options.newer
+            && modified <= newer
+        {
+            return false;
+        }
+        options
+            .mtimes
+            .iter()
+            .all(|mtime| mtime.matches(modified, self.now))
+    }
+
+    /// 剪除不匹配且没有匹配后代的节点，同时按遍历顺序收集匹配项
+    fn prune(&self, node: DirTree, matches: &mut Vec<Entry>) -> Option<DirTree> {
+        let is_match = self.is_match(&node.entry);
+        if is_match {
+            matches.push(node.entry.clone());
+        }
+        let children: Option<Vec<DirTree>> = node.children.map(|children| {
+            children
+                .into_iter()
+                .filter_map(|child| self.prune(child, matches))
+                .collect()
+        });
+
+        let has_children = children.as_ref().is_some_and(|c| !c.is_empty());
+        (is_match || has_children).then_some(DirTree {
+            entry: node.entry,
+            children,
+        })
+    }
+}
+
+/// 在目录树中查找满足全部条件的目录项
+pub fn find<P: AsRef<Path>>(path: P, options: &FindOptions) -> Result<FindResult> {
+    let matcher = Matcher::new(options)?;
+
+    // 与 find -maxdepth 0 一样只检查起点本身
+    if options.max_depth == Some(0) {
+        let root =
+            Entry::from_path(path.as_ref(), 0).map_err(|e| FmgError::from_io(path.as_ref(), e))?;
+        let matches = if matcher.is_match(&root) {
+            vec![root.clone()]
+        } else {
+            Vec::new()
+        };
+        return Ok(FindResult {
+            matches,
+            tree: DirTree {
+                entry: root,
+                children: Some(Vec::new()),
+            },
+            errors: Vec::new(),
+        });
+    }
+
+    let tree_options = TreeOptions {
+        // 树的深度从 0 开始计数，且深度为 max_depth 的目录不再展开
+        max_depth: options.max_depth.map(|depth| depth - 1),
+        sort: options.sort,
+        filter: options.filter.clone(),
+        du: false,
+        follow: options.follow,
+    };
+    let listing = walk_tree(path, &tree_options)?;
+
+    let mut matches = Vec::new();
+    let children = listing
+        .tree
+        .children
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|child| matcher.prune(child, &mut matches))
+        .collect();
+
+    Ok(FindResult {
+        matches,
+        tree: DirTree {
+            entry: listing.tree.entry,
+            children: Some(children),
+        },
+        errors: listing.errors,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_size_filter() {
+        let size: SizeFilter = "+10M".parse().unwrap();
+        assert_eq!(size.comparison, Comparison::Greater);
+        assert_eq!(size.bytes, 10 << 20);
+        assert!(size.matches((10 << 20) + 1) && !size.matches(10 << 20));
+        assert_eq!("-4k".parse::<SizeFilter>().unwrap().bytes, 4096);
+        assert_eq!(
+            "512".parse::<SizeFilter>().unwrap().comparison,
+            Comparison::Equal
+        );
+        assert!("+10X".parse::<SizeFilter>().is_err());
+        assert!("big".parse::<SizeFilter>().is_err());
+    }
+
+    #[test]
+    fn test_age_filter() {
+        let now = SystemTime::now();
+        let days_ago = |days: u64| now - Duration::from_secs(days * 86400 + 60);
+        let within: AgeFilter = "-7d".parse().unwrap();
+        assert!(within.matches(days_ago(1), now) && !within.matches(days_ago(8), now));
+        let older: AgeFilter = "+1w".parse().unwrap();
+        assert!(older.matches(days_ago(8), now) && !older.matches(days_ago(6), now));
+        let exact: AgeFilter = "2".parse().unwrap();
+        assert!(exact.matches(days_ago(2), now) && !exact.matches(days_ago(3), now));
+        assert!("7y".parse::<AgeFilter>().is_err());
+    }
+
+    #[test]
+    fn test_find_prunes_tree() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        std::fs::create_dir_all(dir.join("src/core")).unwrap();
+        std::fs::create_dir_all(dir.join("docs")).unwrap();
+        std::fs::write(dir.join("src/core/lib.rs"), "fn main() {}").unwrap();
+        std::fs::write(dir.join("src/README.md"), "").unwrap();
+        std::fs::write(dir.join("docs/guide.md"), "").unwrap();
+
+        let options = FindOptions {
+            name: Some("*.rs".to_string()),
+            types: vec![TypeFilter::File],
+            ..FindOptions::default()
+        };
+        let result = find(dir, &options).unwrap();
+        let names: Vec<_> = result.matches.iter().map(|e| e.name_lossy()).collect();
+        assert_eq!(names, ["lib.rs"]);
+        // 只保留 src/core/lib.rs 这一条路径
+        let src = &result.tree.children.as_ref().unwrap()[..];
+        assert_eq!(src.len(), 1);
+        assert_eq!(src[0].entry.name_lossy(), "src");
+        assert_eq!(result.tree.descendant_count(), 3);
+
+        let options = FindOptions {
+            regex: Some("^(guide|README)\\.md$".to_string()),
+            max_depth: Some(2),
+            ..FindOptions::default()
+        };
+        let names: Vec<_> = find(dir, &options)
+            .unwrap()
+            .matches
+            .iter()
+            .map(|e| e.name_lossy().into_owned())
+            .collect();
+        assert_eq!(names, ["guide.md", "README.md"]);
+
+        // 深度为 0 时只检查起点本身
+        let root_only = |types| FindOptions {
+            types,
+            max_depth: Some(0),
+            ..FindOptions::default()
+        };
+        let readme = dir.join("src/README.md");
+        let result = find(&readme, &root_only(vec![TypeFilter::File])).unwrap();
+        assert_eq!(result.matches.len(), 1);
+        assert_eq!(result.matches[0].path, readme);
+        let result = find(&readme, &root_only(vec![TypeFilter::Dir])).unwrap();
+        assert!(result.matches.is_empty());
+
+        let options = FindOptions {
+            regex: Some("(".to_string()),
+            ..FindOptions::default()
+        };
+        assert!(matches!(
+            find(dir, &options),
+            Err(FmgError::InvalidPattern { .. })
+        ));
+    }
+}
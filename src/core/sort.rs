@@ -0,0 +1,171 @@
+//! 目录项排序模块
+
+use crate::core::models::Entry;
+use clap::ValueEnum;
+use std::cmp::Ordering;
+
+/// 排序依据 (变体注释会作为命令行帮助显示)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum SortKey {
+    /// Sort by file name
+    #[default]
+    Name,
+    /// Largest first
+    Size,
+    /// Newest modification time first
+    Mtime,
+    /// Newest status change time first
+    Ctime,
+    /// Sort by extension, then name
+    Extension,
+    /// Natural order of numbers within names (file2 before file10)
+    Version,
+    /// Keep directory order (unsorted)
+    None,
+}
+
+/// 排序选项
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SortOptions {
+    /// 排序依据
+    pub key: SortKey,
+    /// 是否倒序
+    pub reverse: bool,
+    /// 是否将目录排在文件之前
+    pub dirs_first: bool,
+}
+
+/// 按照排序选项对目录项排序
+pub fn sort_entries(entries: &mut [Entry], options: &SortOptions) {
+    // 稳定排序，None 模式下只做目录优先分组
+    entries.sort_by(|a, b| {
+        let group = if options.dirs_first {
+            b.is_dir().cmp(&a.is_dir())
+        } else {
+            Ordering::Equal
+        };
+        group.then_with(|| {
+            let ordering = compare_entries(options.key, a, b);
+            if options.reverse {
+                ordering.reverse()
+            } else {
+                ordering
+            }
+        })
+    });
+
+    // 不排序时倒序等价于反转读取顺序
+    if options.key == SortKey::None && options.reverse {
+        if options.dirs_first {
+            let split = entries.iter().take_while(|e| e.is_dir()).count();
+            entries[..split].reverse();
+            entries[split..].reverse();
+        } else {
+            entries.reverse();
+        }
+    }
+}
+
+/// 根据排序依据比较两个目录项
+pub fn compare_entries(key: SortKey, a: &Entry, b: &Entry) -> Ordering {
+    let a_name = a.name_lossy();
+    let b_name = b.name_lossy();
+    // 无效 UTF-8 的名称可能在替换后相同，再按原始名称区分
+    let by_name = || a_name.cmp(&b_name).then_with(|| a.name.cmp(&b.name));
+    match key {
+        SortKey::Name => by_name(),
+        SortKey::Size => b.size.cmp(&a.size).then_with(by_name),
+        SortKey::Mtime => b.modified.cmp(&a.modified).then_with(by_name),
+        SortKey::Ctime => b.changed.cmp(&a.changed).then_with(by_name),
+        SortKey::Extension => extension(&a_name)
+            .cmp(extension(&b_name))
+            .then_with(by_name),
+        SortKey::Version => natural_cmp(&a_name, &b_name),
+        SortKey::None => Ordering::Equal,
+    }
+}
+
+/// 获取扩展名，隐藏文件名开头的点不视为扩展名分隔符
+fn extension(name: &str) -> &str {
+    match name.rfind('.') {
+        Some(0) | None => "",
+        Some(i) => &name[i + 1..],
+    }
+}
+
+/// 自然排序比较：连续数字按数值大小比较，其余字符逐个比较
+pub fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let mut a_chars = a.chars().peekable();
+    let mut b_chars = b.chars().peekable();
+
+    loop {
+        match (a_chars.peek().copied(), b_chars.peek().copied()) {
+            (None, None) => return a.cmp(b),
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(ca), Some(cb)) if ca.is_ascii_digit() && cb.is_ascii_digit() => {
+                let a_num = take_digits(&mut a_chars);
+                let b_num = take_digits(&mut b_chars);
+                let a_trimmed = a_num.trim_start_matches('0');
+                let b_trimmed = b_num.trim_start_matches('0');
+                // 先比较有效位数，再逐位比较，最后前导零少的在前
+                let ordering = a_trimmed
+                    .len()
+                    .cmp(&b_trimmed.len())
+                    .then_with(|| a_trimmed.cmp(b_trimmed))
+                    .then_with(|| a_num.len().cmp(&b_num.len()));
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+            }
+            (Some(ca), Some(cb)) => {
+                if ca != cb {
+                    return ca.cmp(&cb);
+                }
+                a_chars.next();
+                b_chars.next();
+            }
+        }
+    }
+}
+
+/// 读取连续的数字字符
+fn take_digits(chars: &mut std::iter::Peekable<std::str::Chars<'_>>) -> String {
+    let mut digits = String::new();
+    while let Some(&c) = chars.peek() {
+        if !c.is_ascii_digit() {
+            break;
+        }
+        digits.push(c);
+        chars.next();
+    }
+    digits
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_natural_cmp_numbers() {
+        assert_eq!(natural_cmp("file2", "file10"), Ordering::Less);
+        assert_eq!(natural_cmp("file10", "file2"), Ordering::Greater);
+        assert_eq!(natural_cmp("app.log.9", "app.log.10"), Ordering::Less);
+        assert_eq!(natural_cmp("v1.2.10", "v1.10.2"), Ordering::Less);
+    }
+
+    #[test]
+    fn test_natural_cmp_leading_zeros_and_text() {
+        assert_eq!(natural_cmp("a01", "a1"), Ordering::Greater);
+        assert_eq!(natural_cmp("abc", "abd"), Ordering::Less);
+        assert_eq!(natural_cmp("abc", "abc1"), Ordering::Less);
+        assert_eq!(natural_cmp("same", "same"), Ordering::Equal);
+    }
+
+    #[test]
+    fn test_extension() {
+        assert_eq!(extension("archive.tar.gz"), "gz");
+        assert_eq!(extension(".bashrc"), "");
+        assert_eq!(extension("Makefile"), "");
+    }
+}
@@ -0,0 +1,208 @@
+//! 单个路径的详细元数据，对应 `stat(1)`
+
+use crate::core::error::{FmgError, Result};
+use crate::core::models::{Entry, EntryKind};
+use std::fs::{File, Metadata};
+use std::io::{self, Read};
+use std::path::Path;
+
+/// 判断 MIME 类型时读取的字节数
+const SNIFF_LEN: u64 = 8192;
+
+/// 一个扩展属性
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Xattr {
+    pub name: String,
+    pub value: Vec<u8>,
+}
+
+/// 路径的详细元数据
+#[derive(Debug, Clone)]
+pub struct FileStat {
+    /// 基本元数据 (类型、大小、权限、属主、时间戳、inode 等)
+    pub entry: Entry,
+    /// 实际占用的 512 字节块数
+    pub blocks: u64,
+    /// 文件系统建议的 IO 块大小
+    pub block_size: u64,
+    /// 字符设备与块设备的设备号
+    pub rdev: u64,
+    /// 根据内容与扩展名判断的 MIME 类型，内容无法读取时为 None
+    pub mime: Option<String>,
+    /// 扩展属性，按名称排序
+    pub xattrs: Vec<Xattr>,
+}
+
+/// 读取 path 的详细元数据；follow 为 true 时跟随符号链接
+pub fn stat(path: &Path, follow: bool) -> Result<FileStat> {
+    let metadata = if follow {
+        std::fs::metadata(path)
+    } else {
+        std::fs::symlink_metadata(path)
+    }
+    .map_err(|e| FmgError::from_io(path, e))?;
+
+    let mut entry = Entry::from_metadata(path, &metadata, 0);
+    if follow {
+        // 跟随后的元数据不含链接目标，仍显示链接本身指向哪里
+        entry.symlink_target = std::fs::read_link(path).ok();
+    }
+    let (blocks, block_size, rdev) = block_fields(&metadata);
+    let xattrs = read_xattrs(path, follow).map_err(|e| FmgError::from_io(path, e))?;
+    Ok(FileStat {
+        mime: mime_type(&entry),
+        entry,
+        blocks,
+        block_size,
+        rdev,
+        xattrs,
+    })
+}
+
+/// 判断 MIME 类型：先识别文件头的魔数，再根据扩展名猜测，最后区分文本与二进制
+///
+/// 非普通文件使用 `file(1)` 的 `inode/*` 类型。
+fn mime_type(entry: &Entry) -> Option<String> {
+    let inode = match entry.kind {
+        EntryKind::File => None,
+        EntryKind::Dir => Some("directory"),
+        EntryKind::Symlink => Some("symlink"),
+        EntryKind::Fifo => Some("fifo"),
+        EntryKind::Socket => Some("socket"),
+        EntryKind::CharDevice => Some("chardevice"),
+        EntryKind::BlockDevice => Some("blockdevice"),
+        EntryKind::Other => Some("x-unknown"),
+    };
+    if let Some(inode) = inode {
+        return Some(format!("inode/{}", inode));
+    }
+    if entry.size == 0 {
+        return Some("inode/x-empty".to_string());
+    }
+
+    let mut head = Vec::new();
+    File::open(&entry.path)
+        .and_then(|file| file.take(SNIFF_LEN).read_to_end(&mut head))
+        .ok()?;
+    if let Some(kind) = infer::get(&head) {
+        return Some(kind.mime_type().to_string());
+    }
+    if let Some(guess) = mime_guess::from_path(&entry.path).first() {
+        return Some(guess.essence_str().to_string());
+    }
+    let text = !head.contains(&0) && utf8_prefix(&head);
+    Some(
+        if text {
+            "text/plain"
+        } else {
+            "application/octet-stream"
+        }
+        .to_string(),
+    )
+}
+
+/// 读取的开头可能截断在多字节字符中间，只要求截断前的部分是合法的 UTF-8
+fn utf8_prefix(head: &[u8]) -> bool {
+    match std::str::from_utf8(head) {
+        Ok(_) => true,
+        Err(e) => e.error_len().is_none(),
+    }
+}
+
+/// 占用的块数、IO 块大小与设备号
+#[cfg(unix)]
+fn block_fields(metadata: &Metadata) -> (u64, u64, u64) {
+    use std::os::unix::fs::MetadataExt;
+    (metadata.blocks(), metadata.blksize(), metadata.rdev())
+}
+
+/// 占用的块数、IO 块大小与设备号 (非 Unix 平台不可用)
+#[cfg(not(unix))]
+fn block_fields(_metadata: &Metadata) -> (u64, u64, u64) {
+    (0, 0, 0)
+}
+
+/// 读取扩展属性，文件系统不支持扩展属性时视为没有
+#[cfg(unix)]
+fn read_xattrs(path: &Path, follow: bool) -> io::Result<Vec<Xattr>> {
+    let names = if follow {
+        xattr::list_deref(path)
+    } else {
+        xattr::list(path)
+    };
+    let names = match names {
+        Ok(names) => names,
+        Err(e) if e.kind() == io::ErrorKind::Unsupported => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    let mut xattrs = Vec::new();
+    for name in names {
+        let value = if follow {
+            xattr::get_deref(path, &name)?
+        } else {
+            xattr::get(path, &name)?
+        };
+        // 列出后被删除的属性直接跳过
+        if let Some(value) = value {
+            xattrs.push(Xattr {
+                name: name.to_string_lossy().into_owned(),
+                value,
+            });
+        }
+    }
+    xattrs.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(xattrs)
+}
+
+/// 读取扩展属性 (非 Unix 平台不支持)
+#[cfg(not(unix))]
+fn read_xattrs(_path: &Path, _follow: bool) -> io::Result<Vec<Xattr>> {
+    Ok(Vec::new())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_mime_type() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        fs::write(dir.join("image.bin"), b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR").unwrap();
+        fs::write(dir.join("notes"), "héllo\n").unwrap();
+        fs::write(dir.join("data.json"), "{}").unwrap();
+        fs::write(dir.join("blob"), b"\x01\x00\xff").unwrap();
+        fs::write(dir.join("empty"), "").unwrap();
+
+        let mime = |name: &str| stat(&dir.join(name), false).unwrap().mime.unwrap();
+        assert_eq!(mime("image.bin"), "image/png");
+        assert_eq!(mime("notes"), "text/plain");
+        assert_eq!(mime("data.json"), "application/json");
+        assert_eq!(mime("blob"), "application/octet-stream");
+        assert_eq!(mime("empty"), "inode/x-empty");
+        assert_eq!(stat(dir, false).unwrap().mime.unwrap(), "inode/directory");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_stat_symlink() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        fs::write(dir.join("target.txt"), "12345").unwrap();
+        std::os::unix::fs::symlink("target.txt", dir.join("link")).unwrap();
+
+        let link = stat(&dir.join("link"), false).unwrap();
+        assert_eq!(link.entry.kind, EntryKind::Symlink);
+        assert_eq!(link.mime.as_deref(), Some("inode/symlink"));
+        let followed = stat(&dir.join("link"), true).unwrap();
+        assert_eq!(followed.entry.kind, EntryKind::File);
+        assert_eq!(followed.entry.size, 5);
+        assert_eq!(
+            followed.entry.symlink_target,
+            Some(PathBuf::from("target.txt"))
+        );
+        assert!(followed.block_size > 0);
+    }
+}
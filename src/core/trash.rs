@@ -0,0 +1,557 @@
+//! 回收站模块：按 freedesktop.org 回收站规范移入、列出、恢复与清空
+//!
+//! 与主目录位于同一文件系统的文件放入 `$XDG_DATA_HOME/Trash`，其他文件系统上的
+//! 文件放入该文件系统根目录下的 `.Trash/$uid` 或 `.Trash-$uid`，移入回收站始终是
+//! 一次重命名而不是复制。
+
+use crate::core::copy::existing_metadata;
+use crate::core::dirs;
+use crate::core::models::Entry;
+use crate::core::move_ops::{ConflictPolicy, MoveOutcome, move_path, numbered_name, remove_all};
+use crate::core::users::current_uid;
+use crate::display::time::format_local;
+use serde::{Deserialize, Serialize};
+use std::ffi::{OsStr, OsString};
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// 回收站中的一项
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrashItem {
+    /// 所在的回收站目录 (包含 `files` 与 `info`)
+    pub trash_dir: PathBuf,
+    /// 在回收站中的名称
+    pub name: OsString,
+    /// 删除前的绝对路径
+    pub original: PathBuf,
+    /// 删除时间 (本地时间，`YYYY-MM-DDThh:mm:ss`)
+    pub deleted: String,
+}
+
+impl TrashItem {
+    /// 回收站中保存内容的路径
+    pub fn file_path(&self) -> PathBuf {
+        self.trash_dir.join("files").join(&self.name)
+    }
+
+    /// 对应的 `.trashinfo` 文件
+    pub fn info_path(&self) -> PathBuf {
+        info_path(&self.trash_dir, &self.name)
+    }
+}
+
+/// 用户的回收站
+#[derive(Debug, Clone)]
+pub struct Trash {
+    /// 主回收站目录，通常为 `~/.local/share/Trash`
+    home: PathBuf,
+}
+
+impl Trash {
+    /// 以 home 为主回收站目录
+    pub fn new(home: PathBuf) -> Self {
+        Self { home }
+    }
+
+    /// 按 XDG 规范定位主回收站：`$XDG_DATA_HOME/Trash`
+    pub fn from_env() -> Option<Self> {
+        dirs::data_dir().map(|data| Self::new(data.join("Trash")))
+    }
+
+    /// 将 path 移入回收站
+    pub fn put(&self, path: &Path) -> io::Result<TrashItem> {
+        self.put_as(path, path)
+    }
+
+    /// 将 path 移入回收站，但记录为从 original 删除
+    fn put_as(&self, path: &Path, original: &Path) -> io::Result<TrashItem> {
+        let path = absolute_path(path)?;
+        let original = absolute_path(original)?;
+        let metadata = fs::symlink_metadata(&path)?;
+        let trash_dir = self.trash_dir_for(&original, &metadata)?;
+        if original.starts_with(&trash_dir) || trash_dir.starts_with(&original) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "refusing to move the trash into itself",
+            ));
+        }
+        fs::create_dir_all(trash_dir.join("files"))?;
+        fs::create_dir_all(trash_dir.join("info"))?;
+        set_private(&trash_dir)?;
+
+        // 卷回收站中记录相对于卷根目录的路径
+        let recorded = match volume_top(&trash_dir) {
+            Some(top) => original.strip_prefix(&top).unwrap_or(&original),
+            None => &original,
+        };
+        let deleted = format_local(SystemTime::now(), "%Y-%m-%dT%H:%M:%S");
+        let contents = format!(
+            "[Trash Info]\nPath={}\nDeletionDate={}\n",
+            percent_encode(recorded.as_os_str()),
+            deleted
+        );
+
+        // 以独占方式创建 .trashinfo 来占用名称，避免与并发的删除冲突
+        let base = original.file_name().unwrap_or_default();
+        for n in 1.. {
+            let name = match n {
+                1 => base.to_os_string(),
+                n => numbered_name(base, n),
+            };
+            let info = info_path(&trash_dir, &name);
+            if existing_metadata(&trash_dir.join("files").join(&name))?.is_some() {
+                continue;
+            }
+            let mut file = match File::options().write(true).create_new(true).open(&info) {
+                Ok(file) => file,
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+                Err(e) => return Err(e),
+            };
+            let item = TrashItem {
+                trash_dir: trash_dir.clone(),
+                name,
+                original: original.clone(),
+                deleted,
+            };
+            let moved = file
+                .write_all(contents.as_bytes())
+                .and_then(|_| fs::rename(&path, item.file_path()));
+            if let Err(e) = moved {
+                let _ = fs::remove_file(&info);
+                return Err(e);
+            }
+            return Ok(item);
+        }
+        unreachable!("ran out of trash names")
+    }
+
+    /// 列出所有回收站中的项目，按删除时间排序；无法解析的 `.trashinfo` 会被忽略
+    pub fn list(&self) -> io::Result<Vec<TrashItem>> {
+        let mut items = Vec::new();
+        for trash_dir in self.all_trash_dirs() {
+            let entries = match fs::read_dir(trash_dir.join("info")) {
+                Ok(entries) => entries,
+                Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(e),
+            };
+            for entry in entries {
+                let path = entry?.path();
+                if path.extension() != Some(OsStr::new("trashinfo")) {
+                    continue;
+                }
+                if let Some(item) = read_info(&trash_dir, &path) {
+                    items.push(item);
+                }
+            }
+        }
+        // 删除时间只精确到秒，同一秒内的项目再按 .trashinfo 的修改时间排序
+        items.sort_by_cached_key(|item| {
+            let written = fs::metadata(item.info_path()).and_then(|m| m.modified());
+            (item.deleted.clone(), written.ok(), item.original.clone())
+        });
+        Ok(items)
+    }
+
+    /// 将项目恢复到原位置，原位置已存在时按 conflict 处理
+    pub fn restore(&self, item: &TrashItem, conflict: ConflictPolicy) -> io::Result<MoveOutcome> {
+        if let Some(parent) = item.original.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let outcome = move_path(
+            &item.file_path(),
+            &item.original,
+            conflict,
+            &mut |_: &Entry, _| {},
+        )?;
+        if outcome != MoveOutcome::Skipped {
+            fs::remove_file(item.info_path())?;
+        }
+        Ok(outcome)
+    }
+
+    /// 永久删除回收站中的项目
+    pub fn purge(&self, item: &TrashItem) -> io::Result<()> {
+        match remove_all(&item.file_path()) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+            _ => {}
+        }
+        fs::remove_file(item.info_path())
+    }
+
+    /// path 应当放入的回收站目录
+    fn trash_dir_for(&self, path: &Path, metadata: &fs::Metadata) -> io::Result<PathBuf> {
+        fs::create_dir_all(&self.home)?;
+        if same_device(&fs::metadata(&self.home)?, metadata) {
+            return Ok(self.home.clone());
+        }
+        volume_trash_dir(path, metadata)
+    }
+
+    /// 主回收站与各挂载点上已存在的卷回收站
+    fn all_trash_dirs(&self) -> Vec<PathBuf> {
+        let uid = current_uid();
+        let mut dirs = vec![self.home.clone()];
+        for top in mount_points() {
+            for dir in [
+                top.join(".Trash").join(uid.to_string()),
+                top.join(format!(".Trash-{}", uid)),
+            ] {
+                if dir.is_dir() && !dirs.contains(&dir) {
+                    dirs.push(dir);
+                }
+            }
+        }
+        dirs
+    }
+}
+
+/// 覆盖前为目标创建的硬链接，覆盖完成后移入回收站，使覆盖可以撤销
+///
+/// 新内容随后以重命名直接替换目标，目标在任何时刻都不会缺失。
+#[derive(Debug)]
+pub struct Backup {
+    link: PathBuf,
+    target: PathBuf,
+}
+
+impl Backup {
+    /// 在 target 所在目录中为其创建硬链接；目录或不支持硬链接的文件系统返回 None
+    pub fn link(target: &Path) -> Option<Self> {
+        let metadata = fs::symlink_metadata(target).ok()?;
+        if metadata.is_dir() {
+            return None;
+        }
+        let mut name = OsString::from(".");
+        name.push(target.file_name()?);
+        name.push(format!(".fmg-backup-{}", std::process::id()));
+        let link = target.with_file_name(name);
+        fs::hard_link(target, &link).ok()?;
+        Some(Self {
+            link,
+            target: target.to_path_buf(),
+        })
+    }
+
+    /// 覆盖完成后将备份移入回收站；失败时删除备份并返回 None
+    pub fn keep(self, trash: &Trash) -> Option<TrashItem> {
+        let item = trash.put_as(&self.link, &self.target).ok();
+        if item.is_none() {
+            self.discard();
+        }
+        item
+    }
+
+    /// 覆盖失败或不需要撤销时删除备份
+    pub fn discard(self) {
+        let _ = fs::remove_file(&self.link);
+    }
+}
+
+/// 不解析最后一级 (可能是符号链接) 的绝对路径
+fn absolute_path(path: &Path) -> io::Result<PathBuf> {
+    let Some(name) = path.file_name() else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "refusing to remove '.', '..' or '/'",
+        ));
+    };
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    Ok(parent.canonicalize()?.join(name))
+}
+
+/// 名称对应的 `.trashinfo` 路径
+fn info_path(trash_dir: &Path, name: &OsStr) -> PathBuf {
+    let mut info = name.to_os_string();
+    info.push(".trashinfo");
+    trash_dir.join("info").join(info)
+}
+
+/// 解析 `.trashinfo` 文件
+fn read_info(trash_dir: &Path, info: &Path) -> Option<TrashItem> {
+    let contents = fs::read_to_string(info).ok()?;
+    let mut lines = contents.lines().map(str::trim);
+    if lines.next()? != "[Trash Info]" {
+        return None;
+    }
+    let (mut recorded, mut deleted) = (None, None);
+    for line in lines {
+        if let Some(value) = line.strip_prefix("Path=") {
+            recorded = Some(percent_decode(value)?);
+        } else if let Some(value) = line.strip_prefix("DeletionDate=") {
+            deleted = Some(value.to_string());
+        } else if line.starts_with('[') {
+            break;
+        }
+    }
+
+    let recorded = recorded?;
+    let original = match volume_top(trash_dir) {
+        Some(top) if recorded.is_relative() => top.join(recorded),
+        _ => recorded,
+    };
+    let name = info.file_stem()?.to_os_string();
+    Some(TrashItem {
+        trash_dir: trash_dir.to_path_buf(),
+        name,
+        original,
+        deleted: deleted.unwrap_or_default(),
+    })
+}
+
+/// 卷回收站所在卷的根目录；主回收站返回 None
+fn volume_top(trash_dir: &Path) -> Option<PathBuf> {
+    let name = trash_dir.file_name()?.to_str()?;
+    let parent = trash_dir.parent()?;
+    if name.starts_with(".Trash-") {
+        return Some(parent.to_path_buf());
+    }
+    if parent.file_name() == Some(OsStr::new(".Trash")) {
+        return parent.parent().map(Path::to_path_buf);
+    }
+    None
+}
+
+/// 按 RFC 2396 编码路径，保留 `/` 与非保留字符
+fn percent_encode(path: &OsStr) -> String {
+    let mut encoded = String::new();
+    for &byte in path.as_encoded_bytes() {
+        if byte.is_ascii_alphanumeric() || b"-_.~/".contains(&byte) {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    encoded
+}
+
+/// 解码 `%XX` 序列
+fn percent_decode(value: &str) -> Option<PathBuf> {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = std::str::from_utf8(bytes.get(i + 1..i + 3)?).ok()?;
+            decoded.push(u8::from_str_radix(hex, 16).ok()?);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    Some(path_from_bytes(decoded))
+}
+
+#[cfg(unix)]
+fn path_from_bytes(bytes: Vec<u8>) -> PathBuf {
+    use std::os::unix::ffi::OsStringExt;
+    PathBuf::from(OsString::from_vec(bytes))
+}
+
+#[cfg(not(unix))]
+fn path_from_bytes(bytes: Vec<u8>) -> PathBuf {
+    PathBuf::from(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+/// 两个文件是否位于同一文件系统
+#[cfg(unix)]
+fn same_device(a: &fs::Metadata, b: &fs::Metadata) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    a.dev() == b.dev()
+}
+
+/// 非 Unix 平台只使用主回收站
+#[cfg(not(unix))]
+fn same_device(_a: &fs::Metadata, _b: &fs::Metadata) -> bool {
+    true
+}
+
+/// 其他文件系统上的回收站：优先使用管理员创建的 `$top/.Trash/$uid`，否则为 `$top/.Trash-$uid`
+#[cfg(unix)]
+fn volume_trash_dir(path: &Path, metadata: &fs::Metadata) -> io::Result<PathBuf> {
+    use std::os::unix::fs::{MetadataExt, PermissionsExt};
+
+    let device = metadata.dev();
+    let mut top = path;
+    for ancestor in path.ancestors().skip(1) {
+        if fs::metadata(ancestor)?.dev() != device {
+            break;
+        }
+        top = ancestor;
+    }
+    let uid = current_uid();
+
+    // 共享的 .Trash 必须是设置了粘滞位的真实目录，否则不安全
+    let shared = top.join(".Trash");
+    if fs::symlink_metadata(&shared)
+        .is_ok_and(|m| m.is_dir() && m.permissions().mode() & 0o1000 != 0)
+    {
+        let dir = shared.join(uid.to_string());
+        if fs::create_dir_all(&dir).is_ok() {
+            return Ok(dir);
+        }
+    }
+    let dir = top.join(format!(".Trash-{}", uid));
+    fs::create_dir_all(&dir)?;
+    if fs::symlink_metadata(&dir)?.file_type().is_symlink() {
+        return Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            "trash directory is a symbolic link",
+        ));
+    }
+    Ok(dir)
+}
+
+#[cfg(not(unix))]
+fn volume_trash_dir(_path: &Path, _metadata: &fs::Metadata) -> io::Result<PathBuf> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "only the home trash is supported on this platform",
+    ))
+}
+
+/// 回收站目录只允许属主访问
+#[cfg(unix)]
+fn set_private(dir: &Path) -> io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    fs::set_permissions(dir, fs::Permissions::from_mode(0o700))
+}
+
+#[cfg(not(unix))]
+fn set_private(_dir: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+/// 当前挂载的文件系统的挂载点
+#[cfg(target_os = "linux")]
+fn mount_points() -> Vec<PathBuf> {
+    let Ok(mounts) = fs::read_to_string("/proc/self/mounts") else {
+        return Vec::new();
+    };
+    mounts
+        .lines()
+        .filter_map(|line| line.split_whitespace().nth(1))
+        .filter_map(unescape_mount_point)
+        .collect()
+}
+
+/// 其他平台只检查根目录
+#[cfg(not(target_os = "linux"))]
+fn mount_points() -> Vec<PathBuf> {
+    vec![PathBuf::from("/")]
+}
+
+/// 还原 /proc/self/mounts 中以 `\ooo` 转义的空白与反斜杠
+#[cfg(target_os = "linux")]
+fn unescape_mount_point(field: &str) -> Option<PathBuf> {
+    let bytes = field.as_bytes();
+    let mut unescaped = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'\\' {
+            let octal = std::str::from_utf8(bytes.get(i + 1..i + 4)?).ok()?;
+            unescaped.push(u8::from_str_radix(octal, 8).ok()?);
+            i += 4;
+        } else {
+            unescaped.push(bytes[i]);
+            i += 1;
+        }
+    }
+    Some(path_from_bytes(unescaped))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_percent_encoding_round_trip() {
+        let path = Path::new("/home/user/My Files/报告 100%.txt");
+        let encoded = percent_encode(path.as_os_str());
+        assert!(encoded.starts_with("/home/user/My%20Files/%E6%8A%A5"));
+        assert!(encoded.ends_with("%20100%25.txt"));
+        assert_eq!(percent_decode(&encoded).unwrap(), path);
+        assert!(percent_decode("bad%2").is_none());
+    }
+
+    #[test]
+    fn test_put_list_restore_and_purge() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        fs::create_dir_all(dir.join("work/sub")).unwrap();
+        let dir = dir.canonicalize().unwrap();
+        let trash = Trash::new(dir.join("Trash"));
+        let file = dir.join("work/notes.txt");
+        fs::write(&file, "v1").unwrap();
+
+        let first = trash.put(&file).unwrap();
+        assert!(!file.exists());
+        assert_eq!(first.original, file);
+        let info = fs::read_to_string(first.info_path()).unwrap();
+        assert!(info.starts_with("[Trash Info]\nPath="));
+        assert!(info.contains("/work/notes.txt\nDeletionDate="));
+
+        // 同名文件再次删除时使用编号名称
+        fs::write(&file, "v2").unwrap();
+        let second = trash.put(&file).unwrap();
+        assert_eq!(second.name, "notes (2).txt");
+        trash.put(&dir.join("work/sub")).unwrap();
+
+        let items: Vec<_> = trash
+            .list()
+            .unwrap()
+            .into_iter()
+            .filter(|item| item.trash_dir == dir.join("Trash"))
+            .collect();
+        assert_eq!(items.len(), 3);
+
+        let outcome = trash.restore(&second, ConflictPolicy::Fail).unwrap();
+        assert_eq!(outcome, MoveOutcome::Renamed(file.clone()));
+        assert_eq!(fs::read_to_string(&file).unwrap(), "v2");
+        assert!(!second.info_path().exists());
+
+        // 原位置已被占用时按冲突策略处理
+        let err = trash.restore(&first, ConflictPolicy::Fail).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+
+        trash.purge(&first).unwrap();
+        assert!(!first.file_path().exists() && !first.info_path().exists());
+        assert_eq!(
+            trash
+                .list()
+                .unwrap()
+                .iter()
+                .filter(|item| item.trash_dir == dir.join("Trash"))
+                .count(),
+            1
+        );
+        assert!(trash.put(&dir.join("Trash")).is_err());
+    }
+
+    #[test]
+    fn test_backup_before_overwrite() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path().canonicalize().unwrap();
+        let trash = Trash::new(dir.join("Trash"));
+        let target = dir.join("notes.txt");
+        fs::write(&target, "old").unwrap();
+
+        // 备份后直接以重命名替换目标，再把备份移入回收站并记录原路径
+        let backup = Backup::link(&target).unwrap();
+        fs::write(dir.join("new"), "new").unwrap();
+        fs::rename(dir.join("new"), &target).unwrap();
+        let item = backup.keep(&trash).unwrap();
+        assert_eq!(item.original, target);
+        assert_eq!(item.name, "notes.txt");
+        assert_eq!(fs::read_to_string(item.file_path()).unwrap(), "old");
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 2);
+
+        // 目录无法以硬链接备份
+        assert!(Backup::link(&dir.join("Trash")).is_none());
+    }
+}
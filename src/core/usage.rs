@@ -0,0 +1,98 @@
+//! 目录占用空间统计模块
+
+use crate::core::models::EntryError;
+use rayon::prelude::*;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+
+/// 递归统计目录大小，结果按目录缓存，树形遍历时每个目录只会被读取一次
+///
+/// 统计的是文件的表观大小 (与长格式中的大小列一致)，不跟随符号链接。
+/// 子目录并行统计，可以在多个线程间共享。
+#[derive(Debug, Default)]
+pub struct DiskUsage {
+    cache: Mutex<HashMap<PathBuf, u64>>,
+    errors: Mutex<Vec<EntryError>>,
+}
+
+impl DiskUsage {
+    /// 创建空的统计器
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 目录及其所有后代的大小之和 (包含目录自身的大小)
+    pub fn dir_size(&self, dir: &Path) -> u64 {
+        if let Some(&size) = lock(&self.cache).get(dir) {
+            return size;
+        }
+
+        let own = std::fs::symlink_metadata(dir).map(|m| m.len()).unwrap_or(0);
+        let entries: Vec<_> = match std::fs::read_dir(dir) {
+            Ok(entries) => entries
+                .filter_map(|entry| entry.map_err(|error| self.record(dir, error)).ok())
+                .collect(),
+            Err(error) => {
+                self.record(dir, error);
+                Vec::new()
+            }
+        };
+        let total = own
+            + entries
+                .into_par_iter()
+                .map(|entry| match entry.file_type() {
+                    Ok(file_type) if file_type.is_dir() => self.dir_size(&entry.path()),
+                    Ok(_) => entry.metadata().map(|m| m.len()).unwrap_or(0),
+                    Err(error) => {
+                        self.record(&entry.path(), error);
+                        0
+                    }
+                })
+                .sum::<u64>();
+
+        lock(&self.cache).insert(dir.to_path_buf(), total);
+        total
+    }
+
+    /// 取出统计过程中遇到的错误
+    pub fn into_errors(self) -> Vec<EntryError> {
+        self.errors.into_inner().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// 记录读取错误
+    fn record(&self, path: &Path, error: std::io::Error) {
+        lock(&self.errors).push(EntryError {
+            path: path.to_path_buf(),
+            error,
+        });
+    }
+}
+
+/// 获取锁；其他线程 panic 不影响已记录的数据
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dir_size_recursive() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        std::fs::create_dir_all(dir.join("a/b")).unwrap();
+        std::fs::write(dir.join("one"), vec![0u8; 100]).unwrap();
+        std::fs::write(dir.join("a/b/two"), vec![0u8; 1000]).unwrap();
+
+        let usage = DiskUsage::new();
+        let dir_len = |p: &Path| std::fs::metadata(p).unwrap().len();
+        let expected_b = dir_len(&dir.join("a/b")) + 1000;
+        let expected_a = dir_len(&dir.join("a")) + expected_b;
+        assert_eq!(usage.dir_size(dir), dir_len(dir) + 100 + expected_a);
+        // 子目录的结果来自缓存
+        assert_eq!(usage.dir_size(&dir.join("a")), expected_a);
+        assert!(usage.into_errors().is_empty());
+    }
+}
@@ -0,0 +1,123 @@
+//! 用户与用户组名称解析模块
+
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+
+/// uid -> 用户名 缓存
+static USER_CACHE: OnceLock<Mutex<HashMap<u32, Option<String>>>> = OnceLock::new();
+/// gid -> 组名 缓存
+static GROUP_CACHE: OnceLock<Mutex<HashMap<u32, Option<String>>>> = OnceLock::new();
+
+/// 根据 uid 获取用户名，无法解析时返回 None
+pub fn user_name(uid: u32) -> Option<String> {
+    let cache = USER_CACHE.get_or_init(|| Mutex::new(HashMap::new()));
+    let mut cache = cache.lock().unwrap_or_else(|e| e.into_inner());
+    cache.entry(uid).or_insert_with(|| lookup_user(uid)).clone()
+}
+
+/// 根据 gid 获取组名，无法解析时返回 None
+pub fn group_name(gid: u32) -> Option<String> {
+    let cache = GROUP_CACHE.get_or_init(|| Mutex::new(HashMap::new()));
+    let mut cache = cache.lock().unwrap_or_else(|e| e.into_inner());
+    cache
+        .entry(gid)
+        .or_insert_with(|| lookup_group(gid))
+        .clone()
+}
+
+/// 获取用户名，解析失败时回退为数字 uid
+pub fn user_display(uid: u32) -> String {
+    user_name(uid).unwrap_or_else(|| uid.to_string())
+}
+
+/// 获取组名，解析失败时回退为数字 gid
+pub fn group_display(gid: u32) -> String {
+    group_name(gid).unwrap_or_else(|| gid.to_string())
+}
+
+/// 当前进程的 uid
+#[cfg(unix)]
+pub fn current_uid() -> u32 {
+    // SAFETY: getuid 总是成功且没有副作用
+    unsafe { libc::getuid() }
+}
+
+/// 当前进程的 uid (非 Unix 平台没有 uid，固定为 0)
+#[cfg(not(unix))]
+pub fn current_uid() -> u32 {
+    0
+}
+
+#[cfg(unix)]
+fn lookup_user(uid: u32) -> Option<String> {
+    use std::ffi::CStr;
+
+    let mut buf = vec![0 as libc::c_char; 1024];
+    loop {
+        let mut pwd: libc::passwd = unsafe { std::mem::zeroed() };
+        let mut result: *mut libc::passwd = std::ptr::null_mut();
+        // SAFETY: 所有指针均指向本函数内有效的缓冲区
+        let ret =
+            unsafe { libc::getpwuid_r(uid, &mut pwd, buf.as_mut_ptr(), buf.len(), &mut result) };
+        if ret == libc::ERANGE && buf.len() < 1 << 20 {
+            buf.resize(buf.len() * 2, 0);
+            continue;
+        }
+        if ret != 0 || result.is_null() {
+            return None;
+        }
+        // SAFETY: 调用成功时 pw_name 指向 buf 中以 NUL 结尾的字符串
+        let name = unsafe { CStr::from_ptr(pwd.pw_name) };
+        return Some(name.to_string_lossy().into_owned());
+    }
+}
+
+#[cfg(unix)]
+fn lookup_group(gid: u32) -> Option<String> {
+    use std::ffi::CStr;
+
+    let mut buf = vec![0 as libc::c_char; 1024];
+    loop {
+        let mut grp: libc::group = unsafe { std::mem::zeroed() };
+        let mut result: *mut libc::group = std::ptr::null_mut();
+        // SAFETY: 所有指针均指向本函数内有效的缓冲区
+        let ret =
+            unsafe { libc::getgrgid_r(gid, &mut grp, buf.as_mut_ptr(), buf.len(), &mut result) };
+        if ret == libc::ERANGE && buf.len() < 1 << 20 {
+            buf.resize(buf.len() * 2, 0);
+            continue;
+        }
+        if ret != 0 || result.is_null() {
+            return None;
+        }
+        // SAFETY: 调用成功时 gr_name 指向 buf 中以 NUL 结尾的字符串
+        let name = unsafe { CStr::from_ptr(grp.gr_name) };
+        return Some(name.to_string_lossy().into_owned());
+    }
+}
+
+#[cfg(not(unix))]
+fn lookup_user(_uid: u32) -> Option<String> {
+    None
+}
+
+#[cfg(not(unix))]
+fn lookup_group(_gid: u32) -> Option<String> {
+    None
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_root_user_and_group() {
+        assert_eq!(user_name(0).as_deref(), Some("root"));
+        assert!(group_name(0).is_some());
+    }
+
+    #[test]
+    fn test_unknown_id_falls_back_to_number() {
+        assert_eq!(user_display(4_000_000_000), "4000000000");
+    }
+}
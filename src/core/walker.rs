@@ -0,0 +1,270 @@
+//! 并行目录遍历模块
+//!
+//! 同一目录下的目录项并发读取元数据，子目录由 rayon 的工作窃取线程池并行展开。
+//! 每一层在排序后按顺序收集，因此输出与单线程遍历完全一致。
+
+use crate::core::filter::{EntryFilter, IgnoreStack};
+use crate::core::models::{DirTree, Entry, EntryError, EntryKind};
+use crate::core::sort::{SortOptions, sort_entries};
+use crate::core::usage::DiskUsage;
+use rayon::prelude::*;
+use std::io;
+use std::path::Path;
+use std::sync::Mutex;
+
+/// 目录的唯一标识，用于检测目录循环
+#[cfg(unix)]
+type DirId = (u64, u64);
+
+/// 目录的唯一标识，用于检测目录循环 (非 Unix 平台使用规范化路径)
+#[cfg(not(unix))]
+type DirId = std::path::PathBuf;
+
+#[cfg(unix)]
+fn dir_id(entry: &Entry) -> Option<DirId> {
+    Some((entry.dev, entry.ino))
+}
+
+#[cfg(not(unix))]
+fn dir_id(entry: &Entry) -> Option<DirId> {
+    entry.path.canonicalize().ok()
+}
+
+/// 正在展开的祖先目录链，每一层只在栈上保存自身，不需要复制整条路径
+struct Ancestors<'a> {
+    id: DirId,
+    parent: Option<&'a Ancestors<'a>>,
+}
+
+impl Ancestors<'_> {
+    /// 链上是否存在相同的目录
+    fn contains(&self, id: &DirId) -> bool {
+        let mut current = Some(self);
+        while let Some(frame) = current {
+            if frame.id == *id {
+                return true;
+            }
+            current = frame.parent;
+        }
+        false
+    }
+}
+
+/// 一次遍历的上下文：排序与过滤规则，以及遍历中累积的错误
+pub(crate) struct Walker<'a> {
+    sort: &'a SortOptions,
+    filter: EntryFilter,
+    max_depth: usize,
+    follow: bool,
+    usage: Option<DiskUsage>,
+    errors: Mutex<Vec<EntryError>>,
+}
+
+impl<'a> Walker<'a> {
+    /// 创建遍历上下文；max_depth 为 None 时不限制深度
+    pub(crate) fn new(
+        sort: &'a SortOptions,
+        filter: EntryFilter,
+        max_depth: Option<usize>,
+        follow: bool,
+        du: bool,
+    ) -> Self {
+        Self {
+            sort,
+            filter,
+            max_depth: max_depth.unwrap_or(usize::MAX),
+            follow,
+            usage: du.then(DiskUsage::new),
+            errors: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// 过滤器
+    pub(crate) fn filter(&self) -> &EntryFilter {
+        &self.filter
+    }
+
+    /// 结束遍历，返回按路径排序的全部错误
+    pub(crate) fn finish(self) -> Vec<EntryError> {
+        let mut errors = self.errors.into_inner().unwrap_or_else(|e| e.into_inner());
+        if let Some(usage) = self.usage {
+            errors.extend(usage.into_errors());
+        }
+        errors.sort_by(|a, b| a.path.cmp(&b.path));
+        errors
+    }
+
+    /// 记录单个目录项的错误
+    fn record(&self, path: &Path, error: io::Error) {
+        let error = EntryError {
+            path: path.to_path_buf(),
+            error,
+        };
+        self.errors
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(error);
+    }
+
+    /// 读取单层目录，并发读取元数据后过滤并排序，单个目录项的错误记录在 errors 中
+    pub(crate) fn read_entries(
+        &self,
+        path: &Path,
+        depth: usize,
+        ignores: &IgnoreStack,
+    ) -> io::Result<Vec<Entry>> {
+        let mut dir_entries = Vec::new();
+        for dir_entry in std::fs::read_dir(path)? {
+            match dir_entry {
+                Ok(dir_entry) => dir_entries.push(dir_entry),
+                Err(error) => self.record(path, error),
+            }
+        }
+
+        let mut entries: Vec<Entry> = dir_entries
+            .into_par_iter()
+            .filter_map(|dir_entry| {
+                let entry_path = dir_entry.path();
+                let metadata = match dir_entry.metadata() {
+                    Ok(metadata) => metadata,
+                    Err(error) => {
+                        self.record(&entry_path, error);
+                        return None;
+                    }
+                };
+
+                let mut entry = Entry::from_metadata(&entry_path, &metadata, depth);
+                if self.follow {
+                    entry = entry.follow();
+                }
+                if !self.filter.is_visible(&entry, ignores) {
+                    return None;
+                }
+                // 在排序之前替换大小，使 --sort size 按递归大小排序
+                if let Some(usage) = &self.usage
+                    && entry.kind == EntryKind::Dir
+                {
+                    entry.size = usage.dir_size(&entry.path);
+                }
+                Some(entry)
+            })
+            .collect();
+
+        sort_entries(&mut entries, self.sort);
+        Ok(entries)
+    }
+
+    /// 并行展开根目录的所有子节点
+    pub(crate) fn build_children(
+        &self,
+        root: &Entry,
+        ignores: &IgnoreStack,
+    ) -> io::Result<Vec<DirTree>> {
+        let frame = dir_id(root).map(|id| Ancestors { id, parent: None });
+        let children = self
+            .read_entries(&root.path, 0, ignores)?
+            .into_par_iter()
+            .map(|entry| self.build_tree_node(entry, ignores, frame.as_ref()))
+            .collect();
+        Ok(children)
+    }
+
+    /// 递归构建树节点
+    fn build_tree_node(
+        &self,
+        entry: Entry,
+        ignores: &IgnoreStack,
+        ancestors: Option<&Ancestors<'_>>,
+    ) -> DirTree {
+        // 符号链接只在跟随时 (已被替换为目标的元数据) 才会展开
+        if entry.kind != EntryKind::Dir || entry.depth >= self.max_depth {
+            return DirTree {
+                entry,
+                children: None,
+            };
+        }
+
+        // 目录与某个祖先目录相同，说明链接形成了循环
+        let id = dir_id(&entry);
+        if let Some(id) = &id
+            && ancestors.is_some_and(|ancestors| ancestors.contains(id))
+        {
+            self.record(
+                &entry.path,
+                io::Error::other("directory loop detected, not following"),
+            );
+            return DirTree {
+                entry,
+                children: None,
+            };
+        }
+        let frame = id.map(|id| Ancestors {
+            id,
+            parent: ancestors,
+        });
+        let ancestors = frame.as_ref().or(ancestors);
+
+        let child_ignores = self.filter.enter_dir(&entry.path, ignores);
+        let children = match self.read_entries(&entry.path, entry.depth + 1, &child_ignores) {
+            Ok(entries) => entries
+                .into_par_iter()
+                .map(|child| self.build_tree_node(child, &child_ignores, ancestors))
+                .collect(),
+            Err(error) => {
+                self.record(&entry.path, error);
+                Vec::new()
+            }
+        };
+
+        DirTree {
+            entry,
+            children: Some(children),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::core::file_ops::{TreeOptions, walk_tree};
+    use crate::core::models::DirTree;
+
+    /// 深度优先展开为 "深度:名称" 列表
+    fn flatten(node: &DirTree, out: &mut Vec<String>) {
+        for child in node.children.iter().flatten() {
+            out.push(format!(
+                "{}:{}",
+                child.entry.depth,
+                child.entry.name_lossy()
+            ));
+            flatten(child, out);
+        }
+    }
+
+    #[test]
+    fn test_parallel_walk_is_deterministic() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        for i in 0..16 {
+            let sub = dir.join(format!("d{:02}", i));
+            std::fs::create_dir_all(sub.join("nested")).unwrap();
+            for j in 0..16 {
+                std::fs::write(sub.join(format!("f{:02}", j)), "").unwrap();
+            }
+        }
+
+        let walk = || {
+            let listing = walk_tree(dir, &TreeOptions::default()).unwrap();
+            assert_eq!(listing.tree.descendant_count(), 16 * 18);
+            let mut names = Vec::new();
+            flatten(&listing.tree, &mut names);
+            names
+        };
+        let first = walk();
+        assert_eq!(first[..3], ["0:d00", "1:f00", "1:f01"]);
+        assert_eq!(first[17], "1:nested");
+        assert_eq!(first[18], "0:d01");
+        for _ in 0..4 {
+            assert_eq!(walk(), first);
+        }
+    }
+}
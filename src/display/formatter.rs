@@ -1,59 +1,794 @@
 //! 输出格式化模块
 
-use std::time::{SystemTime, UNIX_EPOCH};
+use crate::core::grep::{FileMatches, GrepLine, LineKind};
+use crate::core::models::{DirTree, Entry, EntryKind};
+use crate::core::rename::Rename;
+use crate::core::stat::FileStat;
+use crate::core::users::{group_display, user_display};
+use crate::display::quoting::{QuotingStyle, name_bytes, quote_name};
+use crate::display::styles::style_sheet;
+use crate::display::time::{TimeField, TimeStyle, format_full_time, format_local, format_time};
+use clap::ValueEnum;
 use colored::*;
+use std::io::{self, Write};
+use std::time::SystemTime;
+use unicode_width::UnicodeWidthStr;
 
-/// 格式化系统时间
+/// 输出格式 (变体注释会作为命令行帮助显示)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum OutputFormat {
+    /// Colored human-readable text
+    #[default]
+    Text,
+    /// A single JSON document (nested `children` in tree mode)
+    Json,
+    /// One JSON object per line
+    Ndjson,
+}
+
+// 文件类型位 (与 libc 中 S_IF* 常量一致)
+const S_IFMT: u32 = 0o170000;
+const S_IFSOCK: u32 = 0o140000;
+const S_IFLNK: u32 = 0o120000;
+const S_IFREG: u32 = 0o100000;
+const S_IFBLK: u32 = 0o060000;
+const S_IFDIR: u32 = 0o040000;
+const S_IFCHR: u32 = 0o020000;
+const S_IFIFO: u32 = 0o010000;
+
+/// 将 st_mode 格式化为 `ls -l` 风格的权限字符串，例如 `drwxr-sr-t`
+pub fn format_mode(mode: u32) -> String {
+    let file_type = match mode & S_IFMT {
+        S_IFDIR => 'd',
+        S_IFLNK => 'l',
+        S_IFIFO => 'p',
+        S_IFSOCK => 's',
+        S_IFCHR => 'c',
+        S_IFBLK => 'b',
+        S_IFREG => '-',
+        _ => '?',
+    };
+
+    let bit = |mask: u32, c: char| if mode & mask != 0 { c } else { '-' };
+    // 执行位与特殊位 (setuid/setgid/sticky) 共用一个字符
+    let exec = |x_mask: u32, special_mask: u32, set: char, unset: char| match (
+        mode & x_mask != 0,
+        mode & special_mask != 0,
+    ) {
+        (true, true) => set,
+        (false, true) => unset,
+        (true, false) => 'x',
+        (false, false) => '-',
+    };
+
+    [
+        file_type,
+        bit(0o400, 'r'),
+        bit(0o200, 'w'),
+        exec(0o100, 0o4000, 's', 'S'),
+        bit(0o040, 'r'),
+        bit(0o020, 'w'),
+        exec(0o010, 0o2000, 's', 'S'),
+        bit(0o004, 'r'),
+        bit(0o002, 'w'),
+        exec(0o001, 0o1000, 't', 'T'),
+    ]
+    .iter()
+    .collect()
+}
+
+/// 在本地时区中将系统时间格式化为 `YYYY-MM-DD HH:MM`，早于 1970 年的时间同样适用
 pub fn format_system_time(system_time: SystemTime) -> String {
-    match system_time.duration_since(UNIX_EPOCH) {
-        Ok(duration) => {
-            // Format as YYYY-MM-DD HH:MM
-            let secs = duration.as_secs();
-            let datetime = time::OffsetDateTime::from_unix_timestamp(secs as i64)
-                .unwrap_or(time::OffsetDateTime::UNIX_EPOCH);
-            format!(
-                "{:04}-{:02}-{:02} {:02}:{:02}",
-                datetime.year(),
-                datetime.month() as u8,
-                datetime.day(),
-                datetime.hour(),
-                datetime.minute()
-            )
-        }
-        Err(_) => "???".to_string(),
-    }
-}
-
-/// 格式化树形结构的前缀，并根据深度应用颜色
-pub fn format_tree_prefix(is_last: bool, ancestors: &[bool], depth: usize) -> String {
+    format_local(system_time, "%Y-%m-%d %H:%M")
+}
+
+/// 树形连接线默认按深度循环使用的颜色
+pub const DEFAULT_TREE_COLORS: [Color; 6] = [
+    Color::Blue,    // 深度 0
+    Color::Green,   // 深度 1
+    Color::Yellow,  // 深度 2
+    Color::Magenta, // 深度 3 (使用 Magenta 替代 BrightPurple)
+    Color::Cyan,    // 深度 4
+    Color::Red,     // 深度 5
+];
+
+/// 格式化树形结构的前缀，并根据深度循环使用 colors 中的颜色
+pub fn format_tree_prefix(
+    is_last: bool,
+    ancestors: &[bool],
+    depth: usize,
+    colors: &[Color],
+) -> String {
     let mut prefix = String::new();
-    
-    // 定义颜色列表，用于不同层级
-    let colors = [
-        Color::Blue,   // 深度 0
-        Color::Green,  // 深度 1
-        Color::Yellow, // 深度 2
-        Color::Magenta, // 深度 3 (使用 Magenta 替代 BrightPurple)
-        Color::Cyan,   // 深度 4
-        Color::Red,    // 深度 5
-    ];
-    let current_color = colors[depth % colors.len()];
+    let paint = |s: &str| match colors {
+        [] => s.to_string(),
+        _ => s.color(colors[depth % colors.len()]).to_string(),
+    };
 
     // 添加祖先节点的连接线并着色
     for &is_ancestor_last in ancestors {
         if is_ancestor_last {
-            prefix.push_str(&"    ".color(current_color).to_string()); // 空白占位符
+            prefix.push_str(&paint("    ")); // 空白占位符
         } else {
-            prefix.push_str(&"│   ".color(current_color).to_string()); // 垂直连接线
+            prefix.push_str(&paint("│   ")); // 垂直连接线
         }
     }
 
     // 添加当前节点的连接符并着色
     if is_last {
-        prefix.push_str(&"└── ".color(current_color).to_string()); // L形连接符
-        } else {
-        prefix.push_str(&"├── ".color(current_color).to_string()); // T形连接符
+        prefix.push_str(&paint("└── ")); // L形连接符
+    } else {
+        prefix.push_str(&paint("├── ")); // T形连接符
     }
 
     prefix
 }
+
+/// 大小的显示方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SizeStyle {
+    /// 原始字节数
+    #[default]
+    Bytes,
+    /// 以 1024 为进制 (KiB、MiB ...)
+    Binary,
+    /// 以 1000 为进制 (kB、MB ...)
+    Si,
+}
+
+/// 非长格式列表的排列方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ListLayout {
+    /// 每行一项
+    #[default]
+    SingleColumn,
+    /// 多列，先从上到下填满一列 (`ls -C`)
+    Columns,
+    /// 多列，先从左到右填满一行 (`ls -x`)
+    Across,
+}
+
+/// 列之间的空白宽度
+const COLUMN_GAP: usize = 2;
+
+/// 文本输出选项
+#[derive(Debug, Clone)]
+pub struct RenderOptions {
+    /// 长格式，显示权限、属主、大小与修改时间
+    pub long: bool,
+    /// 大小的显示方式
+    pub size_style: SizeStyle,
+    /// 非长格式下也在文件名前显示大小
+    pub show_size: bool,
+    /// 输出顶层目录项大小之和
+    pub show_total: bool,
+    /// 长格式中时间的显示方式
+    pub time_style: TimeStyle,
+    /// 长格式中显示的时间戳
+    pub time_field: TimeField,
+    /// 树形连接线按深度循环使用的颜色
+    pub tree_colors: Vec<Color>,
+    /// 文件名的引用方式
+    pub quoting: QuotingStyle,
+    /// 非长格式列表的排列方式
+    pub layout: ListLayout,
+    /// 多列排列时可用的终端宽度
+    pub width: usize,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        Self {
+            long: false,
+            size_style: SizeStyle::default(),
+            show_size: false,
+            show_total: false,
+            time_style: TimeStyle::default(),
+            time_field: TimeField::default(),
+            tree_colors: DEFAULT_TREE_COLORS.to_vec(),
+            layout: ListLayout::default(),
+            width: 80,
+            quoting: QuotingStyle::default(),
+        }
+    }
+}
+
+/// 按显示方式格式化大小
+pub fn format_size(bytes: u64, style: SizeStyle) -> String {
+    let (base, units) = match style {
+        SizeStyle::Bytes => return bytes.to_string(),
+        SizeStyle::Binary => (1024.0, ["B", "KiB", "MiB", "GiB", "TiB", "PiB", "EiB"]),
+        SizeStyle::Si => (1000.0, ["B", "kB", "MB", "GB", "TB", "PB", "EB"]),
+    };
+
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= base && unit < units.len() - 1 {
+        value /= base;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        format!("{} {}", bytes, units[0])
+    } else {
+        format!("{:.1} {}", value, units[unit])
+    }
+}
+
+/// 生成长格式中文件名之前的各列：权限、硬链接数、属主、属组、大小、时间
+///
+/// 时间默认为修改时间，文件系统没有记录所选时间戳时显示 `-`。
+pub fn format_long_info(entry: &Entry, options: &RenderOptions) -> String {
+    let now = SystemTime::now();
+    let time = match options.time_field.of(entry) {
+        Some(time) => format_time(time, &options.time_style, now),
+        None => {
+            let width = format_time(SystemTime::UNIX_EPOCH, &options.time_style, now);
+            format!("{:<1$}", "-", width.chars().count())
+        }
+    };
+    // 相对时间长短不一，补齐到最长的 `59 seconds ago` 以对齐文件名
+    let time = match options.time_style {
+        TimeStyle::Relative => format!("{:<14}", time),
+        _ => time,
+    };
+    format!(
+        "{} {:>3} {:<8} {:<8} {:>10} {}",
+        format_mode(entry.mode),
+        entry.nlink,
+        owner_display(entry),
+        group_name_display(entry),
+        format_size(entry.size, options.size_style),
+        time
+    )
+}
+
+/// 属主名称 (非 Unix 平台无此信息)
+fn owner_display(entry: &Entry) -> String {
+    if cfg!(unix) {
+        user_display(entry.uid)
+    } else {
+        "-".to_string()
+    }
+}
+
+/// 属组名称 (非 Unix 平台无此信息)
+fn group_name_display(entry: &Entry) -> String {
+    if cfg!(unix) {
+        group_display(entry.gid)
+    } else {
+        "-".to_string()
+    }
+}
+
+/// 按 LS_COLORS 规则为文件名着色，符号链接附带 ` -> 目标`，失效的链接标记为 `[broken]`
+pub fn format_entry_name(entry: &Entry, quoting: QuotingStyle) -> String {
+    let sheet = style_sheet();
+    let name = sheet.paint(entry, &quote_name(&entry.name, quoting));
+    let Some(target) = &entry.symlink_target else {
+        return name;
+    };
+
+    let target = sheet.paint_target(entry, &quote_name(target.as_os_str(), quoting));
+    if entry.is_broken_link() {
+        format!("{} -> {} [broken]", name, target)
+    } else {
+        format!("{} -> {}", name, target)
+    }
+}
+
+/// 格式化单行目录项
+fn format_entry_line(entry: &Entry, options: &RenderOptions) -> String {
+    if options.long {
+        format!(
+            "{} {}",
+            format_long_info(entry, options),
+            format_entry_name(entry, options.quoting)
+        )
+    } else if options.show_size {
+        format!(
+            "{:>10} {}",
+            format_size(entry.size, options.size_style),
+            format_entry_name(entry, options.quoting)
+        )
+    } else {
+        format_entry_name(entry, options.quoting)
+    }
+}
+
+/// 格式化合计行
+fn format_total(entries: &[&Entry], size_style: SizeStyle) -> String {
+    let total: u64 = entries.iter().map(|entry| entry.size).sum();
+    format!("total {}", format_size(total, size_style))
+}
+
+/// 输出平铺的目录列表
+pub fn write_listing<W: Write>(
+    writer: &mut W,
+    entries: &[Entry],
+    options: &RenderOptions,
+) -> io::Result<()> {
+    if options.show_total {
+        let all: Vec<&Entry> = entries.iter().collect();
+        writeln!(writer, "{}", format_total(&all, options.size_style))?;
+    }
+    let lines: Vec<_> = entries
+        .iter()
+        .map(|entry| format_entry_line(entry, options))
+        .collect();
+    if options.long || options.layout == ListLayout::SingleColumn {
+        for line in lines {
+            writeln!(writer, "{}", line)?;
+        }
+        return Ok(());
+    }
+    write_grid(writer, &lines, options.layout, options.width)
+}
+
+/// 文本在终端中占用的列数：忽略颜色转义序列，中日韩等宽字符计为两列
+pub fn display_width(text: &str) -> usize {
+    let mut width = 0;
+    let mut rest = text;
+    while let Some(start) = rest.find('\x1b') {
+        width += rest[..start].width();
+        // SGR 序列以字母结束，例如 `\x1b[01;34m`
+        rest = &rest[start + 1..];
+        let end = rest
+            .find(|c: char| c.is_ascii_alphabetic())
+            .map_or(rest.len(), |i| i + 1);
+        rest = &rest[end..];
+    }
+    width + rest.width()
+}
+
+/// 按终端宽度排成尽量少的行，每列宽度取该列最长的一项，行尾不留空白
+fn write_grid<W: Write>(
+    writer: &mut W,
+    cells: &[String],
+    layout: ListLayout,
+    width: usize,
+) -> io::Result<()> {
+    if cells.is_empty() {
+        return Ok(());
+    }
+    let widths: Vec<_> = cells.iter().map(|cell| display_width(cell)).collect();
+    let count = cells.len();
+    // 每列至少占最窄一项的宽度加上列间距，与 GNU ls 一样只尝试不超过这个上限的列数
+    let min_width = widths.iter().copied().min().unwrap_or(0);
+    let max_columns = ((width + COLUMN_GAP) / (min_width + COLUMN_GAP)).clamp(1, count);
+    // 列数从多到少尝试，第一个放得下的排列即为行数最少的排列；
+    // 行数相同时只需要能排满所有项目的最少列数
+    let (rows, columns) = (1..=max_columns)
+        .rev()
+        .map(|columns| {
+            let columns = count.div_ceil(count.div_ceil(columns));
+            (count.div_ceil(columns), columns)
+        })
+        .find(|&(rows, columns)| {
+            let total: usize = column_widths(&widths, layout, rows, columns).iter().sum();
+            total + COLUMN_GAP * (columns - 1) <= width
+        })
+        .unwrap_or((count, 1));
+
+    let column_widths = column_widths(&widths, layout, rows, columns);
+    for row in 0..rows {
+        let mut line = String::new();
+        for (column, column_width) in column_widths.iter().enumerate() {
+            let Some(index) =
+                grid_index(layout, rows, columns, row, column).filter(|&index| index < count)
+            else {
+                break;
+            };
+            if column > 0 {
+                line.push_str(&" ".repeat(COLUMN_GAP));
+            }
+            line.push_str(&cells[index]);
+            let next = grid_index(layout, rows, columns, row, column + 1);
+            if column + 1 < columns && next.is_some_and(|next| next < count) {
+                line.push_str(&" ".repeat(column_width - widths[index]));
+            }
+        }
+        writeln!(writer, "{}", line)?;
+    }
+    Ok(())
+}
+
+/// 网格中第 row 行第 column 列对应的项目序号
+fn grid_index(
+    layout: ListLayout,
+    rows: usize,
+    columns: usize,
+    row: usize,
+    column: usize,
+) -> Option<usize> {
+    if column >= columns {
+        return None;
+    }
+    Some(match layout {
+        ListLayout::Across => row * columns + column,
+        _ => column * rows + row,
+    })
+}
+
+/// 每列的宽度
+fn column_widths(widths: &[usize], layout: ListLayout, rows: usize, columns: usize) -> Vec<usize> {
+    let mut column_widths = vec![0; columns];
+    for (index, &width) in widths.iter().enumerate() {
+        let column = match layout {
+            ListLayout::Across => index % columns,
+            _ => index / rows,
+        };
+        column_widths[column] = column_widths[column].max(width);
+    }
+    column_widths
+}
+
+/// 每行输出一个目录项的完整路径
+pub fn write_paths<W: Write>(
+    writer: &mut W,
+    entries: &[Entry],
+    quoting: QuotingStyle,
+) -> io::Result<()> {
+    let sheet = style_sheet();
+    for entry in entries {
+        let path = quote_name(entry.path.as_os_str(), quoting);
+        writeln!(writer, "{}", sheet.paint(entry, &path))?;
+    }
+    Ok(())
+}
+
+/// 输出以 NUL 分隔的原始路径，供 `xargs -0` 等工具使用，不做着色与引用
+pub fn write_paths_null<W: Write>(writer: &mut W, entries: &[Entry]) -> io::Result<()> {
+    for entry in entries {
+        writer.write_all(&name_bytes(entry.path.as_os_str()))?;
+        writer.write_all(b"\0")?;
+    }
+    Ok(())
+}
+
+/// 以 `原路径 -> 新名称` 的形式输出重命名预览，箭头按原路径的宽度对齐
+pub fn write_renames<W: Write>(
+    writer: &mut W,
+    renames: &[Rename],
+    quoting: QuotingStyle,
+) -> io::Result<()> {
+    let sheet = style_sheet();
+    let sources: Vec<_> = renames
+        .iter()
+        .map(|rename| quote_name(rename.entry.path.as_os_str(), quoting))
+        .collect();
+    let width = sources.iter().map(|s| display_width(s)).max().unwrap_or(0);
+    let arrow = "->".cyan().to_string();
+    for (rename, source) in renames.iter().zip(&sources) {
+        let target = quote_name(rename.target.file_name().unwrap_or_default(), quoting);
+        writeln!(
+            writer,
+            "{}{} {} {}",
+            sheet.paint(&rename.entry, source),
+            " ".repeat(width - display_width(source)),
+            arrow,
+            sheet.paint(&rename.entry, &target)
+        )?;
+    }
+    Ok(())
+}
+
+/// 文件类型的完整名称，用于 `fmg stat`
+fn kind_description(entry: &Entry) -> &'static str {
+    match entry.kind {
+        EntryKind::File if entry.size == 0 => "regular empty file",
+        EntryKind::File => "regular file",
+        EntryKind::Dir => "directory",
+        EntryKind::Symlink => "symbolic link",
+        EntryKind::Fifo => "fifo",
+        EntryKind::Socket => "socket",
+        EntryKind::CharDevice => "character special file",
+        EntryKind::BlockDevice => "block special file",
+        EntryKind::Other => "unknown",
+    }
+}
+
+/// 扩展属性的值：可打印的文本加引号显示，其余以十六进制显示
+pub fn format_xattr_value(value: &[u8]) -> String {
+    match std::str::from_utf8(value) {
+        Ok(text) if !text.chars().any(char::is_control) => format!("{:?}", text),
+        _ => {
+            let hex: String = value.iter().map(|byte| format!("{:02x}", byte)).collect();
+            format!("0x{}", hex)
+        }
+    }
+}
+
+/// 以 `stat(1)` 风格输出路径的详细元数据，时间精确到纳秒并带时区
+pub fn write_stat<W: Write>(
+    writer: &mut W,
+    stat: &FileStat,
+    quoting: QuotingStyle,
+) -> io::Result<()> {
+    let entry = &stat.entry;
+    let label = |name: &str| format!("{:>6}:", name).bold();
+    let mut file = quote_name(entry.path.as_os_str(), quoting);
+    if let Some(target) = &entry.symlink_target {
+        file = format!("{} -> {}", file, quote_name(target.as_os_str(), quoting));
+    }
+    writeln!(writer, "{} {}", label("File"), file)?;
+    writeln!(
+        writer,
+        "{} {:<15} {} {:<10} {} {:<6} {}",
+        label("Size"),
+        entry.size,
+        "Blocks:".bold(),
+        stat.blocks,
+        "IO Block:".bold(),
+        stat.block_size,
+        kind_description(entry)
+    )?;
+    let mut device = format!(
+        "{} {:<15} {} {:<10} {} {}",
+        label("Device"),
+        format!("{:x}h/{}d", entry.dev, entry.dev),
+        "Inode:".bold(),
+        entry.ino,
+        "Links:".bold(),
+        entry.nlink
+    );
+    if matches!(entry.kind, EntryKind::CharDevice | EntryKind::BlockDevice) {
+        device.push_str(&format!(
+            "  {} {:x}h/{}d",
+            "Device type:".bold(),
+            stat.rdev,
+            stat.rdev
+        ));
+    }
+    writeln!(writer, "{}", device)?;
+    writeln!(
+        writer,
+        "{} ({:04o}/{})  {} ({:>5}/{:>8})  {} ({:>5}/{:>8})",
+        label("Access"),
+        entry.mode & 0o7777,
+        format_mode(entry.mode),
+        "Uid:".bold(),
+        entry.uid,
+        owner_display(entry),
+        "Gid:".bold(),
+        entry.gid,
+        group_name_display(entry)
+    )?;
+    writeln!(
+        writer,
+        "{} {}",
+        label("MIME"),
+        stat.mime.as_deref().unwrap_or("-")
+    )?;
+
+    let times = [
+        ("Access", entry.accessed),
+        ("Modify", entry.modified),
+        ("Change", entry.changed),
+        ("Birth", entry.created),
+    ];
+    for (name, time) in times {
+        let time = time.map_or_else(|| "-".to_string(), format_full_time);
+        writeln!(writer, "{} {}", label(name), time)?;
+    }
+
+    for xattr in &stat.xattrs {
+        writeln!(
+            writer,
+            "{} {}={}",
+            label("Xattr"),
+            xattr.name,
+            format_xattr_value(&xattr.value)
+        )?;
+    }
+    Ok(())
+}
+
+/// 高亮一行中的匹配部分
+fn highlight_matches(line: &GrepLine) -> String {
+    let mut output = String::with_capacity(line.text.len());
+    let mut last = 0;
+    for range in line.matches.iter().filter(|range| !range.is_empty()) {
+        output.push_str(&line.text[last..range.start]);
+        output.push_str(&line.text[range.clone()].red().bold().to_string());
+        last = range.end;
+    }
+    output.push_str(&line.text[last..]);
+    output
+}
+
+/// 以 `路径:行号:内容` 的形式输出内容搜索结果，上下文行使用 `-` 分隔
+///
+/// separate_groups 为 true 时 (有上下文行)，不相邻的行组之间输出 `--`。
+pub fn write_grep_matches<W: Write>(
+    writer: &mut W,
+    files: &[FileMatches],
+    quoting: QuotingStyle,
+    separate_groups: bool,
+) -> io::Result<()> {
+    let sheet = style_sheet();
+    let separator = "--".cyan().to_string();
+    let mut first_group = true;
+
+    for file in files {
+        let path = sheet.paint(
+            &file.entry,
+            &quote_name(file.entry.path.as_os_str(), quoting),
+        );
+        if file.binary {
+            writeln!(writer, "Binary file {} matches", path)?;
+            continue;
+        }
+
+        let mut previous = None;
+        for line in &file.lines {
+            if separate_groups
+                && !first_group
+                && previous.is_none_or(|number| number + 1 != line.number)
+            {
+                writeln!(writer, "{}", separator)?;
+            }
+            first_group = false;
+            previous = Some(line.number);
+
+            let delimiter = match line.kind {
+                LineKind::Match => ':',
+                LineKind::Context => '-',
+            };
+            writeln!(
+                writer,
+                "{}{}{}{}{}",
+                path,
+                delimiter,
+                line.number.to_string().green(),
+                delimiter,
+                highlight_matches(line)
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// 以 `路径:匹配行数` 的形式输出每个文件的匹配数量
+pub fn write_match_counts<W: Write>(
+    writer: &mut W,
+    files: &[FileMatches],
+    quoting: QuotingStyle,
+) -> io::Result<()> {
+    let sheet = style_sheet();
+    for file in files {
+        let path = quote_name(file.entry.path.as_os_str(), quoting);
+        writeln!(
+            writer,
+            "{}:{}",
+            sheet.paint(&file.entry, &path),
+            file.match_count
+        )?;
+    }
+    Ok(())
+}
+
+/// 以树形结构输出目录树，根节点本身不输出
+pub fn write_tree<W: Write>(
+    writer: &mut W,
+    tree: &DirTree,
+    options: &RenderOptions,
+) -> io::Result<()> {
+    let mut ancestors = Vec::new();
+    write_tree_children(writer, tree, options, &mut ancestors)?;
+
+    if options.show_total {
+        let top_level: Vec<&Entry> = tree.children.iter().flatten().map(|c| &c.entry).collect();
+        writeln!(writer, "{}", format_total(&top_level, options.size_style))?;
+    }
+    Ok(())
+}
+
+/// 递归输出子节点，ancestors 记录每一层祖先是否为最后一个节点
+fn write_tree_children<W: Write>(
+    writer: &mut W,
+    node: &DirTree,
+    options: &RenderOptions,
+    ancestors: &mut Vec<bool>,
+) -> io::Result<()> {
+    let Some(children) = &node.children else {
+        return Ok(());
+    };
+
+    let depth = ancestors.len();
+    for (i, child) in children.iter().enumerate() {
+        let is_last = i == children.len() - 1;
+        let prefix = format_tree_prefix(is_last, ancestors, depth, &options.tree_colors);
+        writeln!(
+            writer,
+            "{}{}",
+            prefix,
+            format_entry_line(&child.entry, options)
+        )?;
+
+        ancestors.push(is_last);
+        write_tree_children(writer, child, options, ancestors)?;
+        ancestors.pop();
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_mode_basic() {
+        assert_eq!(format_mode(S_IFDIR | 0o755), "drwxr-xr-x");
+        assert_eq!(format_mode(S_IFREG | 0o644), "-rw-r--r--");
+        assert_eq!(format_mode(S_IFLNK | 0o777), "lrwxrwxrwx");
+    }
+
+    #[test]
+    fn test_format_mode_special_bits() {
+        assert_eq!(format_mode(S_IFREG | 0o4755), "-rwsr-xr-x");
+        assert_eq!(format_mode(S_IFREG | 0o4644), "-rwSr--r--");
+        assert_eq!(format_mode(S_IFDIR | 0o2775), "drwxrwsr-x");
+        assert_eq!(format_mode(S_IFDIR | 0o1777), "drwxrwxrwt");
+        assert_eq!(format_mode(S_IFDIR | 0o1770), "drwxrwx--T");
+    }
+
+    #[test]
+    fn test_format_mode_file_types() {
+        assert!(format_mode(S_IFIFO | 0o600).starts_with('p'));
+        assert!(format_mode(S_IFSOCK | 0o600).starts_with('s'));
+        assert!(format_mode(S_IFCHR | 0o600).starts_with('c'));
+        assert!(format_mode(S_IFBLK | 0o600).starts_with('b'));
+    }
+
+    #[test]
+    fn test_write_tree_plain() {
+        use crate::core::file_ops::{TreeOptions, walk_tree};
+
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        std::fs::create_dir_all(dir.join("sub")).unwrap();
+        std::fs::write(dir.join("sub").join("inner"), "").unwrap();
+        std::fs::write(dir.join("top"), "").unwrap();
+
+        colored::control::set_override(false);
+        let listing = walk_tree(dir, &TreeOptions::default()).unwrap();
+        let mut output = Vec::new();
+        write_tree(&mut output, &listing.tree, &RenderOptions::default()).unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "├── sub\n│   └── inner\n└── top\n"
+        );
+    }
+
+    #[test]
+    fn test_write_grid() {
+        let cells: Vec<String> = ["a", "bb", "文件", "ddd", "e"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let grid = |layout, width| {
+            let mut output = Vec::new();
+            write_grid(&mut output, &cells, layout, width).unwrap();
+            String::from_utf8(output).unwrap()
+        };
+
+        // 宽字符占两列，列宽按显示宽度计算
+        assert_eq!(grid(ListLayout::Columns, 14), "a   文件  e\nbb  ddd\n");
+        assert_eq!(grid(ListLayout::Across, 14), "a    bb  文件\nddd  e\n");
+        assert_eq!(grid(ListLayout::Columns, 3), "a\nbb\n文件\nddd\ne\n");
+        assert_eq!(grid(ListLayout::Columns, 80), "a  bb  文件  ddd  e\n");
+        assert_eq!(display_width("\x1b[01;34m目录\x1b[0m/"), 5);
+    }
+
+    #[test]
+    fn test_format_size() {
+        assert_eq!(format_size(4096, SizeStyle::Bytes), "4096");
+        assert_eq!(format_size(512, SizeStyle::Binary), "512 B");
+        assert_eq!(format_size(1536, SizeStyle::Binary), "1.5 KiB");
+        assert_eq!(format_size(5 * 1024 * 1024, SizeStyle::Binary), "5.0 MiB");
+        assert_eq!(format_size(1500, SizeStyle::Si), "1.5 kB");
+        assert_eq!(format_size(2_000_000_000, SizeStyle::Si), "2.0 GB");
+    }
+}
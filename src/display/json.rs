@@ -0,0 +1,188 @@
+//! JSON / NDJSON 输出模块
+
+use crate::core::models::{DirTree, Entry, EntryKind};
+use crate::core::stat::FileStat;
+use crate::core::users::{group_display, user_display};
+use crate::display::formatter::format_mode;
+use crate::display::time::utc_datetime;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+use std::time::SystemTime;
+use time::format_description::well_known::Rfc3339;
+
+/// 单个目录项的 JSON 表示
+#[derive(Debug, Serialize)]
+pub struct JsonEntry {
+    pub name: String,
+    pub path: String,
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+    pub size: u64,
+    pub mode: String,
+    pub mode_octal: String,
+    pub nlink: u64,
+    pub owner: String,
+    pub group: String,
+    /// 修改时间 (mtime)，以 UTC 的 RFC 3339 表示，例如 `2024-02-29T13:05:09.5Z`；
+    /// 平台不支持或超出可表示范围时为 null，下同
+    pub modified: Option<String>,
+    /// 访问时间 (atime)
+    pub accessed: Option<String>,
+    /// 状态改变时间 (ctime)
+    pub changed: Option<String>,
+    /// 创建时间 (birth time)
+    pub created: Option<String>,
+    pub symlink_target: Option<String>,
+    /// 失效的符号链接
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub broken: bool,
+    pub depth: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub children: Option<Vec<JsonEntry>>,
+}
+
+impl JsonEntry {
+    /// 根据目录项构建 JSON 条目
+    pub fn new(entry: &Entry) -> Self {
+        let (owner, group) = if cfg!(unix) {
+            (user_display(entry.uid), group_display(entry.gid))
+        } else {
+            (String::new(), String::new())
+        };
+
+        Self {
+            name: entry.name_lossy().into_owned(),
+            path: entry.path.to_string_lossy().into_owned(),
+            kind: entry.kind.as_str(),
+            size: entry.size,
+            mode: format_mode(entry.mode),
+            mode_octal: format!("{:04o}", entry.mode & 0o7777),
+            nlink: entry.nlink,
+            owner,
+            group,
+            modified: entry.modified.and_then(format_rfc3339),
+            accessed: entry.accessed.and_then(format_rfc3339),
+            changed: entry.changed.and_then(format_rfc3339),
+            created: entry.created.and_then(format_rfc3339),
+            symlink_target: entry
+                .symlink_target
+                .as_ref()
+                .map(|target| target.to_string_lossy().into_owned()),
+            broken: entry.is_broken_link(),
+            depth: entry.depth,
+            children: None,
+        }
+    }
+
+    /// 根据目录树节点构建 JSON 条目，展开的目录带有 children 字段
+    pub fn from_tree(node: &DirTree) -> Self {
+        let mut json_entry = Self::new(&node.entry);
+        json_entry.children = node
+            .children
+            .as_ref()
+            .map(|children| children.iter().map(Self::from_tree).collect());
+        json_entry
+    }
+}
+
+/// `fmg stat` 的 JSON 表示，时间为精确到纳秒的 RFC 3339 字符串
+#[derive(Debug, Serialize)]
+pub struct JsonStat {
+    pub path: String,
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+    pub size: u64,
+    pub blocks: u64,
+    pub block_size: u64,
+    pub device: u64,
+    pub inode: u64,
+    pub nlink: u64,
+    pub mode: String,
+    pub mode_octal: String,
+    pub uid: u32,
+    pub gid: u32,
+    pub owner: String,
+    pub group: String,
+    /// 各项时间与 [`JsonEntry`] 相同，以 UTC 的 RFC 3339 表示
+    pub accessed: Option<String>,
+    pub modified: Option<String>,
+    pub changed: Option<String>,
+    pub created: Option<String>,
+    pub symlink_target: Option<String>,
+    /// 字符设备与块设备的设备号
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub device_type: Option<u64>,
+    pub mime: Option<String>,
+    /// 扩展属性，不是 UTF-8 的值以 `0x` 开头的十六进制表示
+    pub xattrs: BTreeMap<String, String>,
+}
+
+impl JsonStat {
+    pub fn new(stat: &FileStat) -> Self {
+        let entry = &stat.entry;
+        let base = JsonEntry::new(entry);
+        let is_device = matches!(entry.kind, EntryKind::CharDevice | EntryKind::BlockDevice);
+        Self {
+            path: base.path,
+            kind: base.kind,
+            size: entry.size,
+            blocks: stat.blocks,
+            block_size: stat.block_size,
+            device: entry.dev,
+            inode: entry.ino,
+            nlink: entry.nlink,
+            mode: base.mode,
+            mode_octal: base.mode_octal,
+            uid: entry.uid,
+            gid: entry.gid,
+            owner: base.owner,
+            group: base.group,
+            accessed: base.accessed,
+            modified: base.modified,
+            changed: base.changed,
+            created: base.created,
+            symlink_target: base.symlink_target,
+            device_type: is_device.then_some(stat.rdev),
+            mime: stat.mime.clone(),
+            xattrs: stat
+                .xattrs
+                .iter()
+                .map(|xattr| {
+                    let value = match std::str::from_utf8(&xattr.value) {
+                        Ok(text) => text.to_string(),
+                        Err(_) => xattr.value.iter().fold("0x".to_string(), |mut hex, byte| {
+                            hex.push_str(&format!("{:02x}", byte));
+                            hex
+                        }),
+                    };
+                    (xattr.name.clone(), value)
+                })
+                .collect(),
+        }
+    }
+}
+
+/// 将系统时间格式化为 UTC 的 RFC 3339 字符串，超出可表示范围时为 None
+fn format_rfc3339(system_time: SystemTime) -> Option<String> {
+    utc_datetime(system_time)?.format(&Rfc3339).ok()
+}
+
+/// 以 JSON 数组形式输出全部条目
+pub fn write_json<W: Write>(writer: &mut W, entries: &[JsonEntry]) -> io::Result<()> {
+    serde_json::to_writer_pretty(&mut *writer, entries)?;
+    writeln!(writer)
+}
+
+/// 以 NDJSON 形式输出，每行一个条目，嵌套的子条目按深度优先顺序展开
+pub fn write_ndjson<W: Write>(writer: &mut W, entries: Vec<JsonEntry>) -> io::Result<()> {
+    for mut entry in entries {
+        let children = entry.children.take();
+        serde_json::to_writer(&mut *writer, &entry)?;
+        writeln!(writer)?;
+        if let Some(children) = children {
+            write_ndjson(writer, children)?;
+        }
+    }
+    Ok(())
+}
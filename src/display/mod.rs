@@ -1,4 +1,8 @@
 //! 显示模块 - 处理各种输出格式和样式
 
 pub mod formatter;
+pub mod json;
+pub mod progress;
+pub mod quoting;
 pub mod styles;
+pub mod time;
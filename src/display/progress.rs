@@ -0,0 +1,142 @@
+//! 进度条模块：在标准错误上显示已处理的字节数、速度与剩余时间
+
+use crate::core::copy::CopyProgress;
+use crate::core::models::Entry;
+use crate::display::formatter::{SizeStyle, format_size};
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+/// 进度条的宽度 (字符)
+const BAR_WIDTH: usize = 24;
+
+/// 标签的最大显示长度 (字符)
+const LABEL_WIDTH: usize = 32;
+
+/// 两次重绘之间的最小间隔
+const REDRAW_INTERVAL: Duration = Duration::from_millis(100);
+
+/// 字节进度条，disabled 时不输出任何内容
+pub struct ProgressBar {
+    total: u64,
+    done: u64,
+    started: Instant,
+    last_draw: Option<Instant>,
+    enabled: bool,
+}
+
+impl ProgressBar {
+    /// 创建进度条；enabled 通常取决于标准错误是否为终端
+    pub fn new(total: u64, enabled: bool) -> Self {
+        Self {
+            total,
+            done: 0,
+            started: Instant::now(),
+            last_draw: None,
+            enabled,
+        }
+    }
+
+    /// 增加已处理的字节数，并在需要时重绘
+    pub fn advance(&mut self, bytes: u64, label: &str) {
+        self.done += bytes;
+        if !self.enabled {
+            return;
+        }
+        let now = Instant::now();
+        if self
+            .last_draw
+            .is_some_and(|last| now.duration_since(last) < REDRAW_INTERVAL)
+            && self.done < self.total
+        {
+            return;
+        }
+        self.last_draw = Some(now);
+        let line = format_progress(self.done, self.total, self.started.elapsed(), label);
+        let mut stderr = io::stderr().lock();
+        let _ = write!(stderr, "\r\x1b[K{}", line);
+        let _ = stderr.flush();
+    }
+
+    /// 清除进度条所在的行
+    pub fn finish(&mut self) {
+        if self.enabled && self.last_draw.is_some() {
+            let mut stderr = io::stderr().lock();
+            let _ = write!(stderr, "\r\x1b[K");
+            let _ = stderr.flush();
+        }
+    }
+}
+
+/// 复制开始时累加总字节数，因此同一个进度条可以依次用于多次复制
+impl CopyProgress for ProgressBar {
+    fn begin(&mut self, total: u64) {
+        self.total += total;
+    }
+
+    fn copied(&mut self, entry: &Entry, bytes: u64) {
+        self.advance(bytes, &entry.name_lossy());
+    }
+}
+
+/// 格式化一行进度，例如 `[#######-----]  58%  1.2 MiB / 2.0 MiB  3.1 MiB/s  ETA 00:01  big.iso`
+pub fn format_progress(done: u64, total: u64, elapsed: Duration, label: &str) -> String {
+    let ratio = if total == 0 {
+        1.0
+    } else {
+        (done as f64 / total as f64).min(1.0)
+    };
+    let filled = (ratio * BAR_WIDTH as f64).round() as usize;
+    let secs = elapsed.as_secs_f64();
+    let rate = if secs > 0.0 { done as f64 / secs } else { 0.0 };
+    let eta = if rate > 0.0 {
+        format_duration(Duration::from_secs_f64(
+            total.saturating_sub(done) as f64 / rate,
+        ))
+    } else {
+        "--:--".to_string()
+    };
+
+    let label = if label.chars().count() > LABEL_WIDTH {
+        let head: String = label.chars().take(LABEL_WIDTH - 1).collect();
+        format!("{}…", head)
+    } else {
+        label.to_string()
+    };
+    format!(
+        "[{}{}] {:>3.0}%  {} / {}  {}/s  ETA {}  {}",
+        "#".repeat(filled),
+        "-".repeat(BAR_WIDTH - filled),
+        ratio * 100.0,
+        format_size(done, SizeStyle::Binary),
+        format_size(total, SizeStyle::Binary),
+        format_size(rate as u64, SizeStyle::Binary),
+        eta,
+        label
+    )
+}
+
+/// 将时长格式化为 `MM:SS`，超过一小时时为 `H:MM:SS`
+fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    if secs >= 3600 {
+        format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+    } else {
+        format!("{:02}:{:02}", secs / 60, secs % 60)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_progress() {
+        let line = format_progress(512 * 1024, 2048 * 1024, Duration::from_secs(1), "big.iso");
+        assert_eq!(
+            line,
+            "[######------------------]  25%  512.0 KiB / 2.0 MiB  512.0 KiB/s  ETA 00:03  big.iso"
+        );
+        assert_eq!(format_duration(Duration::from_secs(3725)), "1:02:05");
+        assert!(format_progress(0, 0, Duration::ZERO, "").contains("100%  0 B / 0 B"));
+    }
+}
@@ -0,0 +1,202 @@
+//! 文件名引用模块：将任意字节组成的文件名转换为无歧义的可显示文本
+
+use clap::ValueEnum;
+use std::ffi::OsStr;
+
+/// 文件名的引用方式 (变体注释会作为命令行帮助显示)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum QuotingStyle {
+    /// Print names as-is; invalid UTF-8 is shown as U+FFFD
+    #[default]
+    Literal,
+    /// Backslash-escape spaces, control characters and invalid bytes
+    Escape,
+    /// Quote for POSIX shells, using $'...' for unprintable bytes
+    Shell,
+    /// Double-quote like a C string literal
+    C,
+}
+
+/// 文件名中的一个片段：合法的 UTF-8 字符或无法解码的字节
+enum Unit {
+    Char(char),
+    Byte(u8),
+}
+
+/// 按所选方式引用文件名
+pub fn quote_name(name: &OsStr, style: QuotingStyle) -> String {
+    match style {
+        QuotingStyle::Literal => name.to_string_lossy().into_owned(),
+        QuotingStyle::Escape => escape(name, false),
+        QuotingStyle::C => format!("\"{}\"", escape(name, true)),
+        QuotingStyle::Shell => shell_quote(name),
+    }
+}
+
+/// 将文件名拆分为字符与无效字节
+fn units(name: &OsStr) -> Vec<Unit> {
+    let mut units = Vec::new();
+    for chunk in name_bytes(name).utf8_chunks() {
+        units.extend(chunk.valid().chars().map(Unit::Char));
+        units.extend(chunk.invalid().iter().map(|&b| Unit::Byte(b)));
+    }
+    units
+}
+
+/// 文件名的原始字节
+#[cfg(unix)]
+pub(crate) fn name_bytes(name: &OsStr) -> std::borrow::Cow<'_, [u8]> {
+    use std::os::unix::ffi::OsStrExt;
+    std::borrow::Cow::Borrowed(name.as_bytes())
+}
+
+/// 文件名的原始字节 (非 Unix 平台的文件名总能转换为 UTF-8 近似值)
+#[cfg(not(unix))]
+pub(crate) fn name_bytes(name: &OsStr) -> std::borrow::Cow<'_, [u8]> {
+    match name.to_string_lossy() {
+        std::borrow::Cow::Borrowed(s) => std::borrow::Cow::Borrowed(s.as_bytes()),
+        std::borrow::Cow::Owned(s) => std::borrow::Cow::Owned(s.into_bytes()),
+    }
+}
+
+/// C 风格的转义；in_quotes 为 true 时转义双引号，否则转义空格
+fn escape(name: &OsStr, in_quotes: bool) -> String {
+    let mut output = String::new();
+    for unit in units(name) {
+        match unit {
+            Unit::Char(c) => push_escaped_char(&mut output, c, in_quotes),
+            Unit::Byte(b) => output.push_str(&format!("\\{:03o}", b)),
+        }
+    }
+    output
+}
+
+/// 写入一个需要时经过转义的字符
+fn push_escaped_char(output: &mut String, c: char, in_quotes: bool) {
+    match c {
+        '\\' => output.push_str("\\\\"),
+        '\n' => output.push_str("\\n"),
+        '\t' => output.push_str("\\t"),
+        '\r' => output.push_str("\\r"),
+        '"' if in_quotes => output.push_str("\\\""),
+        ' ' if !in_quotes => output.push_str("\\ "),
+        c if c.is_control() => {
+            let mut buf = [0; 4];
+            for b in c.encode_utf8(&mut buf).bytes() {
+                output.push_str(&format!("\\{:03o}", b));
+            }
+        }
+        c => output.push(c),
+    }
+}
+
+/// 在 shell 中无需引用的字符
+fn is_shell_safe(c: char) -> bool {
+    c.is_alphanumeric() || ",._+:@%/=-".contains(c)
+}
+
+/// 按 POSIX shell 的规则引用：可打印的部分使用单引号，其余使用 $'...'
+fn shell_quote(name: &OsStr) -> String {
+    let units = units(name);
+    let printable = |unit: &Unit| matches!(unit, Unit::Char(c) if !c.is_control());
+
+    if !units.is_empty()
+        && units
+            .iter()
+            .all(|unit| matches!(unit, Unit::Char(c) if is_shell_safe(*c)))
+    {
+        return name.to_string_lossy().into_owned();
+    }
+
+    let mut output = String::new();
+    let mut i = 0;
+    while i < units.len() {
+        let start = i;
+        if printable(&units[i]) {
+            while i < units.len() && printable(&units[i]) {
+                i += 1;
+            }
+            output.push('\'');
+            for unit in &units[start..i] {
+                match unit {
+                    Unit::Char('\'') => output.push_str("'\\''"),
+                    Unit::Char(c) => output.push(*c),
+                    Unit::Byte(_) => unreachable!(),
+                }
+            }
+            output.push('\'');
+        } else {
+            while i < units.len() && !printable(&units[i]) {
+                i += 1;
+            }
+            output.push_str("$'");
+            for unit in &units[start..i] {
+                match unit {
+                    Unit::Char(c) => push_escaped_char(&mut output, *c, true),
+                    Unit::Byte(b) => output.push_str(&format!("\\{:03o}", b)),
+                }
+            }
+            output.push('\'');
+        }
+    }
+    if output.is_empty() {
+        output.push_str("''");
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quote_printable_names() {
+        let name = OsStr::new("it's a file.txt");
+        assert_eq!(quote_name(name, QuotingStyle::Literal), "it's a file.txt");
+        assert_eq!(
+            quote_name(name, QuotingStyle::Escape),
+            "it's\\ a\\ file.txt"
+        );
+        assert_eq!(quote_name(name, QuotingStyle::C), "\"it's a file.txt\"");
+        assert_eq!(
+            quote_name(name, QuotingStyle::Shell),
+            "'it'\\''s a file.txt'"
+        );
+        assert_eq!(
+            quote_name(OsStr::new("plain-名字.rs"), QuotingStyle::Shell),
+            "plain-名字.rs"
+        );
+    }
+
+    #[test]
+    fn test_quote_control_characters() {
+        let name = OsStr::new("a\nb");
+        assert_eq!(quote_name(name, QuotingStyle::Escape), "a\\nb");
+        assert_eq!(quote_name(name, QuotingStyle::C), "\"a\\nb\"");
+        assert_eq!(quote_name(name, QuotingStyle::Shell), "'a'$'\\n''b'");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_quote_invalid_utf8() {
+        use std::os::unix::ffi::OsStrExt;
+        // GBK 编码的 "中文"
+        let name = OsStr::from_bytes(b"\xd6\xd0\xce\xc4.txt");
+        assert_eq!(
+            quote_name(name, QuotingStyle::Literal),
+            "\u{fffd}\u{fffd}\u{fffd}\u{fffd}.txt"
+        );
+        assert_eq!(
+            quote_name(name, QuotingStyle::Escape),
+            "\\326\\320\\316\\304.txt"
+        );
+        assert_eq!(
+            quote_name(name, QuotingStyle::C),
+            "\"\\326\\320\\316\\304.txt\""
+        );
+        assert_eq!(
+            quote_name(name, QuotingStyle::Shell),
+            "$'\\326\\320\\316\\304''.txt'"
+        );
+    }
+}
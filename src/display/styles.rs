@@ -1,3 +1,350 @@
 //! 样式和颜色处理模块
 
-// 这里将包含颜色方案、样式设置等功能
+use crate::core::models::{Entry, EntryKind};
+use clap::ValueEnum;
+use std::collections::HashMap;
+use std::ffi::OsStr;
+use std::sync::OnceLock;
+
+/// 未设置 LS_COLORS 时使用的默认配色 (在 GNU dircolors 默认值的基础上增加源代码文件)
+const DEFAULT_LS_COLORS: &str = concat!(
+    "rs=0:di=01;34:ln=01;36:mh=00:pi=40;33:so=01;35:do=01;35:bd=40;33;01:cd=40;33;01:",
+    "or=40;31;01:mi=00:su=37;41:sg=30;43:ca=00:tw=30;42:ow=34;42:st=37;44:ex=01;32:",
+    // 压缩包
+    "*.tar=01;31:*.tgz=01;31:*.gz=01;31:*.bz2=01;31:*.xz=01;31:*.zst=01;31:*.zip=01;31:",
+    "*.7z=01;31:*.rar=01;31:*.deb=01;31:*.rpm=01;31:*.jar=01;31:",
+    // 图片
+    "*.jpg=01;35:*.jpeg=01;35:*.png=01;35:*.gif=01;35:*.bmp=01;35:*.svg=01;35:",
+    "*.webp=01;35:*.tif=01;35:*.tiff=01;35:*.ico=01;35:",
+    // 音频
+    "*.mp3=00;36:*.flac=00;36:*.ogg=00;36:*.wav=00;36:",
+    // 源代码
+    "*.rs=00;33:*.c=00;33:*.h=00;33:*.cpp=00;33:*.hpp=00;33:*.py=00;33:*.js=00;33:",
+    "*.ts=00;33:*.go=00;33:*.java=00;33:*.rb=00;33:*.sh=00;33",
+);
+
+/// dircolors 数据库中的关键字与 LS_COLORS 指示符的对应关系
+const DIRCOLORS_KEYWORDS: &[(&str, &str)] = &[
+    ("NORMAL", "no"),
+    ("NORM", "no"),
+    ("FILE", "fi"),
+    ("RESET", "rs"),
+    ("DIR", "di"),
+    ("LINK", "ln"),
+    ("LNK", "ln"),
+    ("SYMLINK", "ln"),
+    ("ORPHAN", "or"),
+    ("MISSING", "mi"),
+    ("FIFO", "pi"),
+    ("PIPE", "pi"),
+    ("SOCK", "so"),
+    ("DOOR", "do"),
+    ("BLK", "bd"),
+    ("BLOCK", "bd"),
+    ("CHR", "cd"),
+    ("CHAR", "cd"),
+    ("EXEC", "ex"),
+    ("SETUID", "su"),
+    ("SETGID", "sg"),
+    ("CAPABILITY", "ca"),
+    ("STICKY_OTHER_WRITABLE", "tw"),
+    ("OTHER_WRITABLE", "ow"),
+    ("STICKY", "st"),
+    ("MULTIHARDLINK", "mh"),
+];
+
+/// 何时输出颜色 (变体注释会作为命令行帮助显示)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum ColorChoice {
+    /// Color when stdout is a terminal, honouring NO_COLOR and CLICOLOR_FORCE
+    #[default]
+    Auto,
+    /// Always emit color escape sequences
+    Always,
+    /// Never emit color escape sequences
+    Never,
+}
+
+impl ColorChoice {
+    /// 根据选项、环境变量以及标准输出是否为终端决定是否着色
+    pub fn should_colorize(self, is_terminal: bool) -> bool {
+        match self {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto => env_override(
+                std::env::var_os("NO_COLOR").as_deref(),
+                std::env::var_os("CLICOLOR_FORCE").as_deref(),
+            )
+            .unwrap_or(is_terminal),
+        }
+    }
+}
+
+/// 环境变量对自动着色的覆盖：CLICOLOR_FORCE 优先于 NO_COLOR，空值或 "0" 视为未设置
+fn env_override(no_color: Option<&OsStr>, clicolor_force: Option<&OsStr>) -> Option<bool> {
+    let is_set = |value: Option<&OsStr>| value.is_some_and(|v| !v.is_empty() && v != "0");
+    if is_set(clicolor_force) {
+        Some(true)
+    } else if no_color.is_some_and(|v| !v.is_empty()) {
+        Some(false)
+    } else {
+        None
+    }
+}
+
+/// 按文件类型与扩展名为文件名着色的样式表，规则与 LS_COLORS 相同
+#[derive(Debug, Clone, Default)]
+pub struct StyleSheet {
+    /// 类型指示符 (di、ln、ex 等) 对应的 SGR 参数
+    indicators: HashMap<String, String>,
+    /// 文件名后缀 (小写) 对应的 SGR 参数，后定义的优先
+    suffixes: Vec<(String, String)>,
+}
+
+impl StyleSheet {
+    /// 解析 LS_COLORS 格式的配置，例如 `di=01;34:*.tar=01;31`
+    pub fn from_ls_colors(spec: &str) -> Self {
+        let mut sheet = Self::default();
+        for item in spec.split(':') {
+            let Some((key, value)) = item.split_once('=') else {
+                continue;
+            };
+            match key.strip_prefix('*') {
+                Some(suffix) => sheet
+                    .suffixes
+                    .push((suffix.to_lowercase(), value.to_string())),
+                None => {
+                    sheet.indicators.insert(key.to_string(), value.to_string());
+                }
+            }
+        }
+        sheet
+    }
+
+    /// 解析 dircolors 数据库 (`dircolors -p` 的输出格式)
+    pub fn from_dircolors(database: &str) -> Self {
+        Self::from_ls_colors(&dircolors_to_ls_colors(database))
+    }
+
+    /// 读取 LS_COLORS 环境变量，未设置时使用默认配色
+    pub fn from_env() -> Self {
+        match std::env::var("LS_COLORS") {
+            Ok(spec) if !spec.is_empty() => Self::from_ls_colors(&spec),
+            _ => Self::from_ls_colors(DEFAULT_LS_COLORS),
+        }
+    }
+
+    /// 目录项对应的 SGR 参数，不需要着色时返回 None
+    pub fn style_for(&self, entry: &Entry) -> Option<&str> {
+        if entry.kind == EntryKind::Symlink {
+            return match entry.target_kind {
+                None => self.indicator("or").or_else(|| self.indicator("ln")),
+                // ln=target 表示按链接目标的类型着色
+                Some(target) if self.indicators.get("ln").is_some_and(|s| s == "target") => {
+                    self.kind_style(target, 0, 1, &entry.name)
+                }
+                Some(_) => self.indicator("ln"),
+            };
+        }
+        self.kind_style(entry.kind, entry.mode, entry.nlink, &entry.name)
+    }
+
+    /// 符号链接目标对应的 SGR 参数：按目标类型着色，目标不存在时使用 mi
+    pub fn target_style(&self, entry: &Entry) -> Option<&str> {
+        let target = entry.symlink_target.as_deref()?;
+        let name = target.file_name().unwrap_or(target.as_os_str());
+        match entry.target_kind {
+            Some(kind) => self.kind_style(kind, 0, 1, name),
+            None => self.indicator("mi").or_else(|| self.indicator("or")),
+        }
+    }
+
+    /// 为文本加上目录项的颜色，颜色被关闭时原样返回
+    pub fn paint(&self, entry: &Entry, text: &str) -> String {
+        paint_with(self.style_for(entry), text)
+    }
+
+    /// 为符号链接目标加上颜色，颜色被关闭时原样返回
+    pub fn paint_target(&self, entry: &Entry, text: &str) -> String {
+        paint_with(self.target_style(entry), text)
+    }
+
+    /// 按类型、权限位与扩展名选择样式，与 ls 的优先级一致
+    fn kind_style(&self, kind: EntryKind, mode: u32, nlink: u64, name: &OsStr) -> Option<&str> {
+        let style = match kind {
+            EntryKind::Dir => {
+                let sticky = mode & 0o1000 != 0;
+                let other_writable = mode & 0o002 != 0;
+                match (sticky, other_writable) {
+                    (true, true) => self.indicator("tw"),
+                    (false, true) => self.indicator("ow"),
+                    (true, false) => self.indicator("st"),
+                    (false, false) => None,
+                }
+                .or_else(|| self.indicator("di"))
+            }
+            EntryKind::File => {
+                // 非 Unix 平台的权限位是合成的，不据此判断可执行
+                let executable = cfg!(unix) && mode & 0o111 != 0;
+                [
+                    (mode & 0o4000 != 0, "su"),
+                    (mode & 0o2000 != 0, "sg"),
+                    (executable, "ex"),
+                    (nlink > 1, "mh"),
+                ]
+                .into_iter()
+                .filter(|(applies, _)| *applies)
+                .find_map(|(_, key)| self.indicator(key))
+                .or_else(|| self.suffix_style(name))
+                .or_else(|| self.indicator("fi"))
+            }
+            EntryKind::Symlink => self.indicator("ln"),
+            EntryKind::Fifo => self.indicator("pi"),
+            EntryKind::Socket => self.indicator("so"),
+            EntryKind::CharDevice => self.indicator("cd"),
+            EntryKind::BlockDevice => self.indicator("bd"),
+            EntryKind::Other => None,
+        };
+        style.or_else(|| self.indicator("no"))
+    }
+
+    /// 按文件名后缀匹配 (不区分大小写)
+    fn suffix_style(&self, name: &OsStr) -> Option<&str> {
+        let name = name.to_string_lossy().to_lowercase();
+        self.suffixes
+            .iter()
+            .rev()
+            .find(|(suffix, _)| name.ends_with(suffix.as_str()))
+            .map(|(_, style)| style.as_str())
+            .filter(|style| is_colored(style))
+    }
+
+    /// 已设置且不是 "0"/"00" 的指示符样式
+    fn indicator(&self, key: &str) -> Option<&str> {
+        self.indicators
+            .get(key)
+            .map(String::as_str)
+            .filter(|style| is_colored(style))
+    }
+}
+
+/// 用 SGR 参数包裹文本，颜色被关闭时原样返回
+fn paint_with(style: Option<&str>, text: &str) -> String {
+    match style {
+        Some(style) if colored::control::SHOULD_COLORIZE.should_colorize() => {
+            format!("\x1b[{}m{}\x1b[0m", style, text)
+        }
+        _ => text.to_string(),
+    }
+}
+
+/// 样式是否会产生颜色
+fn is_colored(style: &str) -> bool {
+    !matches!(style, "" | "0" | "00")
+}
+
+/// 将 dircolors 数据库转换为 LS_COLORS 格式，TERM 等终端匹配条件被忽略
+fn dircolors_to_ls_colors(database: &str) -> String {
+    let mut items = Vec::new();
+    for line in database.lines() {
+        let line = line.split('#').next().unwrap_or_default().trim();
+        let mut parts = line.split_whitespace();
+        let (Some(key), Some(value)) = (parts.next(), parts.next()) else {
+            continue;
+        };
+
+        if let Some(ext) = key.strip_prefix('.') {
+            items.push(format!("*.{}={}", ext, value));
+        } else if key.starts_with('*') {
+            items.push(format!("{}={}", key, value));
+        } else if let Some((_, code)) = DIRCOLORS_KEYWORDS
+            .iter()
+            .find(|(keyword, _)| keyword.eq_ignore_ascii_case(key))
+        {
+            items.push(format!("{}={}", code, value));
+        }
+    }
+    items.join(":")
+}
+
+/// 当前进程使用的样式表，首次使用时从环境变量加载
+pub fn style_sheet() -> &'static StyleSheet {
+    STYLE_SHEET.get_or_init(StyleSheet::from_env)
+}
+
+/// 在首次着色之前替换全局样式表，已经初始化时返回 Err
+pub fn set_style_sheet(sheet: StyleSheet) -> Result<(), StyleSheet> {
+    STYLE_SHEET.set(sheet)
+}
+
+static STYLE_SHEET: OnceLock<StyleSheet> = OnceLock::new();
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_env_override() {
+        let on = Some(OsStr::new("1"));
+        assert_eq!(env_override(None, None), None);
+        assert_eq!(env_override(on, None), Some(false));
+        assert_eq!(env_override(Some(OsStr::new("")), None), None);
+        assert_eq!(env_override(on, on), Some(true));
+        assert_eq!(env_override(None, Some(OsStr::new("0"))), None);
+    }
+
+    #[test]
+    fn test_explicit_choice_ignores_terminal() {
+        assert!(ColorChoice::Always.should_colorize(false));
+        assert!(!ColorChoice::Never.should_colorize(true));
+    }
+
+    #[test]
+    fn test_style_by_type_and_suffix() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        std::fs::create_dir(dir.join("sub")).unwrap();
+        std::fs::write(dir.join("archive.TAR"), "").unwrap();
+        std::fs::write(dir.join("plain"), "").unwrap();
+
+        let sheet = StyleSheet::from_ls_colors("di=01;34:ex=01;32:*.tar=01;31:*.gz=00");
+        let style = |name: &str| {
+            let entry = Entry::from_path(&dir.join(name), 0).unwrap();
+            sheet.style_for(&entry).map(str::to_string)
+        };
+        assert_eq!(style("sub").as_deref(), Some("01;34"));
+        assert_eq!(style("archive.TAR").as_deref(), Some("01;31"));
+        assert_eq!(style("plain"), None);
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::{PermissionsExt, symlink};
+            let script = dir.join("run.tar");
+            std::fs::write(&script, "").unwrap();
+            std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+            // 可执行权限优先于扩展名
+            assert_eq!(style("run.tar").as_deref(), Some("01;32"));
+
+            symlink(dir.join("missing"), dir.join("broken")).unwrap();
+            let sheet = StyleSheet::from_ls_colors("ln=01;36:or=40;31;01");
+            let broken = Entry::from_path(&dir.join("broken"), 0).unwrap();
+            assert_eq!(sheet.style_for(&broken), Some("40;31;01"));
+
+            symlink(dir.join("sub"), dir.join("link")).unwrap();
+            let link = Entry::from_path(&dir.join("link"), 0).unwrap();
+            assert_eq!(sheet.style_for(&link), Some("01;36"));
+            let sheet = StyleSheet::from_ls_colors("ln=target:di=01;34");
+            assert_eq!(sheet.style_for(&link), Some("01;34"));
+        }
+    }
+
+    #[test]
+    fn test_dircolors_database() {
+        let database =
+            "# comment\nTERM xterm*\nDIR 01;34 # dirs\nEXEC 01;32\n.zip 01;31\n*README 04\n";
+        assert_eq!(
+            dircolors_to_ls_colors(database),
+            "di=01;34:ex=01;32:*.zip=01;31:*README=04"
+        );
+    }
+}
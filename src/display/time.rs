@@ -0,0 +1,350 @@
+//! 时间格式化模块
+
+use crate::core::models::Entry;
+use clap::ValueEnum;
+use std::fmt::Write;
+use std::str::FromStr;
+use std::time::{Duration, SystemTime};
+use time::{OffsetDateTime, UtcOffset};
+
+/// `iso` 风格中视为近期的时间范围 (半个平均公历年，与 ls 一致)
+const RECENT: Duration = Duration::from_secs(31_556_952 / 2);
+
+/// 长格式中时间的显示方式
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum TimeStyle {
+    /// 近半年内显示 `MM-DD HH:MM`，更早或未来的时间显示 `YYYY-MM-DD`
+    Iso,
+    /// `YYYY-MM-DD HH:MM`
+    #[default]
+    LongIso,
+    /// 精确到纳秒并带时区，例如 `2024-02-29 21:05:09.123456789 +0800`
+    Full,
+    /// 相对于当前的时间，例如 `3 hours ago`
+    Relative,
+    /// strftime 格式 (命令行中以 `+` 开头)
+    Format(String),
+}
+
+impl FromStr for TimeStyle {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "iso" => Ok(TimeStyle::Iso),
+            "long-iso" => Ok(TimeStyle::LongIso),
+            "full" | "full-iso" => Ok(TimeStyle::Full),
+            "relative" => Ok(TimeStyle::Relative),
+            _ => match s.strip_prefix('+') {
+                Some(format) => Ok(TimeStyle::Format(format.to_string())),
+                None => Err("expected iso, long-iso, full, relative or +FORMAT".to_string()),
+            },
+        }
+    }
+}
+
+/// 显示哪一个时间戳 (变体注释会作为命令行帮助显示)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum TimeField {
+    /// Last access time
+    #[value(alias = "access", alias = "use")]
+    Atime,
+    /// Last modification time
+    #[default]
+    #[value(alias = "modification")]
+    Mtime,
+    /// Last status change time
+    #[value(alias = "status")]
+    Ctime,
+    /// Creation time, where the filesystem records it
+    #[value(alias = "creation")]
+    Birth,
+}
+
+impl TimeField {
+    /// 目录项中对应的时间，平台或文件系统不支持时为 None
+    pub fn of(self, entry: &Entry) -> Option<SystemTime> {
+        match self {
+            TimeField::Atime => entry.accessed,
+            TimeField::Mtime => entry.modified,
+            TimeField::Ctime => entry.changed,
+            TimeField::Birth => entry.created,
+        }
+    }
+}
+
+/// 按显示方式在本地时区中格式化时间，now 用于 `iso` 与 `relative`
+pub fn format_time(system_time: SystemTime, style: &TimeStyle, now: SystemTime) -> String {
+    match style {
+        TimeStyle::Iso => {
+            let recent = now
+                .duration_since(system_time)
+                .is_ok_and(|age| age < RECENT);
+            if recent {
+                format_local(system_time, "%m-%d %H:%M")
+            } else {
+                // 补齐到与近期时间相同的宽度
+                format_local(system_time, "%Y-%m-%d ")
+            }
+        }
+        TimeStyle::LongIso => format_local(system_time, "%Y-%m-%d %H:%M"),
+        TimeStyle::Full => format_local(system_time, "%Y-%m-%d %H:%M:%S.%N %z"),
+        TimeStyle::Relative => format_relative(system_time, now),
+        TimeStyle::Format(format) => format_local(system_time, format),
+    }
+}
+
+/// 在本地时区中按 strftime 格式化时间，超出可表示的年份范围时回退为 Unix 秒数
+pub fn format_local(system_time: SystemTime, format: &str) -> String {
+    match local_datetime(system_time) {
+        Some(datetime) => strftime(datetime, format),
+        None => unix_nanos(system_time)
+            .div_euclid(1_000_000_000)
+            .to_string(),
+    }
+}
+
+/// 相对于 now 的时间，例如 `3 hours ago`、`in 2 days`
+pub fn format_relative(system_time: SystemTime, now: SystemTime) -> String {
+    let (elapsed, past) = match now.duration_since(system_time) {
+        Ok(elapsed) => (elapsed, true),
+        Err(e) => (e.duration(), false),
+    };
+    let secs = elapsed.as_secs();
+    let (count, unit) = match secs {
+        0 => return "just now".to_string(),
+        1..60 => (secs, "second"),
+        60..3_600 => (secs / 60, "minute"),
+        3_600..86_400 => (secs / 3_600, "hour"),
+        86_400..2_592_000 => (secs / 86_400, "day"),
+        2_592_000..31_536_000 => (secs / 2_592_000, "month"),
+        _ => (secs / 31_536_000, "year"),
+    };
+    let plural = if count == 1 { "" } else { "s" };
+    if past {
+        format!("{} {}{} ago", count, unit, plural)
+    } else {
+        format!("in {} {}{}", count, unit, plural)
+    }
+}
+
+const WEEKDAYS: [&str; 7] = [
+    "Monday",
+    "Tuesday",
+    "Wednesday",
+    "Thursday",
+    "Friday",
+    "Saturday",
+    "Sunday",
+];
+
+const MONTHS: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+
+/// 按 strftime 风格的格式输出时间
+///
+/// 支持常用的转换符：`%Y %C %y %m %d %e %H %I %M %S %N %p %j %a %A %b %h %B
+/// %F %T %R %D %z %Z %s %n %t %%`，无法识别的转换符原样输出。
+pub fn strftime(datetime: OffsetDateTime, format: &str) -> String {
+    let mut output = String::with_capacity(format.len() + 16);
+    let mut chars = format.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            output.push(c);
+            continue;
+        }
+        let Some(spec) = chars.next() else {
+            output.push('%');
+            break;
+        };
+
+        // 写入 String 不会失败
+        let _ = match spec {
+            'Y' => write!(output, "{}", datetime.year()),
+            'C' => write!(output, "{:02}", datetime.year().div_euclid(100)),
+            'y' => write!(output, "{:02}", datetime.year().rem_euclid(100)),
+            'm' => write!(output, "{:02}", datetime.month() as u8),
+            'd' => write!(output, "{:02}", datetime.day()),
+            'e' => write!(output, "{:>2}", datetime.day()),
+            'H' => write!(output, "{:02}", datetime.hour()),
+            'I' => write!(output, "{:02}", (datetime.hour() + 11) % 12 + 1),
+            'M' => write!(output, "{:02}", datetime.minute()),
+            'S' => write!(output, "{:02}", datetime.second()),
+            'N' => write!(output, "{:09}", datetime.nanosecond()),
+            'p' => write!(output, "{}", if datetime.hour() < 12 { "AM" } else { "PM" }),
+            'j' => write!(output, "{:03}", datetime.ordinal()),
+            'a' => write!(output, "{}", &weekday_name(datetime)[..3]),
+            'A' => write!(output, "{}", weekday_name(datetime)),
+            'b' | 'h' => write!(output, "{}", &month_name(datetime)[..3]),
+            'B' => write!(output, "{}", month_name(datetime)),
+            'F' => write!(output, "{}", strftime(datetime, "%Y-%m-%d")),
+            'T' => write!(output, "{}", strftime(datetime, "%H:%M:%S")),
+            'R' => write!(output, "{}", strftime(datetime, "%H:%M")),
+            'D' => write!(output, "{}", strftime(datetime, "%m/%d/%y")),
+            'z' => write!(output, "{}", format_offset(datetime, "")),
+            'Z' => match datetime.offset().is_utc() {
+                true => write!(output, "UTC"),
+                false => write!(output, "{}", format_offset(datetime, ":")),
+            },
+            's' => write!(output, "{}", datetime.unix_timestamp()),
+            'n' => writeln!(output),
+            't' => write!(output, "\t"),
+            '%' => write!(output, "%"),
+            other => write!(output, "%{}", other),
+        };
+    }
+
+    output
+}
+
+/// 本地时区中精确到纳秒的完整时间，例如 `2024-02-29 21:05:09.123456789 +0800`
+pub fn format_full_time(system_time: SystemTime) -> String {
+    format_local(system_time, "%Y-%m-%d %H:%M:%S.%N %z")
+}
+
+/// 系统时间在本地时区中的表示，无法确定本地时区时使用 UTC
+///
+/// 超出可表示的年份范围 (±9999 年) 时为 None，例如 `touch -d @1e12` 设置的时间。
+pub fn local_datetime(system_time: SystemTime) -> Option<OffsetDateTime> {
+    let utc = utc_datetime(system_time)?;
+    Some(match local_offset(utc.unix_timestamp()) {
+        Some(offset) => utc.to_offset(offset),
+        None => utc,
+    })
+}
+
+/// 系统时间对应的 UTC 时间，超出可表示的年份范围时为 None
+pub fn utc_datetime(system_time: SystemTime) -> Option<OffsetDateTime> {
+    OffsetDateTime::from_unix_timestamp_nanos(unix_nanos(system_time)).ok()
+}
+
+/// 相对 Unix 纪元的纳秒数，早于纪元时为负数
+fn unix_nanos(system_time: SystemTime) -> i128 {
+    match system_time.duration_since(SystemTime::UNIX_EPOCH) {
+        Ok(elapsed) => elapsed.as_nanos() as i128,
+        Err(e) => -(e.duration().as_nanos() as i128),
+    }
+}
+
+/// 指定时刻本地时区 (遵循 TZ 环境变量) 相对 UTC 的偏移
+#[cfg(unix)]
+fn local_offset(timestamp: i64) -> Option<UtcOffset> {
+    let time = libc::time_t::try_from(timestamp).ok()?;
+    // SAFETY: tm 为纯数据结构，全零是合法的初始值；localtime_r 只写入 tm
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+    if unsafe { libc::localtime_r(&time, &mut tm) }.is_null() {
+        return None;
+    }
+    UtcOffset::from_whole_seconds(i32::try_from(tm.tm_gmtoff).ok()?).ok()
+}
+
+/// 指定时刻本地时区相对 UTC 的偏移 (非 Unix 平台使用 UTC)
+#[cfg(not(unix))]
+fn local_offset(_timestamp: i64) -> Option<UtcOffset> {
+    None
+}
+
+/// 星期的英文全称
+fn weekday_name(datetime: OffsetDateTime) -> &'static str {
+    WEEKDAYS[datetime.weekday().number_days_from_monday() as usize]
+}
+
+/// 月份的英文全称
+fn month_name(datetime: OffsetDateTime) -> &'static str {
+    MONTHS[datetime.month() as usize - 1]
+}
+
+/// 时区偏移，例如 `+0800`
+fn format_offset(datetime: OffsetDateTime, separator: &str) -> String {
+    let offset = datetime.offset();
+    let sign = if offset.is_negative() { '-' } else { '+' };
+    format!(
+        "{}{:02}{}{:02}",
+        sign,
+        offset.whole_hours().unsigned_abs(),
+        separator,
+        offset.minutes_past_hour().unsigned_abs()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::UNIX_EPOCH;
+
+    #[test]
+    fn test_strftime() {
+        // 2024-02-29 13:05:09 UTC, 星期四
+        let datetime = OffsetDateTime::from_unix_timestamp(1_709_211_909).unwrap();
+        assert_eq!(strftime(datetime, "%F %T"), "2024-02-29 13:05:09");
+        assert_eq!(
+            strftime(datetime, "%a %b %e %I:%M %p"),
+            "Thu Feb 29 01:05 PM"
+        );
+        assert_eq!(
+            strftime(datetime, "%A %B %j %y %z %Z"),
+            "Thursday February 060 24 +0000 UTC"
+        );
+        assert_eq!(strftime(datetime, "100%% %q %"), "100% %q %");
+    }
+
+    #[test]
+    fn test_time_style() {
+        let parse = |s: &str| s.parse::<TimeStyle>();
+        assert_eq!(parse("long-iso"), Ok(TimeStyle::LongIso));
+        assert_eq!(parse("full-iso"), Ok(TimeStyle::Full));
+        assert_eq!(parse("+%Y"), Ok(TimeStyle::Format("%Y".to_string())));
+        assert!(parse("%Y").is_err());
+
+        // 1969-07-20 20:17:40 UTC，早于 Unix 纪元
+        let landing = UNIX_EPOCH - Duration::from_secs(14_182_940);
+        let now = UNIX_EPOCH + Duration::from_secs(1_000_000_000);
+        let utc = utc_datetime(landing).unwrap();
+        assert_eq!(utc.year(), 1969);
+        assert_eq!(strftime(utc, "%F %T"), "1969-07-20 20:17:40");
+        let local = format_time(landing, &TimeStyle::LongIso, now);
+        assert!(local.starts_with("1969-07-2"), "{}", local);
+        assert_eq!(format_time(landing, &TimeStyle::Iso, now).len(), 11);
+        assert_eq!(
+            format_time(now - Duration::from_secs(60), &TimeStyle::Iso, now).len(),
+            11
+        );
+
+        // 超出可表示范围的时间 (touch -d @1e12) 回退为 Unix 秒数而不是崩溃
+        let far = UNIX_EPOCH + Duration::from_secs(1_000_000_000_000);
+        assert!(local_datetime(far).is_none());
+        assert_eq!(format_time(far, &TimeStyle::Full, now), "1000000000000");
+        let past = UNIX_EPOCH - Duration::from_secs(400_000_000_000);
+        assert_eq!(format_local(past, "%Y"), "-400000000000");
+    }
+
+    #[test]
+    fn test_format_relative() {
+        let now = UNIX_EPOCH + Duration::from_secs(1_000_000_000);
+        let ago = |secs| format_relative(now - Duration::from_secs(secs), now);
+        assert_eq!(ago(0), "just now");
+        assert_eq!(ago(1), "1 second ago");
+        assert_eq!(ago(3 * 3_600 + 59), "3 hours ago");
+        assert_eq!(ago(86_400 * 45), "1 month ago");
+        assert_eq!(ago(86_400 * 800), "2 years ago");
+        assert_eq!(
+            format_relative(now + Duration::from_secs(172_800), now),
+            "in 2 days"
+        );
+        // 早于纪元的时间同样可以计算
+        assert_eq!(ago(1_000_000_000 + 31_536_000), "32 years ago");
+    }
+}
@@ -0,0 +1,32 @@
+//! fmg 的核心库
+//!
+//! 提供目录遍历、排序与输出格式化功能，`fmg` 命令行工具构建在此库之上。
+//!
+//! ```no_run
+//! use file_manage::{ListOptions, TreeOptions, list, walk_tree};
+//!
+//! # fn main() -> file_manage::Result<()> {
+//! let listing = list(".", &ListOptions::default())?;
+//! for entry in &listing.entries {
+//!     println!("{} {}", entry.kind.as_str(), entry.name_lossy());
+//! }
+//!
+//! let options = TreeOptions {
+//!     max_depth: Some(2),
+//!     ..TreeOptions::default()
+//! };
+//! let tree = walk_tree("src", &options)?;
+//! let render = file_manage::display::formatter::RenderOptions::default();
+//! file_manage::display::formatter::write_tree(&mut std::io::stdout(), &tree.tree, &render)?;
+//! # Ok(())
+//! # }
+//! ```
+
+pub mod core;
+pub mod display;
+
+pub use crate::core::error::{FmgError, Result};
+pub use crate::core::file_ops::{ListOptions, TreeOptions, list, walk_tree};
+pub use crate::core::models::{DirTree, Entry, EntryError, EntryKind, Listing, TreeListing};
+pub use crate::core::search::{FindOptions, FindResult, find};
+pub use crate::core::sort::{SortKey, SortOptions};
@@ -59,6 +59,7 @@ mod tests {
     use super::*;
 
     #[test]
+    #[allow(clippy::field_reassign_with_default)]
     fn test_print_logo() {
         let config = LogoConfig::default();
         FileManLogo::print(&config);
@@ -1,25 +1,90 @@
 mod cli;
-mod core;
-mod display;
 mod logo;
+mod tui;
 
-use clap::Parser;
-use cli::commands::handle_list_command;
+use clap::{CommandFactory, FromArgMatches};
+use cli::commands::{
+    handle_copy_command, handle_find_command, handle_grep_command, handle_history_command,
+    handle_list_command, handle_move_command, handle_rename_command, handle_rm_command,
+    handle_stat_command, handle_trash_command, handle_tui_command, handle_undo_command,
+};
+use cli::config::Config;
 use cli::parser::Cli;
+use colored::*;
+use file_manage::Result;
+use file_manage::display::formatter::OutputFormat;
+use file_manage::display::styles::set_style_sheet;
 use logo::{FileManLogo, LogoConfig};
+use std::io::IsTerminal;
+use std::process::ExitCode;
 
-fn main() {
-    // 创建默认的 Logo 配置
-    let logo_config = LogoConfig::default();
-    
-    // 打印 Logo
-    FileManLogo::print(&logo_config);
-    
-    let cli = Cli::parse();
-
-    match &cli.command {
-        cli::parser::Commands::List(args) => {
-            handle_list_command(args);
+fn main() -> ExitCode {
+    match run() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{} {}", "Error:".red().bold(), e);
+            ExitCode::from(e.exit_code())
         }
     }
 }
+
+fn run() -> Result<()> {
+    // 配置文件中的别名会改变命令行参数，因此先读取配置再解析
+    let args: Vec<_> = std::env::args_os().collect();
+    let config = Config::load(&args)?;
+    let args = config.expand_alias(args)?;
+    let matches = Cli::command().get_matches_from(args);
+    let mut cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    config.apply(&mut cli, &matches);
+    let command = cli.command.take().unwrap_or_default();
+
+    // 输出被重定向时默认不着色，避免转义序列污染管道
+    let is_terminal = std::io::stdout().is_terminal();
+    let colored = cli.color.should_colorize(is_terminal);
+    colored::control::set_override(colored);
+    if let Some(sheet) = config.style_sheet()? {
+        // 此时尚未输出任何内容，样式表一定未初始化
+        let _ = set_style_sheet(sheet);
+    }
+
+    // 结构化输出时不打印 Logo，避免破坏 JSON
+    let structured_output = match &command {
+        cli::parser::Commands::List(args) => args.format != OutputFormat::Text,
+        cli::parser::Commands::Find(args) => args.format != OutputFormat::Text || args.print0,
+        cli::parser::Commands::Stat(args) => args.format != OutputFormat::Text,
+        cli::parser::Commands::Grep(_)
+        | cli::parser::Commands::Copy(_)
+        | cli::parser::Commands::Move(_)
+        | cli::parser::Commands::Rename(_)
+        | cli::parser::Commands::Rm(_)
+        | cli::parser::Commands::Trash(_)
+        | cli::parser::Commands::Undo(_)
+        | cli::parser::Commands::History => false,
+        // 全屏界面不需要 Logo
+        cli::parser::Commands::Tui(_) => true,
+    };
+
+    // Logo 只在交互式终端中显示
+    if !structured_output && !cli.no_logo && is_terminal {
+        let logo_config = LogoConfig {
+            colored,
+            ..LogoConfig::default()
+        };
+        FileManLogo::print(&logo_config);
+    }
+
+    match &command {
+        cli::parser::Commands::List(args) => handle_list_command(args, &config),
+        cli::parser::Commands::Find(args) => handle_find_command(args, &config),
+        cli::parser::Commands::Grep(args) => handle_grep_command(args),
+        cli::parser::Commands::Stat(args) => handle_stat_command(args),
+        cli::parser::Commands::Copy(args) => handle_copy_command(args, &config),
+        cli::parser::Commands::Move(args) => handle_move_command(args),
+        cli::parser::Commands::Rename(args) => handle_rename_command(args),
+        cli::parser::Commands::Rm(args) => handle_rm_command(args),
+        cli::parser::Commands::Trash(args) => handle_trash_command(args),
+        cli::parser::Commands::Undo(args) => handle_undo_command(args),
+        cli::parser::Commands::History => handle_history_command(),
+        cli::parser::Commands::Tui(args) => handle_tui_command(args),
+    }
+}
@@ -0,0 +1,780 @@
+//! TUI 的状态与按键处理，与终端无关，便于在测试中直接驱动
+
+use file_manage::core::copy::{CopyOptions, copy, plan_copy};
+use file_manage::core::filter::FilterOptions;
+use file_manage::core::journal::{Action, Journal};
+use file_manage::core::move_ops::{ConflictPolicy, MoveOutcome, free_name, move_path};
+use file_manage::core::trash::Trash;
+use file_manage::display::formatter::{SizeStyle, format_size};
+use file_manage::{Entry, EntryKind, ListOptions, Result, SortOptions, list};
+use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::widgets::ListState;
+use std::collections::BTreeSet;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+/// 预览文本文件时读取的最大字节数
+const PREVIEW_BYTES: u64 = 64 * 1024;
+
+/// 预览的最大行数
+const PREVIEW_LINES: usize = 200;
+
+/// 底部的按键提示
+const HELP: &str = "j/k move  l/h open/back  space mark  y copy  x cut  p paste  d trash  \
+                    . hidden  t sidebar  tab focus  r refresh  q quit";
+
+/// 接收按键的窗格
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Focus {
+    Sidebar,
+    Files,
+}
+
+/// 剪贴板中的项目在粘贴时复制还是移动
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClipMode {
+    Copy,
+    Cut,
+}
+
+/// 通过 `y`/`x` 放入剪贴板的项目
+#[derive(Debug, Clone)]
+pub struct Clipboard {
+    pub mode: ClipMode,
+    pub paths: Vec<PathBuf>,
+}
+
+/// 侧边栏目录树中的节点，子目录在首次展开时读取
+#[derive(Debug)]
+struct DirNode {
+    path: PathBuf,
+    expanded: bool,
+    children: Option<Vec<DirNode>>,
+}
+
+impl DirNode {
+    fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            expanded: false,
+            children: None,
+        }
+    }
+
+    /// 展开节点，必要时读取子目录
+    fn expand(&mut self, filter: &FilterOptions) {
+        if self.children.is_none() {
+            let options = ListOptions {
+                sort: SortOptions::default(),
+                filter: filter.clone(),
+                ..ListOptions::default()
+            };
+            // 无法读取的目录按空目录显示
+            let children = list(&self.path, &options)
+                .map(|listing| {
+                    listing
+                        .entries
+                        .into_iter()
+                        .filter(|entry| entry.kind == EntryKind::Dir)
+                        .map(|entry| DirNode::new(entry.path))
+                        .collect()
+                })
+                .unwrap_or_default();
+            self.children = Some(children);
+        }
+        self.expanded = true;
+    }
+
+    /// 按显示顺序展开为侧边栏的行
+    fn flatten(
+        &self,
+        depth: usize,
+        ancestors: &mut Vec<bool>,
+        is_last: bool,
+        rows: &mut Vec<SidebarRow>,
+    ) {
+        rows.push(SidebarRow {
+            path: self.path.clone(),
+            depth,
+            ancestors: ancestors.clone(),
+            is_last,
+            expanded: self.expanded,
+        });
+        if !self.expanded {
+            return;
+        }
+        let children = self.children.as_deref().unwrap_or_default();
+        // 根节点没有连接线，其子节点从第一层开始画
+        if depth > 0 {
+            ancestors.push(is_last);
+        }
+        for (i, child) in children.iter().enumerate() {
+            child.flatten(depth + 1, ancestors, i + 1 == children.len(), rows);
+        }
+        if depth > 0 {
+            ancestors.pop();
+        }
+    }
+
+    /// 查找 path 对应的节点，沿途展开经过的目录
+    fn reveal(&mut self, path: &Path, filter: &FilterOptions) -> bool {
+        if self.path == path {
+            return true;
+        }
+        if !path.starts_with(&self.path) {
+            return false;
+        }
+        self.expand(filter);
+        self.children
+            .iter_mut()
+            .flatten()
+            .any(|child| child.reveal(path, filter))
+    }
+
+    fn find_mut(&mut self, path: &Path) -> Option<&mut DirNode> {
+        if self.path == path {
+            return Some(self);
+        }
+        if !path.starts_with(&self.path) {
+            return None;
+        }
+        self.children
+            .iter_mut()
+            .flatten()
+            .find_map(|child| child.find_mut(path))
+    }
+}
+
+/// 侧边栏中的一行
+#[derive(Debug, Clone)]
+pub struct SidebarRow {
+    pub path: PathBuf,
+    pub depth: usize,
+    /// 各层祖先是否为其父节点的最后一个子节点，用于画连接线
+    pub ancestors: Vec<bool>,
+    pub is_last: bool,
+    pub expanded: bool,
+}
+
+/// 等待确认的操作
+#[derive(Debug, Clone)]
+pub enum Prompt {
+    /// 将这些项目移入回收站
+    Trash(Vec<PathBuf>),
+}
+
+/// 右侧窗格的预览内容
+#[derive(Debug, Clone, Default)]
+pub struct Preview {
+    pub path: PathBuf,
+    pub lines: Vec<String>,
+}
+
+/// TUI 的全部状态
+pub struct App {
+    pub cwd: PathBuf,
+    pub entries: Vec<Entry>,
+    pub files: ListState,
+    pub marked: BTreeSet<PathBuf>,
+    pub filter: FilterOptions,
+    pub focus: Focus,
+    pub show_sidebar: bool,
+    pub sidebar: ListState,
+    pub clipboard: Option<Clipboard>,
+    pub prompt: Option<Prompt>,
+    pub message: Option<String>,
+    pub preview: Preview,
+    pub quit: bool,
+    /// 删除使用的回收站，不可用时无法删除
+    pub trash: Option<Trash>,
+    /// 记录复制、移动与删除以便 `fmg undo`
+    pub journal: Option<Journal>,
+    tree: DirNode,
+    rows: Vec<SidebarRow>,
+}
+
+impl App {
+    /// 在 path 目录中打开，侧边栏以该目录为根
+    pub fn new(path: &Path, filter: FilterOptions) -> Result<Self> {
+        let cwd = path
+            .canonicalize()
+            .map_err(|e| file_manage::FmgError::from_io(path, e))?;
+        // 平铺列表中的 `.` 与 `..` 在这里没有意义
+        let filter = FilterOptions {
+            dot_entries: false,
+            ..filter
+        };
+        let mut app = Self {
+            tree: DirNode::new(cwd.clone()),
+            cwd,
+            entries: Vec::new(),
+            files: ListState::default(),
+            marked: BTreeSet::new(),
+            filter,
+            focus: Focus::Files,
+            show_sidebar: true,
+            sidebar: ListState::default(),
+            clipboard: None,
+            prompt: None,
+            message: None,
+            preview: Preview::default(),
+            quit: false,
+            trash: Trash::from_env(),
+            journal: Journal::from_env(),
+            rows: Vec::new(),
+        };
+        app.load(None)?;
+        Ok(app)
+    }
+
+    /// 侧边栏的行
+    pub fn sidebar_rows(&self) -> &[SidebarRow] {
+        &self.rows
+    }
+
+    /// 文件窗格中选中的目录项
+    pub fn selected(&self) -> Option<&Entry> {
+        self.files.selected().and_then(|i| self.entries.get(i))
+    }
+
+    /// 底部状态栏的内容
+    pub fn status(&self) -> String {
+        match (&self.prompt, &self.message) {
+            (Some(Prompt::Trash(paths)), _) => {
+                format!("Move {} to the trash? [y/N]", describe_items(paths))
+            }
+            (None, Some(message)) => message.clone(),
+            (None, None) => {
+                let mut parts = Vec::new();
+                if !self.marked.is_empty() {
+                    parts.push(format!("{} marked", self.marked.len()));
+                }
+                if let Some(clipboard) = &self.clipboard {
+                    let verb = match clipboard.mode {
+                        ClipMode::Copy => "copy",
+                        ClipMode::Cut => "move",
+                    };
+                    parts.push(format!(
+                        "{} {} (p to paste)",
+                        verb,
+                        describe_items(&clipboard.paths)
+                    ));
+                }
+                parts.push(HELP.to_string());
+                parts.join(" | ")
+            }
+        }
+    }
+
+    /// 处理一次按键
+    pub fn handle_key(&mut self, key: KeyEvent) {
+        self.message = None;
+        if let Some(prompt) = self.prompt.take() {
+            if matches!(key.code, KeyCode::Char('y') | KeyCode::Char('Y')) {
+                match prompt {
+                    Prompt::Trash(paths) => self.trash_paths(&paths),
+                }
+            } else {
+                self.message = Some("Cancelled".to_string());
+            }
+            return;
+        }
+        if key.modifiers.contains(KeyModifiers::CONTROL) {
+            if key.code == KeyCode::Char('c') {
+                self.quit = true;
+            }
+            return;
+        }
+
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => self.quit = true,
+            KeyCode::Tab => {
+                self.focus = match self.focus {
+                    Focus::Files if self.show_sidebar => Focus::Sidebar,
+                    _ => Focus::Files,
+                };
+            }
+            KeyCode::Char('t') => {
+                self.show_sidebar = !self.show_sidebar;
+                self.focus = Focus::Files;
+            }
+            KeyCode::Char('.') => {
+                self.filter.show_hidden = !self.filter.show_hidden;
+                self.tree = DirNode::new(self.tree.path.clone());
+                self.reload();
+            }
+            KeyCode::Char('r') => {
+                self.tree = DirNode::new(self.tree.path.clone());
+                self.reload();
+            }
+            _ if self.focus == Focus::Sidebar => self.sidebar_key(key.code),
+            _ => self.files_key(key.code),
+        }
+        self.update_preview();
+    }
+
+    fn files_key(&mut self, code: KeyCode) {
+        let len = self.entries.len();
+        match code {
+            KeyCode::Char('j') | KeyCode::Down => self.move_files(1),
+            KeyCode::Char('k') | KeyCode::Up => self.move_files(-1),
+            KeyCode::PageDown => self.move_files(20),
+            KeyCode::PageUp => self.move_files(-20),
+            KeyCode::Char('g') | KeyCode::Home => self.files.select((len > 0).then_some(0)),
+            KeyCode::Char('G') | KeyCode::End => self.files.select(len.checked_sub(1)),
+            KeyCode::Char('l') | KeyCode::Right | KeyCode::Enter => {
+                if let Some(entry) = self.selected().filter(|entry| entry.is_dir()) {
+                    let path = entry.path.clone();
+                    self.change_dir(path, None);
+                }
+            }
+            KeyCode::Char('h') | KeyCode::Left | KeyCode::Backspace => self.go_parent(),
+            KeyCode::Char(' ') => {
+                if let Some(path) = self.selected().map(|entry| entry.path.clone()) {
+                    if !self.marked.remove(&path) {
+                        self.marked.insert(path);
+                    }
+                    self.move_files(1);
+                }
+            }
+            KeyCode::Char('u') => self.marked.clear(),
+            KeyCode::Char('y') => self.yank(ClipMode::Copy),
+            KeyCode::Char('x') => self.yank(ClipMode::Cut),
+            KeyCode::Char('p') => self.paste(),
+            KeyCode::Char('d') | KeyCode::Delete => {
+                let paths = self.targets();
+                if !paths.is_empty() {
+                    self.prompt = Some(Prompt::Trash(paths));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn sidebar_key(&mut self, code: KeyCode) {
+        let Some(index) = self.sidebar.selected() else {
+            return;
+        };
+        let row = self.rows[index].clone();
+        match code {
+            KeyCode::Char('j') | KeyCode::Down => {
+                self.sidebar
+                    .select(Some((index + 1).min(self.rows.len() - 1)));
+            }
+            KeyCode::Char('k') | KeyCode::Up => self.sidebar.select(Some(index.saturating_sub(1))),
+            KeyCode::Char('l') | KeyCode::Right => {
+                if let Some(node) = self.tree.find_mut(&row.path) {
+                    node.expand(&self.filter);
+                }
+                self.rebuild_rows(&row.path);
+            }
+            KeyCode::Char('h') | KeyCode::Left => {
+                if row.expanded && row.depth > 0 {
+                    if let Some(node) = self.tree.find_mut(&row.path) {
+                        node.expanded = false;
+                    }
+                    self.rebuild_rows(&row.path);
+                } else if let Some(parent) = row.path.parent().filter(|_| row.depth > 0) {
+                    self.rebuild_rows(parent);
+                }
+            }
+            KeyCode::Enter => {
+                self.change_dir(row.path, None);
+                self.focus = Focus::Files;
+            }
+            _ => {}
+        }
+    }
+
+    fn move_files(&mut self, delta: isize) {
+        if self.entries.is_empty() {
+            return;
+        }
+        let current = self.files.selected().unwrap_or(0) as isize;
+        let last = self.entries.len() as isize - 1;
+        self.files
+            .select(Some((current + delta).clamp(0, last) as usize));
+    }
+
+    /// 返回上级目录并选中刚离开的目录
+    fn go_parent(&mut self) {
+        if let Some(parent) = self.cwd.parent().map(Path::to_path_buf) {
+            let child = self.cwd.clone();
+            self.change_dir(parent, Some(child));
+        }
+    }
+
+    fn change_dir(&mut self, path: PathBuf, select: Option<PathBuf>) {
+        let previous = std::mem::replace(&mut self.cwd, path);
+        if let Err(error) = self.load(select.as_deref()) {
+            self.message = Some(error.to_string());
+            self.cwd = previous;
+            let _ = self.load(None);
+        }
+    }
+
+    /// 重新读取当前目录，尽量保持选中项不变
+    fn reload(&mut self) {
+        let selected = self.selected().map(|entry| entry.path.clone());
+        if let Err(error) = self.load(selected.as_deref()) {
+            self.message = Some(error.to_string());
+        }
+    }
+
+    /// 读取当前目录并选中 select (不存在时选中第一项)，同时在侧边栏中定位
+    fn load(&mut self, select: Option<&Path>) -> Result<()> {
+        let options = ListOptions {
+            sort: SortOptions {
+                dirs_first: true,
+                ..SortOptions::default()
+            },
+            filter: self.filter.clone(),
+            ..ListOptions::default()
+        };
+        self.entries = list(&self.cwd, &options)?.entries;
+        let index = select
+            .and_then(|path| self.entries.iter().position(|entry| entry.path == path))
+            .unwrap_or(0);
+        self.files
+            .select((!self.entries.is_empty()).then_some(index));
+
+        // 离开侧边栏的根时以当前目录为新的根
+        if !self.cwd.starts_with(&self.tree.path) {
+            self.tree = DirNode::new(self.cwd.clone());
+        }
+        self.tree.reveal(&self.cwd, &self.filter);
+        if let Some(node) = self.tree.find_mut(&self.cwd) {
+            node.expand(&self.filter);
+        }
+        let cwd = self.cwd.clone();
+        self.rebuild_rows(&cwd);
+        self.update_preview();
+        Ok(())
+    }
+
+    /// 重新展开侧边栏的行并选中 select
+    fn rebuild_rows(&mut self, select: &Path) {
+        self.rows.clear();
+        self.tree.flatten(0, &mut Vec::new(), true, &mut self.rows);
+        let index = self.rows.iter().position(|row| row.path == select);
+        self.sidebar.select(Some(index.unwrap_or(0)));
+    }
+
+    /// 已标记的项目，没有标记时为选中项
+    fn targets(&self) -> Vec<PathBuf> {
+        if self.marked.is_empty() {
+            self.selected()
+                .map(|entry| entry.path.clone())
+                .into_iter()
+                .collect()
+        } else {
+            self.marked.iter().cloned().collect()
+        }
+    }
+
+    fn yank(&mut self, mode: ClipMode) {
+        let paths = self.targets();
+        if paths.is_empty() {
+            return;
+        }
+        self.marked.clear();
+        self.clipboard = Some(Clipboard { mode, paths });
+    }
+
+    /// 将剪贴板中的项目复制或移动到当前目录，同名时使用 `name (1)` 等名称
+    fn paste(&mut self) {
+        let Some(clipboard) = self.clipboard.take() else {
+            self.message = Some("Nothing to paste".to_string());
+            return;
+        };
+        let mut actions = Vec::new();
+        let mut failures = Vec::new();
+        let mut done = 0;
+        for source in &clipboard.paths {
+            let Some(name) = source.file_name() else {
+                continue;
+            };
+            let result = match clipboard.mode {
+                ClipMode::Copy => copy_to(source, &unused_name(&self.cwd.join(name))),
+                ClipMode::Cut => move_path(
+                    source,
+                    &self.cwd.join(name),
+                    ConflictPolicy::Rename,
+                    &mut |_: &Entry, _| {},
+                )
+                .and_then(|outcome| match outcome {
+                    MoveOutcome::Renamed(to) | MoveOutcome::Copied(to) => {
+                        Action::moved(source, &to)
+                    }
+                    // ConflictPolicy::Rename 不会跳过
+                    MoveOutcome::Skipped => Err(io::Error::other("skipped")),
+                }),
+            };
+            match result {
+                Ok(action) => {
+                    done += 1;
+                    actions.push(action);
+                }
+                Err(error) => failures.push(format!("{}: {}", source.display(), error)),
+            }
+        }
+
+        let (verb, command) = match clipboard.mode {
+            ClipMode::Copy => ("Copied", "copy"),
+            ClipMode::Cut => ("Moved", "move"),
+        };
+        // 复制的项目可以再次粘贴，剪切的项目只能粘贴一次
+        if clipboard.mode == ClipMode::Copy {
+            self.clipboard = Some(clipboard);
+        }
+        self.record(command, actions);
+        self.reload();
+        self.report(verb, done, failures);
+    }
+
+    /// 将 paths 移入回收站
+    fn trash_paths(&mut self, paths: &[PathBuf]) {
+        let Some(trash) = &self.trash else {
+            self.message = Some("The trash is not available".to_string());
+            return;
+        };
+        let mut actions = Vec::new();
+        let mut failures = Vec::new();
+        for path in paths {
+            match trash.put(path).and_then(Action::trashed) {
+                Ok(action) => {
+                    self.marked.remove(path);
+                    actions.push(action);
+                }
+                Err(error) => failures.push(format!("{}: {}", path.display(), error)),
+            }
+        }
+        let done = actions.len();
+        self.record("rm", actions);
+        self.reload();
+        self.report("Moved to the trash:", done, failures);
+    }
+
+    fn record(&mut self, command: &str, actions: Vec<Action>) {
+        if let Some(journal) = &self.journal
+            && let Err(error) = journal.record(command, actions)
+        {
+            self.message = Some(format!("Could not record for undo: {}", error));
+        }
+    }
+
+    /// 在状态栏中报告操作结果，只显示第一个失败原因
+    fn report(&mut self, verb: &str, done: usize, failures: Vec<String>) {
+        let mut message = format!("{} {} item{}", verb, done, if done == 1 { "" } else { "s" });
+        if let Some(first) = failures.first() {
+            message.push_str(&format!(", {} failed ({})", failures.len(), first));
+        }
+        if self.message.is_none() {
+            self.message = Some(message);
+        }
+    }
+
+    /// 更新选中项的预览
+    fn update_preview(&mut self) {
+        let Some(entry) = self.selected() else {
+            self.preview = Preview::default();
+            return;
+        };
+        if self.preview.path == entry.path && !self.preview.lines.is_empty() {
+            return;
+        }
+        let path = entry.path.clone();
+        let lines = preview_lines(entry, &self.filter).unwrap_or_else(|e| vec![e.to_string()]);
+        self.preview = Preview { path, lines };
+    }
+}
+
+/// 预览内容：目录显示其中的名称，文本文件显示开头的几行
+fn preview_lines(entry: &Entry, filter: &FilterOptions) -> io::Result<Vec<String>> {
+    if entry.is_dir() {
+        let options = ListOptions {
+            sort: SortOptions {
+                dirs_first: true,
+                ..SortOptions::default()
+            },
+            filter: filter.clone(),
+            ..ListOptions::default()
+        };
+        let listing = list(&entry.path, &options).map_err(io::Error::other)?;
+        if listing.entries.is_empty() {
+            return Ok(vec!["(empty)".to_string()]);
+        }
+        return Ok(listing
+            .entries
+            .iter()
+            .take(PREVIEW_LINES)
+            .map(|child| {
+                let suffix = if child.is_dir() { "/" } else { "" };
+                format!("{}{}", child.name_lossy(), suffix)
+            })
+            .collect());
+    }
+    if entry.kind != EntryKind::File && entry.target_kind != Some(EntryKind::File) {
+        return Ok(vec![format!("{} file", entry.kind.as_str())]);
+    }
+
+    let mut buffer = Vec::new();
+    File::open(&entry.path)?
+        .take(PREVIEW_BYTES)
+        .read_to_end(&mut buffer)?;
+    if buffer.contains(&0) {
+        let size = std::fs::metadata(&entry.path)?.len();
+        return Ok(vec![format!(
+            "binary file ({})",
+            format_size(size, SizeStyle::Binary)
+        )]);
+    }
+    Ok(String::from_utf8_lossy(&buffer)
+        .lines()
+        .take(PREVIEW_LINES)
+        .map(|line| line.replace('\t', "    "))
+        .collect())
+}
+
+/// target 已存在时改用 `name (1)` 等未被占用的名称
+fn unused_name(target: &Path) -> PathBuf {
+    if std::fs::symlink_metadata(target).is_ok() {
+        free_name(target)
+    } else {
+        target.to_path_buf()
+    }
+}
+
+/// 复制 source 到 target，返回记录用的动作
+fn copy_to(source: &Path, target: &Path) -> io::Result<Action> {
+    let options = CopyOptions::default();
+    let plan = plan_copy(&[source], target, &options).map_err(io::Error::other)?;
+    let report = copy(&plan, &options, &mut |_: &Entry, _| {});
+    if let Some(failure) = report.errors.into_iter().next() {
+        return Err(failure.error);
+    }
+    Action::created(target)
+}
+
+/// 单个项目显示名称，多个项目显示数量
+fn describe_items(paths: &[PathBuf]) -> String {
+    match paths {
+        [path] => format!(
+            "'{}'",
+            path.file_name()
+                .unwrap_or(path.as_os_str())
+                .to_string_lossy()
+        ),
+        _ => format!("{} items", paths.len()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::crossterm::event::KeyEvent;
+    use std::fs;
+
+    fn press(app: &mut App, keys: &str) {
+        for c in keys.chars() {
+            app.handle_key(KeyEvent::from(KeyCode::Char(c)));
+        }
+    }
+
+    fn names(app: &App) -> Vec<String> {
+        app.entries
+            .iter()
+            .map(|e| e.name_lossy().into_owned())
+            .collect()
+    }
+
+    fn app_in(dir: &Path) -> App {
+        let mut app = App::new(dir, FilterOptions::default()).unwrap();
+        app.trash = Some(Trash::new(dir.join(".trash")));
+        app.journal = Some(Journal::new(dir.join(".journal.jsonl")));
+        app
+    }
+
+    #[test]
+    fn test_navigation_and_sidebar() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path().canonicalize().unwrap();
+        fs::create_dir_all(dir.join("b/inner")).unwrap();
+        fs::create_dir(dir.join("c")).unwrap();
+        fs::write(dir.join("a.txt"), "hello\nworld\n").unwrap();
+        fs::write(dir.join(".hidden"), "").unwrap();
+        let mut app = app_in(&dir);
+
+        // 目录在前，隐藏文件默认不显示
+        assert_eq!(names(&app), ["b", "c", "a.txt"]);
+        press(&mut app, "G");
+        assert_eq!(app.preview.lines, ["hello", "world"]);
+        press(&mut app, "gl");
+        assert_eq!(app.cwd, dir.join("b"));
+        assert_eq!(names(&app), ["inner"]);
+        assert_eq!(app.sidebar_rows().len(), 4);
+
+        // 返回上级时选中刚离开的目录
+        press(&mut app, "h");
+        assert_eq!(app.cwd, dir);
+        assert_eq!(app.selected().unwrap().name, "b");
+        press(&mut app, ".");
+        assert_eq!(names(&app), ["b", "c", ".hidden", "a.txt"]);
+
+        // 在侧边栏中折叠与打开目录
+        app.handle_key(KeyEvent::from(KeyCode::Tab));
+        assert_eq!(app.focus, Focus::Sidebar);
+        press(&mut app, "jl");
+        assert_eq!(app.sidebar_rows().len(), 4);
+        press(&mut app, "h");
+        assert_eq!(app.sidebar_rows().len(), 3);
+        app.handle_key(KeyEvent::from(KeyCode::Enter));
+        assert_eq!(app.cwd, dir.join("b"));
+        assert_eq!(app.focus, Focus::Files);
+        press(&mut app, "q");
+        assert!(app.quit);
+    }
+
+    #[test]
+    fn test_paste_and_trash() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path().canonicalize().unwrap();
+        fs::create_dir(dir.join("dest")).unwrap();
+        fs::write(dir.join("one.txt"), "1").unwrap();
+        fs::write(dir.join("two.txt"), "2").unwrap();
+        let mut app = app_in(&dir);
+
+        // 标记两个文件复制到 dest，再把 one.txt 移动过去
+        press(&mut app, "j  ");
+        assert_eq!(app.marked.len(), 2);
+        press(&mut app, "yglp");
+        assert!(app.marked.is_empty());
+        assert_eq!(names(&app), ["one.txt", "two.txt"]);
+        assert_eq!(app.message.as_deref(), Some("Copied 2 items"));
+        press(&mut app, "p");
+        assert_eq!(
+            names(&app),
+            ["one (1).txt", "one.txt", "two (1).txt", "two.txt"]
+        );
+
+        press(&mut app, "hjxklp");
+        assert_eq!(app.message.as_deref(), Some("Moved 1 item"));
+        assert!(app.clipboard.is_none());
+        assert!(!dir.join("one.txt").exists());
+
+        // 删除需要确认，取消时不做任何事
+        press(&mut app, "d");
+        assert!(app.status().starts_with("Move 'one (1).txt' to the trash?"));
+        press(&mut app, "n");
+        assert_eq!(app.entries.len(), 5);
+        press(&mut app, "dy");
+        assert_eq!(app.message.as_deref(), Some("Moved to the trash: 1 item"));
+        assert_eq!(app.entries.len(), 4);
+        assert_eq!(app.trash.as_ref().unwrap().list().unwrap().len(), 1);
+
+        let history = app.journal.as_ref().unwrap().history().unwrap();
+        let commands: Vec<_> = history.iter().map(|op| op.command.as_str()).collect();
+        assert_eq!(commands, ["copy", "copy", "move", "rm"]);
+    }
+}
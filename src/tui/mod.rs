@@ -0,0 +1,43 @@
+//! 交互式终端界面：ranger/nnn 风格的多窗格文件浏览器
+//!
+//! 状态与按键处理在 [`app`] 中，绘制在 [`ui`] 中，两者都不依赖真实终端，
+//! 测试使用 ratatui 的 `TestBackend` 驱动。
+
+mod app;
+mod ui;
+
+use app::App;
+use file_manage::core::filter::FilterOptions;
+use file_manage::{FmgError, Result};
+use ratatui::crossterm::event::{self, Event, KeyEventKind};
+use std::io::{self, IsTerminal};
+use std::path::Path;
+
+/// 在 path 目录中启动界面，直到用户退出
+pub fn run(path: &Path, filter: FilterOptions) -> Result<()> {
+    if !io::stdout().is_terminal() {
+        return Err(FmgError::Io {
+            path: None,
+            source: io::Error::other("the interactive interface requires a terminal"),
+        });
+    }
+    let mut app = App::new(path, filter)?;
+
+    let mut terminal = ratatui::try_init()?;
+    let result = event_loop(&mut terminal, &mut app);
+    ratatui::restore();
+    result.map_err(FmgError::from)
+}
+
+fn event_loop(terminal: &mut ratatui::DefaultTerminal, app: &mut App) -> io::Result<()> {
+    while !app.quit {
+        terminal.draw(|frame| ui::draw(frame, app))?;
+        // 只处理按下事件，Windows 上松开按键也会产生事件
+        if let Event::Key(key) = event::read()?
+            && key.kind == KeyEventKind::Press
+        {
+            app.handle_key(key);
+        }
+    }
+    Ok(())
+}
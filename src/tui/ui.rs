@@ -0,0 +1,258 @@
+//! TUI 的绘制：标题、侧边栏目录树、文件列表、预览与状态栏
+
+use super::app::{App, Focus};
+use file_manage::display::formatter::format_tree_prefix;
+use file_manage::display::styles::style_sheet;
+use ratatui::Frame;
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style, Stylize};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, List, ListItem, Paragraph};
+
+/// 绘制整个界面
+pub fn draw(frame: &mut Frame, app: &mut App) {
+    let [title, body, status] = Layout::vertical([
+        Constraint::Length(1),
+        Constraint::Min(0),
+        Constraint::Length(1),
+    ])
+    .areas(frame.area());
+
+    frame.render_widget(
+        Paragraph::new(app.cwd.display().to_string()).style(Style::new().bold().fg(Color::Blue)),
+        title,
+    );
+    let style = match (&app.prompt, &app.message) {
+        (Some(_), _) => Style::new().fg(Color::Yellow).bold(),
+        (None, Some(_)) => Style::new().fg(Color::Green),
+        (None, None) => Style::new().dim(),
+    };
+    frame.render_widget(Paragraph::new(app.status()).style(style), status);
+
+    if app.show_sidebar {
+        let [sidebar, files, preview] = Layout::horizontal([
+            Constraint::Percentage(25),
+            Constraint::Percentage(40),
+            Constraint::Percentage(35),
+        ])
+        .areas(body);
+        draw_sidebar(frame, app, sidebar);
+        draw_files(frame, app, files);
+        draw_preview(frame, app, preview);
+    } else {
+        let [files, preview] =
+            Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)])
+                .areas(body);
+        draw_files(frame, app, files);
+        draw_preview(frame, app, preview);
+    }
+}
+
+/// 带边框的窗格，拥有焦点时边框高亮
+fn pane(title: &str, focused: bool) -> Block<'_> {
+    let border = if focused {
+        Style::new().fg(Color::Cyan)
+    } else {
+        Style::new().dim()
+    };
+    Block::new()
+        .borders(Borders::ALL)
+        .border_style(border)
+        .title(title)
+}
+
+fn highlight(focused: bool) -> Style {
+    if focused {
+        Style::new().add_modifier(Modifier::REVERSED)
+    } else {
+        Style::new().add_modifier(Modifier::UNDERLINED)
+    }
+}
+
+fn draw_sidebar(frame: &mut Frame, app: &mut App, area: Rect) {
+    let items: Vec<_> = app
+        .sidebar_rows()
+        .iter()
+        .map(|row| {
+            let name = if row.depth == 0 {
+                row.path.display().to_string()
+            } else {
+                row.path
+                    .file_name()
+                    .unwrap_or_default()
+                    .to_string_lossy()
+                    .into_owned()
+            };
+            let prefix = if row.depth == 0 {
+                String::new()
+            } else {
+                format_tree_prefix(row.is_last, &row.ancestors, row.depth, &[])
+            };
+            let marker = if row.expanded { "▾ " } else { "▸ " };
+            let mut style = Style::new().fg(Color::Blue);
+            if row.path == app.cwd {
+                style = style.bold();
+            }
+            ListItem::new(Line::from(vec![
+                Span::styled(prefix, Style::new().dim()),
+                Span::raw(marker),
+                Span::styled(name, style),
+            ]))
+        })
+        .collect();
+    let focused = app.focus == Focus::Sidebar;
+    let list = List::new(items)
+        .block(pane("Tree", focused))
+        .highlight_style(highlight(focused));
+    frame.render_stateful_widget(list, area, &mut app.sidebar);
+}
+
+fn draw_files(frame: &mut Frame, app: &mut App, area: Rect) {
+    let sheet = style_sheet();
+    let items: Vec<_> = app
+        .entries
+        .iter()
+        .map(|entry| {
+            let marked = app.marked.contains(&entry.path);
+            let mark = if marked { "* " } else { "  " };
+            let mut name = entry.name_lossy().into_owned();
+            if entry.is_dir() {
+                name.push('/');
+            }
+            let style = sheet.style_for(entry).map(sgr_style).unwrap_or_default();
+            ListItem::new(Line::from(vec![
+                Span::styled(mark, Style::new().fg(Color::Yellow).bold()),
+                Span::styled(name, style),
+            ]))
+        })
+        .collect();
+    let focused = app.focus == Focus::Files;
+    let title = format!("Files ({})", app.entries.len());
+    let list = List::new(items)
+        .block(pane(&title, focused))
+        .highlight_style(highlight(focused));
+    frame.render_stateful_widget(list, area, &mut app.files);
+}
+
+fn draw_preview(frame: &mut Frame, app: &App, area: Rect) {
+    let lines: Vec<_> = app
+        .preview
+        .lines
+        .iter()
+        .map(|line| Line::raw(line.as_str()))
+        .collect();
+    frame.render_widget(Paragraph::new(lines).block(pane("Preview", false)), area);
+}
+
+/// 将 LS_COLORS 中的 SGR 参数 (如 `01;34`) 转换为终端界面的样式
+pub fn sgr_style(spec: &str) -> Style {
+    let codes: Vec<u8> = spec
+        .split(';')
+        .filter_map(|code| code.parse().ok())
+        .collect();
+    let mut style = Style::new();
+    let mut i = 0;
+    while i < codes.len() {
+        match codes[i] {
+            1 => style = style.add_modifier(Modifier::BOLD),
+            2 => style = style.add_modifier(Modifier::DIM),
+            3 => style = style.add_modifier(Modifier::ITALIC),
+            4 => style = style.add_modifier(Modifier::UNDERLINED),
+            5 => style = style.add_modifier(Modifier::SLOW_BLINK),
+            7 => style = style.add_modifier(Modifier::REVERSED),
+            code @ 30..=37 => style = style.fg(Color::Indexed(code - 30)),
+            code @ 40..=47 => style = style.bg(Color::Indexed(code - 40)),
+            code @ 90..=97 => style = style.fg(Color::Indexed(code - 90 + 8)),
+            code @ 100..=107 => style = style.bg(Color::Indexed(code - 100 + 8)),
+            code @ (38 | 48) => {
+                let color = match codes.get(i + 1) {
+                    Some(5) if i + 2 < codes.len() => {
+                        i += 2;
+                        Some(Color::Indexed(codes[i]))
+                    }
+                    Some(2) if i + 4 < codes.len() => {
+                        i += 4;
+                        Some(Color::Rgb(codes[i - 2], codes[i - 1], codes[i]))
+                    }
+                    _ => None,
+                };
+                if let Some(color) = color {
+                    style = if code == 38 {
+                        style.fg(color)
+                    } else {
+                        style.bg(color)
+                    };
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    style
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use file_manage::core::filter::FilterOptions;
+    use ratatui::Terminal;
+    use ratatui::backend::TestBackend;
+    use std::fs;
+
+    /// 将测试终端的缓冲区按行转换为文本
+    fn screen(terminal: &Terminal<TestBackend>) -> Vec<String> {
+        let buffer = terminal.backend().buffer();
+        (0..buffer.area.height)
+            .map(|y| {
+                (0..buffer.area.width)
+                    .map(|x| buffer[(x, y)].symbol())
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_draw() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        fs::create_dir_all(dir.join("docs")).unwrap();
+        fs::write(dir.join("notes.txt"), "first line\nsecond line\n").unwrap();
+        let mut app = App::new(dir, FilterOptions::default()).unwrap();
+        app.handle_key(ratatui::crossterm::event::KeyEvent::from(
+            ratatui::crossterm::event::KeyCode::Char('j'),
+        ));
+
+        let mut terminal = Terminal::new(TestBackend::new(100, 12)).unwrap();
+        terminal.draw(|frame| draw(frame, &mut app)).unwrap();
+        let text = screen(&terminal).join("\n");
+        assert!(text.contains("Files (2)"));
+        assert!(text.contains("docs/"));
+        assert!(text.contains("notes.txt"));
+        assert!(text.contains("first line"));
+        assert!(text.contains("└── ▸ docs"));
+
+        // 隐藏侧边栏后只剩文件与预览
+        app.handle_key(ratatui::crossterm::event::KeyEvent::from(
+            ratatui::crossterm::event::KeyCode::Char('t'),
+        ));
+        terminal.draw(|frame| draw(frame, &mut app)).unwrap();
+        let text = screen(&terminal).join("\n");
+        assert!(!text.contains("Tree"));
+        assert!(text.contains("second line"));
+    }
+
+    #[test]
+    fn test_sgr_style() {
+        assert_eq!(
+            sgr_style("01;34"),
+            Style::new()
+                .add_modifier(Modifier::BOLD)
+                .fg(Color::Indexed(4))
+        );
+        assert_eq!(
+            sgr_style("38;5;208;48;2;1;2;3"),
+            Style::new().fg(Color::Indexed(208)).bg(Color::Rgb(1, 2, 3))
+        );
+        assert_eq!(sgr_style("00"), Style::new());
+    }
+}
@@ -1,3 +1,6 @@
+#![allow(clippy::needless_borrows_for_generic_args)]
+
+use std::io::Write;
 use std::process::Command;
 
 #[test]
@@ -68,7 +71,15 @@ fn test_list_nonexistent_path() {
     println!("Stderr: {}", stderr);
 
     // Command should fail for nonexistent directory
-    // assert!(!cmd.status.success(), "Command should have failed for nonexistent directory");
+    assert!(
+        !cmd.status.success(),
+        "Command should have failed for nonexistent directory"
+    );
+    assert_eq!(
+        cmd.status.code(),
+        Some(2),
+        "Missing path is a serious error"
+    );
     assert!(
         stderr.contains("does not exist"),
         "Error message should mention that path does not exist"
@@ -166,6 +177,25 @@ fn test_ls_long_format_with_path() {
         "Command failed with stderr: {}",
         stderr
     );
+
+    // Long format should show real permission bits instead of a placeholder
+    #[cfg(unix)]
+    {
+        let cli_line = stdout
+            .lines()
+            .find(|line| line.contains("cli"))
+            .expect("Output should contain 'cli' directory");
+        assert!(
+            cli_line.starts_with("dr"),
+            "Directory line should start with real mode bits: {}",
+            cli_line
+        );
+        assert!(
+            !cli_line.starts_with("d---------"),
+            "Permissions should not be a placeholder: {}",
+            cli_line
+        );
+    }
 }
 
 #[test]
@@ -238,7 +268,6 @@ fn test_list_tree_depth_1() {
     // With depth 1, we shouldn't see contents of subdirectories
     // This is a rough check - in a real scenario, you might need to adjust based on your directory structure
     if stdout.contains("src") {
-        let src_line = stdout.lines().find(|line| line.contains("src")).unwrap();
         // If src is a directory, it should not have any children listed at depth 1
         // This is a heuristic and might need adjustment
         let next_line = stdout
@@ -307,6 +336,12 @@ fn test_list_tree_long_format() {
         "Command failed with stderr: {}",
         stderr
     );
+
+    // Tree entries should carry permission columns as well
+    assert!(
+        stdout.contains("── -r") || stdout.contains("── dr"),
+        "Tree long format should contain permission strings"
+    );
 }
 
 #[test]
@@ -342,3 +377,1022 @@ fn test_ls_tree() {
         "Output should contain 'Cargo.toml' file"
     );
 }
+
+/// A self-cleaning fixture directory holding `files` (path, contents); paths ending in `/` are directories
+///
+/// It lives under cargo's per-target temp dir, where `cargo run` still finds the manifest.
+fn fixture(files: &[(&str, &str)]) -> tempfile::TempDir {
+    let temp = tempfile::Builder::new()
+        .prefix("fixture-")
+        .tempdir_in(env!("CARGO_TARGET_TMPDIR"))
+        .expect("Failed to create fixture directory");
+    for (name, contents) in files {
+        let path = temp.path().join(name);
+        if name.ends_with('/') {
+            std::fs::create_dir_all(&path).unwrap();
+        } else {
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(&path, contents).unwrap();
+        }
+    }
+    temp
+}
+
+/// `fmg` run inside the fixture `dir`, keeping its trash and journal there too
+fn fmg(dir: &std::path::Path) -> Command {
+    let mut cmd = Command::new("cargo");
+    cmd.args(["run", "--"])
+        .current_dir(dir)
+        .env("XDG_DATA_HOME", dir.join(".data"))
+        .env("XDG_STATE_HOME", dir.join(".state"));
+    cmd
+}
+
+/// Position of the first line whose text ends with `name`
+fn line_index(stdout: &str, name: &str) -> usize {
+    stdout
+        .lines()
+        .position(|line| line.trim_end().ends_with(name))
+        .unwrap_or_else(|| panic!("Output should contain '{}'", name))
+}
+
+#[test]
+fn test_list_sort_version() {
+    let temp = fixture(&[("file10", ""), ("file2", ""), ("file1", "")]);
+    let dir = temp.path();
+
+    let cmd = fmg(dir)
+        .args(["list", "--sort", "version"])
+        .arg(dir)
+        .output()
+        .expect("Failed to execute command");
+
+    let stdout = String::from_utf8_lossy(&cmd.stdout);
+    assert!(cmd.status.success());
+    assert!(line_index(&stdout, "file1") < line_index(&stdout, "file2"));
+    assert!(line_index(&stdout, "file2") < line_index(&stdout, "file10"));
+}
+
+#[test]
+fn test_list_tree_sort_reverse_dirs_first() {
+    let temp = fixture(&[
+        ("b_dir/inner_a", ""),
+        ("b_dir/inner_b", ""),
+        ("a_file", ""),
+        ("c_file", ""),
+    ]);
+    let dir = temp.path();
+
+    let cmd = fmg(dir)
+        .args(["list", "--tree", "--reverse", "--dirs-first"])
+        .arg(dir)
+        .output()
+        .expect("Failed to execute command");
+
+    let stdout = String::from_utf8_lossy(&cmd.stdout);
+    assert!(cmd.status.success());
+    // Directories stay first, and every level is reversed
+    assert!(line_index(&stdout, "b_dir") < line_index(&stdout, "c_file"));
+    assert!(line_index(&stdout, "c_file") < line_index(&stdout, "a_file"));
+    assert!(line_index(&stdout, "inner_b") < line_index(&stdout, "inner_a"));
+}
+
+#[test]
+fn test_list_tree_json() {
+    let temp = fixture(&[("sub/inner.txt", "hello"), ("top.txt", "")]);
+    let dir = temp.path();
+
+    let cmd = fmg(dir)
+        .args(["list", "--tree", "--format", "json"])
+        .arg(dir)
+        .output()
+        .expect("Failed to execute command");
+
+    assert!(cmd.status.success());
+    let value: serde_json::Value =
+        serde_json::from_slice(&cmd.stdout).expect("Output should be valid JSON");
+    let entries = value.as_array().expect("Top level should be an array");
+    assert_eq!(entries.len(), 2);
+
+    let sub = entries.iter().find(|e| e["name"] == "sub").unwrap();
+    assert_eq!(sub["type"], "dir");
+    assert_eq!(sub["depth"], 0);
+    let children = sub["children"].as_array().unwrap();
+    assert_eq!(children[0]["name"], "inner.txt");
+    assert_eq!(children[0]["size"], 5);
+    assert_eq!(children[0]["depth"], 1);
+    // 时间以 UTC 的 RFC 3339 表示
+    assert!(children[0]["modified"].as_str().unwrap().ends_with('Z'));
+    if cfg!(unix) {
+        assert!(children[0]["changed"].as_str().unwrap().ends_with('Z'));
+    }
+}
+
+#[test]
+fn test_list_tree_ndjson() {
+    let temp = fixture(&[("sub/inner.txt", "")]);
+    let dir = temp.path();
+
+    let cmd = fmg(dir)
+        .args(["ls", "-t", "--format", "ndjson"])
+        .arg(dir)
+        .output()
+        .expect("Failed to execute command");
+
+    assert!(cmd.status.success());
+    let stdout = String::from_utf8_lossy(&cmd.stdout);
+    let lines: Vec<serde_json::Value> = stdout
+        .lines()
+        .map(|line| serde_json::from_str(line).expect("Each line should be a JSON object"))
+        .collect();
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[0]["name"], "sub");
+    assert_eq!(lines[1]["name"], "inner.txt");
+    assert!(lines[1].get("children").is_none());
+}
+
+#[test]
+fn test_list_not_a_directory() {
+    let cmd = Command::new("cargo")
+        .args(["run", "--", "list", "Cargo.toml"])
+        .output()
+        .expect("Failed to execute command");
+
+    let stderr = String::from_utf8_lossy(&cmd.stderr);
+    assert_eq!(cmd.status.code(), Some(2));
+    assert!(
+        stderr.contains("is not a directory"),
+        "Error message should mention that path is not a directory"
+    );
+}
+
+#[test]
+fn test_list_hidden_and_ignore() {
+    let temp = fixture(&[(".env", ""), ("app.rs", ""), ("app.o", "")]);
+    let dir = temp.path();
+
+    let run = |extra: &[&str]| {
+        let cmd = fmg(dir)
+            .arg("list")
+            .args(extra)
+            .arg(dir)
+            .output()
+            .expect("Failed to execute command");
+        assert!(cmd.status.success());
+        String::from_utf8_lossy(&cmd.stdout).into_owned()
+    };
+
+    let default = run(&[]);
+    assert!(!default.contains(".env"), "Dotfiles are hidden by default");
+    assert!(default.contains("app.o"));
+
+    let almost_all = run(&["-A", "--ignore", "*.o"]);
+    assert!(almost_all.contains(".env"));
+    assert!(!almost_all.contains("app.o"));
+    assert!(
+        !almost_all
+            .lines()
+            .any(|line| line.trim_end().ends_with(".."))
+    );
+
+    let all = run(&["-a"]);
+    assert!(all.lines().any(|line| line.trim_end().ends_with("..")));
+}
+
+#[test]
+fn test_list_tree_gitignore() {
+    let temp = fixture(&[
+        (".git/", ""),
+        ("target/debug/", ""),
+        (".gitignore", "/target\n"),
+        ("src/.gitignore", "*.tmp\n"),
+        ("src/main.rs", ""),
+        ("src/scratch.tmp", ""),
+    ]);
+    let dir = temp.path();
+
+    let cmd = fmg(dir)
+        .args(["list", "--tree", "-A", "--gitignore"])
+        .arg(dir)
+        .output()
+        .expect("Failed to execute command");
+
+    let stdout = String::from_utf8_lossy(&cmd.stdout);
+    assert!(cmd.status.success());
+    assert!(stdout.contains("main.rs"));
+    assert!(!stdout.contains("target"));
+    assert!(!stdout.contains("scratch.tmp"));
+    assert!(!stdout.contains(".git\n"));
+}
+
+#[test]
+fn test_list_du_human_sizes() {
+    let temp = fixture(&[("big/nested/", ""), ("small.txt", "hello")]);
+    let dir = temp.path();
+    std::fs::write(dir.join("big/nested/blob"), vec![0u8; 3 * 1024 * 1024]).unwrap();
+
+    let cmd = fmg(dir)
+        .args(["list", "--du", "-h"])
+        .arg(dir)
+        .output()
+        .expect("Failed to execute command");
+
+    let stdout = String::from_utf8_lossy(&cmd.stdout);
+    assert!(cmd.status.success());
+    let big = stdout.lines().find(|l| l.ends_with("big")).unwrap();
+    assert!(big.contains("3.0 MiB"), "unexpected line: {}", big);
+    let small = stdout.lines().find(|l| l.ends_with("small.txt")).unwrap();
+    assert!(small.contains("5 B"), "unexpected line: {}", small);
+    // 合计行位于列表之前
+    assert!(line_index(&stdout, "total 3.0 MiB") < line_index(&stdout, "small.txt"));
+
+    let cmd = fmg(dir)
+        .args(["list", "--tree", "--du", "--si"])
+        .arg(dir)
+        .output()
+        .expect("Failed to execute command");
+
+    let stdout = String::from_utf8_lossy(&cmd.stdout);
+    assert!(cmd.status.success());
+    // 目录自身的大小因文件系统而异，只检查单位
+    let total = stdout.lines().last().unwrap();
+    assert!(
+        total.starts_with("total 3.") && total.ends_with(" MB"),
+        "{}",
+        total
+    );
+
+    // 不带 --du 的长格式树没有合计行
+    let cmd = fmg(dir)
+        .args(["list", "--tree", "-l"])
+        .arg(dir)
+        .output()
+        .expect("Failed to execute command");
+    assert!(cmd.status.success());
+    assert!(!String::from_utf8_lossy(&cmd.stdout).contains("total "));
+}
+
+#[test]
+fn test_color_choice_and_logo() {
+    let run = |args: &[&str], env: &[(&str, &str)]| {
+        let mut cmd = Command::new("cargo");
+        cmd.args(["run", "--", "list", "src"])
+            .args(args)
+            .env_remove("NO_COLOR")
+            .env_remove("CLICOLOR_FORCE");
+        for (key, value) in env {
+            cmd.env(key, value);
+        }
+        let output = cmd.output().expect("Failed to execute command");
+        assert!(output.status.success());
+        String::from_utf8_lossy(&output.stdout).into_owned()
+    };
+
+    // 管道输出：无颜色、无 Logo
+    let piped = run(&[], &[]);
+    assert!(!piped.contains('\x1b'));
+    assert!(!piped.contains("File Manager CLI"));
+    assert_eq!(piped.lines().next(), Some("cli"));
+
+    let forced = run(&["--color", "always"], &[]);
+    assert!(forced.contains("\x1b["));
+    // Logo 仍然只在终端中显示
+    assert!(!forced.contains("File Manager CLI"));
+
+    assert!(run(&[], &[("CLICOLOR_FORCE", "1")]).contains("\x1b["));
+    let never = run(&["--color", "never"], &[("CLICOLOR_FORCE", "1")]);
+    assert!(!never.contains('\x1b'));
+}
+
+#[test]
+fn test_ls_colors() {
+    let temp = fixture(&[("sub/", ""), ("archive.tar", ""), ("notes", "")]);
+    let dir = temp.path();
+
+    let cmd = fmg(dir)
+        .args(["list", "--color", "always"])
+        .arg(dir)
+        .env("LS_COLORS", "di=01;34:*.tar=01;31")
+        .output()
+        .expect("Failed to execute command");
+
+    let stdout = String::from_utf8_lossy(&cmd.stdout);
+    assert!(cmd.status.success());
+    assert!(stdout.contains("\x1b[01;31marchive.tar\x1b[0m"));
+    assert!(stdout.contains("\x1b[01;34msub\x1b[0m"));
+    // 未配置的类型不着色
+    assert!(stdout.lines().any(|line| line == "notes"));
+}
+
+#[test]
+fn test_config_file_defaults_and_aliases() {
+    let temp = fixture(&[
+        ("files/a_small", "1"),
+        ("files/b_large", "1234567890"),
+        ("files/skip.o", ""),
+        (
+            "config.toml",
+            "[list]\nsort = \"size\"\nignore = [\"*.o\"]\n\
+             [aliases]\nbyname = \"list --sort name\"\n",
+        ),
+    ]);
+    let dir = temp.path();
+    let config = dir.join("config.toml");
+
+    let run = |args: &[&str], use_env: bool| {
+        let mut cmd = fmg(dir);
+        if use_env {
+            cmd.env("FMG_CONFIG", &config);
+        } else {
+            cmd.arg("--config").arg(&config);
+        }
+        let output = cmd
+            .args(args)
+            .arg(dir.join("files"))
+            .output()
+            .expect("Failed to execute command");
+        assert!(output.status.success());
+        String::from_utf8_lossy(&output.stdout).into_owned()
+    };
+
+    // 配置中的排序与忽略模式生效
+    let stdout = run(&["list"], false);
+    assert!(line_index(&stdout, "b_large") < line_index(&stdout, "a_small"));
+    assert!(!stdout.contains("skip.o"));
+
+    // 命令行参数优先于配置，别名可以通过 FMG_CONFIG 使用
+    let stdout = run(&["byname"], true);
+    assert!(line_index(&stdout, "a_small") < line_index(&stdout, "b_large"));
+    let stdout = run(&["list", "--sort", "name"], true);
+    assert!(line_index(&stdout, "a_small") < line_index(&stdout, "b_large"));
+}
+
+#[test]
+fn test_config_errors() {
+    let temp = fixture(&[("config.toml", "[list]\nsort = \"sideways\"\n")]);
+    let dir = temp.path();
+    let config = dir.join("config.toml");
+
+    for path in [config, dir.join("missing.toml")] {
+        let cmd = fmg(dir)
+            .arg("--config")
+            .arg(&path)
+            .arg("list")
+            .output()
+            .expect("Failed to execute command");
+        assert_eq!(cmd.status.code(), Some(2));
+        let stderr = String::from_utf8_lossy(&cmd.stderr);
+        assert!(stderr.contains(&*path.to_string_lossy()), "{}", stderr);
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn test_list_symlinks_and_loops() {
+    let temp = fixture(&[("pkg/node_modules/", ""), ("pkg/index.js", "")]);
+    let dir = temp.path();
+    std::os::unix::fs::symlink("..", dir.join("pkg/node_modules/pkg")).unwrap();
+    std::os::unix::fs::symlink("gone", dir.join("dangling")).unwrap();
+
+    let run = |args: &[&str]| {
+        fmg(dir)
+            .args(["list", "--tree"])
+            .args(args)
+            .arg(dir)
+            .output()
+            .expect("Failed to execute command")
+    };
+
+    let cmd = run(&[]);
+    let stdout = String::from_utf8_lossy(&cmd.stdout);
+    assert!(cmd.status.success());
+    assert!(stdout.contains("pkg -> .."));
+    assert!(stdout.contains("dangling -> gone [broken]"));
+
+    // 跟随链接时循环只报告一次，不会无限展开
+    let cmd = run(&["-L"]);
+    let stdout = String::from_utf8_lossy(&cmd.stdout);
+    let stderr = String::from_utf8_lossy(&cmd.stderr);
+    assert_eq!(cmd.status.code(), Some(1));
+    assert_eq!(stdout.matches("index.js").count(), 1);
+    assert!(stderr.contains("directory loop detected"));
+}
+
+#[test]
+fn test_list_quoting_style() {
+    let temp = fixture(&[("two words", ""), ("it's", "")]);
+    let dir = temp.path();
+
+    let run = |style: &str| {
+        let cmd = fmg(dir)
+            .args(["list", "--quoting-style", style])
+            .arg(dir)
+            .output()
+            .expect("Failed to execute command");
+        assert!(cmd.status.success());
+        String::from_utf8_lossy(&cmd.stdout).into_owned()
+    };
+
+    assert!(run("literal").lines().any(|line| line == "two words"));
+    assert!(run("escape").lines().any(|line| line == "two\\ words"));
+    assert!(run("c").lines().any(|line| line == "\"two words\""));
+    assert!(run("shell").lines().any(|line| line == "'it'\\''s'"));
+}
+
+#[test]
+fn test_list_tree_stats() {
+    let temp = fixture(&[("a/b/c.txt", "")]);
+    let dir = temp.path();
+
+    let cmd = fmg(dir)
+        .args(["list", "--tree", "--stats"])
+        .arg(dir)
+        .output()
+        .expect("Failed to execute command");
+
+    let stderr = String::from_utf8_lossy(&cmd.stderr);
+    assert!(cmd.status.success());
+    assert!(stderr.contains("3 entries in"), "{}", stderr);
+    assert!(stderr.contains("entries/s)"));
+}
+
+#[test]
+fn test_find_filters_and_output() {
+    let temp = fixture(&[
+        ("src/core/lib.rs", &"x".repeat(2048)),
+        ("src/main.rs", ""),
+        ("docs/guide.md", ""),
+    ]);
+    let dir = temp.path();
+
+    let run = |args: &[&str]| {
+        let cmd = fmg(dir)
+            .arg("find")
+            .arg(dir)
+            .args(args)
+            .output()
+            .expect("Failed to execute command");
+        assert!(
+            cmd.status.success(),
+            "{}",
+            String::from_utf8_lossy(&cmd.stderr)
+        );
+        cmd.stdout
+    };
+
+    let stdout = String::from_utf8(run(&["--name", "*.rs"])).unwrap();
+    let names: Vec<_> = stdout.lines().collect();
+    assert_eq!(names.len(), 2);
+    assert!(names[0].ends_with("lib.rs") && names[1].ends_with("main.rs"));
+
+    let stdout =
+        String::from_utf8(run(&["--type", "f", "--size", "+1k", "--mtime", "-1d"])).unwrap();
+    assert_eq!(stdout.lines().count(), 1);
+    assert!(stdout.trim_end().ends_with("lib.rs"));
+
+    let stdout = String::from_utf8(run(&["--type", "d", "--max-depth", "1"])).unwrap();
+    assert_eq!(stdout.lines().count(), 2);
+
+    // 树形输出只保留匹配项与其祖先目录
+    let stdout = String::from_utf8(run(&["--regex", "^lib", "--tree"])).unwrap();
+    assert!(line_index(&stdout, "src") < line_index(&stdout, "core"));
+    assert!(line_index(&stdout, "core") < line_index(&stdout, "lib.rs"));
+    assert!(!stdout.contains("docs") && !stdout.contains("main.rs"));
+
+    let stdout = run(&["--name", "*.md", "-0"]);
+    assert!(stdout.ends_with(b"guide.md\0"));
+    assert_eq!(stdout.iter().filter(|&&b| b == 0).count(), 1);
+}
+
+#[test]
+fn test_grep_context_and_highlight() {
+    let temp = fixture(&[
+        ("logs/app.log", "boot\nWARN low disk\nok\n"),
+        ("target/out.log", "WARN ignored\n"),
+        ("blob.bin", "\0WARN"),
+    ]);
+    let dir = temp.path();
+
+    let run = |args: &[&str]| {
+        let cmd = fmg(dir)
+            .arg("grep")
+            .args(args)
+            .arg(dir)
+            .args(["-I", "target"])
+            .output()
+            .expect("Failed to execute command");
+        assert!(
+            cmd.status.success(),
+            "{}",
+            String::from_utf8_lossy(&cmd.stderr)
+        );
+        String::from_utf8_lossy(&cmd.stdout).into_owned()
+    };
+
+    let stdout = run(&["-i", "warn", "-C", "1"]);
+    let lines: Vec<_> = stdout.lines().collect();
+    assert_eq!(lines.len(), 4, "{}", stdout);
+    assert!(lines[0].starts_with("Binary file") && lines[0].ends_with("blob.bin matches"));
+    assert!(lines[1].ends_with("app.log-1-boot"));
+    assert!(lines[2].ends_with("app.log:2:WARN low disk"));
+    assert!(lines[3].ends_with("app.log-3-ok"));
+
+    let stdout = run(&["-c", "disk"]);
+    assert!(stdout.trim_end().ends_with("app.log:1"));
+
+    // 强制着色时匹配部分被高亮
+    let stdout = run(&["--color", "always", "--name", "*.log", "low"]);
+    assert!(stdout.contains("\x1b[1;31mlow\x1b[0m"), "{:?}", stdout);
+}
+
+#[test]
+fn test_copy_dry_run_and_resume() {
+    let temp = fixture(&[("src/nested/data.txt", "0123456789"), ("src/.env", "KEY=1")]);
+    let dir = temp.path();
+
+    let run = |args: &[&str]| {
+        fmg(dir)
+            .arg("copy")
+            .args(args)
+            .output()
+            .expect("Failed to execute command")
+    };
+
+    // 预演只输出树形计划，不创建任何文件
+    let cmd = run(&["src", "dest", "--dry-run"]);
+    let stdout = String::from_utf8_lossy(&cmd.stdout);
+    assert!(cmd.status.success());
+    assert!(stdout.starts_with("src -> dest"));
+    assert!(line_index(&stdout, "nested") < line_index(&stdout, "data.txt"));
+    assert!(stdout.contains("Would copy 2 files (15 B)"));
+    assert!(!dir.join("dest").exists());
+
+    // 已复制一部分的文件从断点继续 (dest 已存在，因此复制到 dest/src)
+    std::fs::create_dir_all(dir.join("dest/src/nested")).unwrap();
+    std::fs::write(dir.join("dest/src/nested/data.txt"), "01234").unwrap();
+    let cmd = run(&["src", "dest", "--resume", "--verify"]);
+    let stdout = String::from_utf8_lossy(&cmd.stdout);
+    assert!(
+        cmd.status.success(),
+        "{}",
+        String::from_utf8_lossy(&cmd.stderr)
+    );
+    assert!(stdout.contains("Copied 2 files (10 B), 5 B already present"));
+    assert_eq!(
+        std::fs::read_to_string(dir.join("dest/src/nested/data.txt")).unwrap(),
+        "0123456789"
+    );
+
+    // 目标已存在且未指定 --overwrite 时报告失败
+    let cmd = run(&["src/.env", "dest/src/.env"]);
+    let stderr = String::from_utf8_lossy(&cmd.stderr);
+    assert_eq!(cmd.status.code(), Some(1));
+    assert!(stderr.contains("destination already exists"));
+}
+
+#[test]
+fn test_move_conflicts() {
+    let temp = fixture(&[("app.log", "new"), ("archive/app.log", "old")]);
+    let dir = temp.path();
+
+    let run = |args: &[&str]| {
+        fmg(dir)
+            .arg("move")
+            .args(args)
+            .output()
+            .expect("Failed to execute command")
+    };
+
+    let cmd = run(&["app.log", "archive"]);
+    assert_eq!(cmd.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&cmd.stderr).contains("destination already exists"));
+
+    let cmd = run(&["app.log", "archive", "--skip"]);
+    assert!(cmd.status.success());
+    assert!(String::from_utf8_lossy(&cmd.stdout).contains("Moved 0 items, skipped 1"));
+    assert!(dir.join("app.log").exists());
+
+    // --rename 与 --overwrite 不能同时使用
+    let cmd = run(&["app.log", "archive", "--rename", "--overwrite"]);
+    assert_eq!(cmd.status.code(), Some(2));
+
+    let cmd = run(&["app.log", "archive", "--rename"]);
+    assert!(cmd.status.success());
+    assert!(!dir.join("app.log").exists());
+    assert_eq!(
+        std::fs::read_to_string(dir.join("archive/app (1).log")).unwrap(),
+        "new"
+    );
+    assert_eq!(
+        std::fs::read_to_string(dir.join("archive/app.log")).unwrap(),
+        "old"
+    );
+}
+
+#[test]
+fn test_rm_and_trash() {
+    let temp = fixture(&[("work/old.log", "v1")]);
+    let dir = temp.path();
+
+    let run = |args: &[&str], stdin: &str| {
+        let mut child = fmg(dir)
+            .args(args)
+            .current_dir(dir.join("work"))
+            .stdin(std::process::Stdio::piped())
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped())
+            .spawn()
+            .expect("Failed to execute command");
+        child
+            .stdin
+            .take()
+            .unwrap()
+            .write_all(stdin.as_bytes())
+            .unwrap();
+        child.wait_with_output().unwrap()
+    };
+
+    let cmd = run(&["rm", "old.log"], "");
+    assert!(cmd.status.success());
+    assert!(!dir.join("work/old.log").exists());
+    assert!(dir.join(".data/Trash/files/old.log").exists());
+    let info = std::fs::read_to_string(dir.join(".data/Trash/info/old.log.trashinfo")).unwrap();
+    assert!(info.contains("/work/old.log\nDeletionDate="));
+
+    let cmd = run(&["trash", "list"], "");
+    assert!(String::from_utf8_lossy(&cmd.stdout).contains("/work/old.log"));
+
+    // 原位置被占用时恢复失败，--rename 时恢复到新名称
+    std::fs::write(dir.join("work/old.log"), "v2").unwrap();
+    let cmd = run(&["trash", "restore", "old.log"], "");
+    assert_eq!(cmd.status.code(), Some(1));
+    let cmd = run(&["trash", "restore", "old.log", "--rename"], "");
+    assert!(cmd.status.success());
+    assert_eq!(
+        std::fs::read_to_string(dir.join("work/old (1).log")).unwrap(),
+        "v1"
+    );
+
+    // 永久删除需要确认
+    let cmd = run(&["rm", "--permanent", "old.log"], "n\n");
+    assert!(cmd.status.success());
+    assert!(dir.join("work/old.log").exists());
+    let cmd = run(&["rm", "--permanent", "old.log"], "y\n");
+    assert!(String::from_utf8_lossy(&cmd.stdout).contains("Deleted 1 item"));
+    assert!(!dir.join("work/old.log").exists());
+
+    run(&["rm", "old (1).log"], "");
+    let cmd = run(&["trash", "empty", "-y"], "");
+    assert!(cmd.status.success());
+    assert!(!dir.join(".data/Trash/files/old (1).log").exists());
+    let cmd = run(&["trash", "list"], "");
+    assert!(cmd.stdout.is_empty());
+}
+
+#[test]
+fn test_undo_and_history() {
+    let temp = fixture(&[("work/a.txt", "a")]);
+    let dir = temp.path();
+
+    let run = |args: &[&str]| {
+        fmg(dir)
+            .args(args)
+            .current_dir(dir.join("work"))
+            .output()
+            .expect("Failed to execute command")
+    };
+
+    assert!(run(&["copy", "a.txt", "b.txt"]).status.success());
+    assert!(run(&["move", "a.txt", "c.txt"]).status.success());
+    assert!(run(&["rm", "c.txt"]).status.success());
+
+    let cmd = run(&["history"]);
+    let stdout = String::from_utf8_lossy(&cmd.stdout);
+    assert!(stdout.contains("#1    "));
+    assert!(stdout.contains("a.txt -> "));
+    assert!(stdout.contains("trashed "));
+
+    // 依次撤销删除与移动
+    let cmd = run(&["undo", "2"]);
+    assert!(cmd.status.success());
+    let stdout = String::from_utf8_lossy(&cmd.stdout);
+    assert!(stdout.contains("Undid #3 rm") && stdout.contains("Undid #2 move"));
+    assert_eq!(
+        std::fs::read_to_string(dir.join("work/a.txt")).unwrap(),
+        "a"
+    );
+    assert!(!dir.join("work/c.txt").exists());
+
+    // 复制的目标在之后被修改，拒绝撤销且不做任何修改
+    std::fs::write(dir.join("work/b.txt"), "edited").unwrap();
+    let cmd = run(&["undo"]);
+    assert_eq!(cmd.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&cmd.stderr).contains("has changed since"));
+    assert!(dir.join("work/b.txt").exists());
+
+    let cmd = run(&["history"]);
+    assert!(String::from_utf8_lossy(&cmd.stdout).contains("rm (undone)"));
+}
+
+#[test]
+fn test_undo_after_trash_empty() {
+    let temp = fixture(&[("work/a.txt", "a"), ("work/b.txt", "b")]);
+    let dir = temp.path();
+
+    let run = |args: &[&str]| {
+        fmg(dir)
+            .args(args)
+            .current_dir(dir.join("work"))
+            .output()
+            .expect("Failed to execute command")
+    };
+
+    assert!(run(&["rm", "b.txt"]).status.success());
+    assert!(run(&["move", "a.txt", "c.txt"]).status.success());
+    assert!(run(&["trash", "empty", "--yes"]).status.success());
+    let cmd = run(&["history"]);
+    assert!(String::from_utf8_lossy(&cmd.stdout).contains("trash empty (cannot be undone)"));
+
+    // 清空回收站无法撤销，撤销时跳过它，撤销更早的移动
+    let cmd = run(&["undo"]);
+    assert!(cmd.status.success());
+    assert!(String::from_utf8_lossy(&cmd.stdout).contains("Undid #2 move"));
+    assert!(dir.join("work/a.txt").exists() && !dir.join("work/c.txt").exists());
+}
+
+#[test]
+fn test_undo_copy_into_existing_directory() {
+    let temp = fixture(&[("src/new.txt", "new"), ("dest/src/keep.txt", "keep")]);
+    let dir = temp.path();
+
+    let run = |args: &[&str]| {
+        fmg(dir)
+            .args(args)
+            .output()
+            .expect("Failed to execute command")
+    };
+
+    // 合并到已有目录时只记录写入的文件，撤销后已有的内容保持不变
+    assert!(run(&["copy", "src", "dest"]).status.success());
+    let cmd = run(&["history"]);
+    let stdout = String::from_utf8_lossy(&cmd.stdout);
+    assert!(stdout.contains("created ") && !stdout.contains("replaced "));
+    assert!(run(&["undo"]).status.success());
+    assert!(!dir.join("dest/src/new.txt").exists());
+    assert_eq!(
+        std::fs::read_to_string(dir.join("dest/src/keep.txt")).unwrap(),
+        "keep"
+    );
+}
+
+#[test]
+fn test_undo_overwrite() {
+    let temp = fixture(&[("new.txt", "new"), ("old.txt", "old")]);
+    let dir = temp.path();
+
+    let run = |args: &[&str]| {
+        fmg(dir)
+            .args(args)
+            .output()
+            .expect("Failed to execute command")
+    };
+    let read = |name: &str| std::fs::read_to_string(dir.join(name)).unwrap();
+
+    // 被覆盖的目标备份到回收站，撤销时放回原处
+    assert!(run(&["copy", "-f", "new.txt", "old.txt"]).status.success());
+    assert_eq!(read("old.txt"), "new");
+    assert!(run(&["undo"]).status.success());
+    assert_eq!(read("old.txt"), "old");
+
+    assert!(run(&["move", "-f", "new.txt", "old.txt"]).status.success());
+    assert_eq!(read("old.txt"), "new");
+    assert!(run(&["undo"]).status.success());
+    assert_eq!(
+        (read("new.txt"), read("old.txt")),
+        ("new".into(), "old".into())
+    );
+
+    // 从回收站恢复并覆盖同名文件
+    assert!(run(&["rm", "old.txt"]).status.success());
+    std::fs::write(dir.join("old.txt"), "other").unwrap();
+    assert!(run(&["trash", "restore", "-f", "old.txt"]).status.success());
+    assert_eq!(read("old.txt"), "old");
+    assert!(run(&["undo"]).status.success());
+    assert_eq!(read("old.txt"), "other");
+}
+
+#[test]
+fn test_rename_preview_and_apply() {
+    let temp = fixture(&[
+        ("IMG_2.JPG", "IMG_2.JPG"),
+        ("IMG_10.JPG", "IMG_10.JPG"),
+        ("notes.txt", "notes.txt"),
+    ]);
+    let dir = temp.path();
+
+    let run = |args: &[&str]| {
+        fmg(dir)
+            .arg("rename")
+            .args(args)
+            .output()
+            .expect("Failed to execute command")
+    };
+
+    // 预览不修改任何文件，未匹配的名称不出现在预览中
+    let cmd = run(&["-e", r"s/IMG_(\d+)/photo-$1/", "--case", "lower", ".", "-n"]);
+    assert!(cmd.status.success());
+    let stdout = String::from_utf8_lossy(&cmd.stdout);
+    assert!(stdout.contains("IMG_2.JPG  -> photo-2.jpg"));
+    assert!(stdout.contains("IMG_10.JPG -> photo-10.jpg"));
+    assert!(!stdout.contains("notes"));
+    assert!(dir.join("IMG_2.JPG").exists());
+
+    // 两项得到相同的名称时拒绝执行
+    let cmd = run(&["-t", "same{ext}", "IMG_2.JPG", "IMG_10.JPG", "-y"]);
+    assert_eq!(cmd.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&cmd.stderr).contains("would also be renamed"));
+
+    // 计数器按自然顺序编号
+    let cmd = run(&["-t", "{n:02}-{name}{ext}", "IMG_10.JPG", "IMG_2.JPG", "-y"]);
+    assert!(cmd.status.success());
+    assert!(String::from_utf8_lossy(&cmd.stdout).contains("Renamed 2 items"));
+    assert_eq!(
+        std::fs::read_to_string(dir.join("01-IMG_10.JPG")).unwrap(),
+        "IMG_10.JPG"
+    );
+    assert!(dir.join("02-IMG_2.JPG").exists());
+}
+
+#[cfg(unix)]
+#[test]
+fn test_rename_in_editor() {
+    let temp = fixture(&[("a", "a"), ("b", "b")]);
+    let dir = temp.path();
+
+    // 编辑器交换两个名称，形成需要临时名称的环
+    let cmd = fmg(dir)
+        .args(["rename", "--editor", ".", "-y"])
+        .env_remove("VISUAL")
+        .env("EDITOR", "perl -pi -e tr/ab/ba/")
+        .output()
+        .expect("Failed to execute command");
+    assert!(cmd.status.success());
+    assert!(String::from_utf8_lossy(&cmd.stdout).contains("1 cycle"));
+    assert_eq!(std::fs::read_to_string(dir.join("a")).unwrap(), "b");
+    assert_eq!(std::fs::read_to_string(dir.join("b")).unwrap(), "a");
+}
+
+#[test]
+fn test_rename_in_editor_empty_command() {
+    let temp = fixture(&[("a", "a")]);
+    let dir = temp.path();
+    let tmp = fixture(&[]);
+
+    // 分词后为空的编辑器命令报错而不是崩溃；空的 VISUAL 视为未设置
+    let cmd = fmg(dir)
+        .args(["rename", "--editor", ".", "-y"])
+        .env("VISUAL", "")
+        .env("EDITOR", "#x")
+        .env("TMPDIR", tmp.path())
+        .output()
+        .expect("Failed to execute command");
+    assert_eq!(cmd.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&cmd.stderr).contains("editor command is empty"));
+    assert!(dir.join("a").exists());
+    // 出错时也删除了临时文件
+    assert_eq!(std::fs::read_dir(tmp.path()).unwrap().count(), 0);
+}
+
+#[test]
+fn test_tui_requires_terminal() {
+    // 没有子命令时进入 TUI，输出不是终端时报错而不是破坏管道
+    let output = Command::new("cargo")
+        .args(["run", "--"])
+        .output()
+        .expect("Failed to execute command");
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("requires a terminal"));
+}
+
+#[test]
+fn test_stat() {
+    let temp = fixture(&[("notes.txt", "hello\n")]);
+    let dir = temp.path();
+    #[cfg(unix)]
+    {
+        std::os::unix::fs::symlink("notes.txt", dir.join("link")).unwrap();
+        // 文件系统不支持扩展属性时跳过这部分检查
+        let _ = xattr::set(dir.join("notes.txt"), "user.comment", b"draft");
+    }
+    let run = |args: &[&str]| {
+        fmg(dir)
+            .arg("stat")
+            .args(args)
+            .output()
+            .expect("Failed to execute command")
+    };
+
+    let output = run(&["notes.txt"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Size: 6"));
+    assert!(stdout.contains("regular file"));
+    assert!(stdout.contains("MIME: text/plain"));
+    // 时间精确到纳秒并带时区
+    let modify = stdout.lines().find(|l| l.starts_with("Modify:")).unwrap();
+    let time =
+        regex::Regex::new(r"^Modify: \d{4}-\d\d-\d\d \d\d:\d\d:\d\d\.\d{9} [+-]\d{4}$").unwrap();
+    assert!(time.is_match(modify), "{}", modify);
+    #[cfg(unix)]
+    if xattr::get(dir.join("notes.txt"), "user.comment").is_ok_and(|v| v.is_some()) {
+        assert!(stdout.contains(r#"Xattr: user.comment="draft""#));
+    }
+
+    #[cfg(unix)]
+    {
+        let output = run(&["link", "--format", "json"]);
+        let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        assert_eq!(json[0]["type"], "symlink");
+        assert_eq!(json[0]["symlink_target"], "notes.txt");
+        assert_eq!(json[0]["mime"], "inode/symlink");
+        let output = run(&["-L", "link", "--format", "json"]);
+        let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        assert_eq!(json[0]["type"], "file");
+        assert_eq!(json[0]["size"], 6);
+    }
+
+    // 无法读取的路径不影响其余路径
+    let output = run(&["missing", "notes.txt"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stdout).contains("File: notes.txt"));
+}
+
+#[test]
+fn test_list_time_style() {
+    let temp = fixture(&[("apollo", ""), ("recent", "")]);
+    let dir = temp.path();
+    // 早于 1970 年的修改时间
+    let landing = std::time::UNIX_EPOCH - std::time::Duration::from_secs(14_182_940);
+    std::fs::File::options()
+        .write(true)
+        .open(dir.join("apollo"))
+        .and_then(|file| file.set_modified(landing))
+        .unwrap();
+    let run = |args: &[&str]| {
+        let output = fmg(dir)
+            .args(["list", "-l"])
+            .args(args)
+            .env("TZ", "UTC")
+            .output()
+            .expect("Failed to execute command");
+        assert!(output.status.success());
+        String::from_utf8(output.stdout).unwrap()
+    };
+
+    let stdout = run(&[]);
+    assert!(stdout.contains("1969-07-20 20:17 apollo"), "{}", stdout);
+    assert!(!stdout.contains("???"));
+    let stdout = run(&["--time-style", "full"]);
+    assert!(stdout.contains("1969-07-20 20:17:40.000000000 +0000 apollo"));
+    let stdout = run(&["--time-style", "+%s"]);
+    assert!(stdout.contains("-14182940 apollo"));
+    let stdout = run(&["--time-style", "relative"]);
+    assert!(stdout.contains("years ago"));
+    assert!(stdout.contains("just now"));
+    // 访问时间不受 set_modified 影响
+    let stdout = run(&["--time", "atime", "--time-style", "iso"]);
+    assert!(!stdout.contains("1969"));
+}
+
+#[test]
+fn test_list_columns() {
+    let temp = fixture(&[
+        ("alpha", ""),
+        ("beta", ""),
+        ("gamma", ""),
+        ("报告.txt", ""),
+        ("数据", ""),
+    ]);
+    let dir = temp.path();
+    let run = |args: &[&str]| {
+        let output = fmg(dir)
+            .arg("list")
+            .args(args)
+            .env("COLUMNS", "30")
+            .output()
+            .expect("Failed to execute command");
+        assert!(output.status.success());
+        String::from_utf8(output.stdout).unwrap()
+    };
+
+    // 输出被重定向时默认每行一项
+    assert_eq!(run(&[]).lines().count(), 5);
+    let stdout = run(&["-C"]);
+    assert_eq!(stdout, "alpha  gamma     数据\nbeta   报告.txt\n");
+    let stdout = run(&["-x"]);
+    assert_eq!(stdout, "alpha     beta  gamma\n报告.txt  数据\n");
+    // 后给出的选项优先
+    assert_eq!(run(&["-C", "-1"]).lines().count(), 5);
+    // 长格式不受影响
+    assert_eq!(run(&["-C", "-l"]).lines().count(), 6);
+}